@@ -0,0 +1,455 @@
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    links::add_annotation,
+    metadata::{add_description, escape_xml, update_xmp},
+    outline::text_string,
+    output::{catalog_mut, page_id},
+    utils::mm_to_pt,
+    Error, Location, Result,
+};
+
+/// How an attachment relates to the content of the document.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relationship {
+    /// The original content the document was created from.
+    Source,
+
+    /// Data the content of the document is based on, like the XML of an e-invoice.
+    Data,
+
+    /// Another representation of the content of the document, like an XML invoice that is also
+    /// legally binding.
+    Alternative,
+
+    Supplement,
+
+    #[default]
+    Unspecified,
+}
+
+impl Relationship {
+    fn name(self) -> &'static [u8] {
+        match self {
+            Relationship::Source => b"Source",
+            Relationship::Data => b"Data",
+            Relationship::Alternative => b"Alternative",
+            Relationship::Supplement => b"Supplement",
+            Relationship::Unspecified => b"Unspecified",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attachment {
+    pub file_name: String,
+    pub description: Option<String>,
+
+    /// Like `text/xml`.
+    pub mime_type: String,
+
+    pub relationship: Relationship,
+    pub data: Vec<u8>,
+}
+
+/// The XMP properties that identify a Factur-X or ZUGFeRD invoice. The XML invoice has to be
+/// attached with the same file name.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FacturX {
+    pub document_file_name: String,
+
+    /// `INVOICE` for invoices.
+    pub document_type: String,
+
+    pub version: String,
+
+    /// The profile of the invoice, like `MINIMUM`, `BASIC`, `EN 16931` or `EXTENDED`.
+    pub conformance_level: String,
+}
+
+const FACTUR_X_NAMESPACE: &str = "urn:factur-x:pdfa:CrossIndustryDocument:invoice:1p0#";
+
+impl FacturX {
+    /// An invoice in `factur-x.xml` with the profile.
+    pub fn invoice(conformance_level: &str) -> Self {
+        FacturX {
+            document_file_name: "factur-x.xml".to_string(),
+            document_type: "INVOICE".to_string(),
+            version: "1.0".to_string(),
+            conformance_level: conformance_level.to_string(),
+        }
+    }
+
+    /// The properties together with the extension schema PDF/A needs to allow them.
+    fn xmp(&self) -> String {
+        let mut xmp = String::new();
+
+        xmp += concat!(
+            "<rdf:Description rdf:about=\"\"",
+            " xmlns:pdfaExtension=\"http://www.aiim.org/pdfa/ns/extension/\"",
+            " xmlns:pdfaSchema=\"http://www.aiim.org/pdfa/ns/schema#\"",
+            " xmlns:pdfaProperty=\"http://www.aiim.org/pdfa/ns/property#\">",
+            "<pdfaExtension:schemas><rdf:Bag><rdf:li rdf:parseType=\"Resource\">",
+            "<pdfaSchema:schema>Factur-X PDFA Extension Schema</pdfaSchema:schema>",
+        );
+        xmp += &format!(
+            "<pdfaSchema:namespaceURI>{}</pdfaSchema:namespaceURI>",
+            FACTUR_X_NAMESPACE
+        );
+        xmp += "<pdfaSchema:prefix>fx</pdfaSchema:prefix>";
+        xmp += "<pdfaSchema:property><rdf:Seq>";
+
+        for (name, description) in [
+            ("DocumentFileName", "The name of the embedded XML invoice"),
+            ("DocumentType", "INVOICE"),
+            ("Version", "The version of the XML invoice"),
+            ("ConformanceLevel", "The profile of the XML invoice"),
+        ] {
+            xmp += &format!(
+                concat!(
+                    "<rdf:li rdf:parseType=\"Resource\">",
+                    "<pdfaProperty:name>{}</pdfaProperty:name>",
+                    "<pdfaProperty:valueType>Text</pdfaProperty:valueType>",
+                    "<pdfaProperty:category>external</pdfaProperty:category>",
+                    "<pdfaProperty:description>{}</pdfaProperty:description>",
+                    "</rdf:li>",
+                ),
+                name, description,
+            );
+        }
+
+        xmp += "</rdf:Seq></pdfaSchema:property>";
+        xmp += "</rdf:li></rdf:Bag></pdfaExtension:schemas></rdf:Description>";
+
+        xmp += &format!(
+            concat!(
+                "<rdf:Description rdf:about=\"\" xmlns:fx=\"{}\">",
+                "<fx:DocumentType>{}</fx:DocumentType>",
+                "<fx:DocumentFileName>{}</fx:DocumentFileName>",
+                "<fx:Version>{}</fx:Version>",
+                "<fx:ConformanceLevel>{}</fx:ConformanceLevel>",
+                "</rdf:Description>",
+            ),
+            FACTUR_X_NAMESPACE,
+            escape_xml(&self.document_type),
+            escape_xml(&self.document_file_name),
+            escape_xml(&self.version),
+            escape_xml(&self.conformance_level),
+        );
+
+        xmp
+    }
+}
+
+struct AttachmentAnnotation {
+    page: usize,
+
+    /// Left, bottom, right and top in mm.
+    rect: [f64; 4],
+
+    attachment: Attachment,
+}
+
+/// The files embedded in the document. Files added here are attached to the whole document, while
+/// [crate::elements::file_attachment::FileAttachment] attaches them to an area of a page.
+#[derive(Default)]
+pub struct Attachments {
+    files: Vec<Attachment>,
+    annotations: Vec<AttachmentAnnotation>,
+
+    /// Marks the document as an e-invoice.
+    pub factur_x: Option<FacturX>,
+}
+
+impl Attachments {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.annotations.is_empty() && self.factur_x.is_none()
+    }
+
+    pub fn add(&mut self, attachment: Attachment) {
+        self.files.push(attachment);
+    }
+
+    /// Adds an annotation for a rectangle with the size in mm, starting at the location and going
+    /// down.
+    pub fn add_annotation(
+        &mut self,
+        location: &Location,
+        size: (f64, f64),
+        attachment: Attachment,
+    ) {
+        let scale = location.scale_factor;
+        let (x, y) = (location.pos.0 * scale, location.pos.1 * scale);
+
+        self.annotations.push(AttachmentAnnotation {
+            page: location.layer.page.0,
+            rect: [x, y - size.1 * scale, x + size.0 * scale, y],
+            attachment,
+        });
+    }
+
+    /// Embeds the files in a saved document. The files of the document are listed in the name tree
+    /// of the catalog and associated with it, the others get file attachment annotations.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+        let mod_date = doc
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .and_then(|id| doc.get_dictionary(id))
+            .and_then(|info| info.get(b"ModDate"))
+            .ok()
+            .cloned();
+
+        let mut files: Vec<(&str, ObjectId)> = self
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.file_name.as_str(),
+                    file_specification(doc, file, mod_date.clone()),
+                )
+            })
+            .collect();
+
+        for annotation in &self.annotations {
+            let page = page_id(&pages, annotation.page)?;
+
+            let file = file_specification(doc, &annotation.attachment, mod_date.clone());
+
+            let [left, bottom, right, top] = annotation.rect.map(mm_to_pt);
+
+            // An empty appearance, so that the annotation looks the same in all viewers.
+            let appearance = doc.add_object(Stream::new(
+                Dictionary::from_iter(vec![
+                    ("Type", Object::Name(b"XObject".to_vec())),
+                    ("Subtype", Object::Name(b"Form".to_vec())),
+                    (
+                        "BBox",
+                        Object::Array(vec![
+                            Object::Integer(0),
+                            Object::Integer(0),
+                            Object::Real(right - left),
+                            Object::Real(top - bottom),
+                        ]),
+                    ),
+                ]),
+                Vec::new(),
+            ));
+
+            let description = annotation
+                .attachment
+                .description
+                .as_ref()
+                .unwrap_or(&annotation.attachment.file_name);
+
+            let annotation = doc.add_object(Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"Annot".to_vec())),
+                ("Subtype", Object::Name(b"FileAttachment".to_vec())),
+                (
+                    "Rect",
+                    Object::Array(
+                        [left, bottom, right, top]
+                            .iter()
+                            .map(|&c| Object::Real(c))
+                            .collect(),
+                    ),
+                ),
+                ("FS", Object::Reference(file)),
+                ("AF", Object::Array(vec![Object::Reference(file)])),
+                ("Contents", text_string(description)),
+                ("Name", Object::Name(b"PushPin".to_vec())),
+                (
+                    "AP",
+                    Object::Dictionary(Dictionary::from_iter(vec![(
+                        "N",
+                        Object::Reference(appearance),
+                    )])),
+                ),
+            ]));
+
+            add_annotation(doc, page, annotation)?;
+        }
+
+        if !files.is_empty() {
+            // The keys of a name tree have to be sorted.
+            files.sort_by(|a, b| a.0.cmp(b.0));
+
+            let names = files
+                .iter()
+                .flat_map(|&(name, id)| [text_string(name), Object::Reference(id)])
+                .collect();
+
+            let embedded_files = Dictionary::from_iter(vec![("Names", Object::Array(names))]);
+
+            let associated_files = files.iter().map(|&(_, id)| Object::Reference(id)).collect();
+
+            let catalog = catalog_mut(doc)?;
+
+            match catalog.get_mut(b"Names") {
+                Ok(Object::Dictionary(names)) => names.set("EmbeddedFiles", embedded_files),
+                _ => catalog.set(
+                    "Names",
+                    Dictionary::from_iter(vec![(
+                        "EmbeddedFiles",
+                        Object::Dictionary(embedded_files),
+                    )]),
+                ),
+            }
+
+            catalog.set("AF", Object::Array(associated_files));
+        }
+
+        if let Some(ref factur_x) = self.factur_x {
+            if !update_xmp(doc, |xmp| add_description(xmp, &factur_x.xmp()))? {
+                return Err(Error::Pdf(
+                    "the Factur-X description needs XMP metadata".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds the file as an embedded file stream and returns its file specification.
+fn file_specification(
+    doc: &mut Document,
+    attachment: &Attachment,
+    mod_date: Option<Object>,
+) -> ObjectId {
+    let mut params = Dictionary::from_iter(vec![(
+        "Size",
+        Object::Integer(attachment.data.len() as i64),
+    )]);
+
+    if let Some(mod_date) = mod_date {
+        params.set("ModDate", mod_date);
+    }
+
+    let file = doc.add_object(Stream::new(
+        Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"EmbeddedFile".to_vec())),
+            (
+                "Subtype",
+                Object::Name(attachment.mime_type.as_bytes().to_vec()),
+            ),
+            ("Params", Object::Dictionary(params)),
+        ]),
+        attachment.data.clone(),
+    ));
+
+    let mut specification = Dictionary::from_iter(vec![
+        ("Type", Object::Name(b"Filespec".to_vec())),
+        ("F", text_string(&attachment.file_name)),
+        ("UF", text_string(&attachment.file_name)),
+        (
+            "EF",
+            Object::Dictionary(Dictionary::from_iter(vec![
+                ("F", Object::Reference(file)),
+                ("UF", Object::Reference(file)),
+            ])),
+        ),
+        (
+            "AFRelationship",
+            Object::Name(attachment.relationship.name().to_vec()),
+        ),
+    ]);
+
+    if let Some(ref description) = attachment.description {
+        specification.set("Desc", text_string(description));
+    }
+
+    doc.add_object(specification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pdf, conformance::Conformance, test_utils::FakeText, Error};
+
+    #[test]
+    fn test_attachments() {
+        let build = || {
+            build_pdf(
+                "test",
+                (10., 10.),
+                |_| Ok(()),
+                |_: &()| FakeText {
+                    lines: 1,
+                    line_height: 5.,
+                    width: 3.,
+                },
+            )
+            .unwrap()
+        };
+
+        let invoice = Attachment {
+            file_name: "factur-x.xml".to_string(),
+            description: Some("Invoice".to_string()),
+            mime_type: "text/xml".to_string(),
+            relationship: Relationship::Alternative,
+            data: b"<rsm:CrossIndustryInvoice/>".to_vec(),
+        };
+
+        let mut pdf = build();
+        pdf.attachments.add(invoice.clone());
+        pdf.attachments.add(Attachment {
+            file_name: "a.txt".to_string(),
+            description: None,
+            mime_type: "text/plain".to_string(),
+            relationship: Relationship::Supplement,
+            data: b"a".to_vec(),
+        });
+        pdf.attachments.factur_x = Some(FacturX::invoice("EN 16931"));
+        pdf.conformance = Some(Conformance::PdfA3b);
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+        let catalog = document.catalog().unwrap();
+
+        let names = catalog
+            .get(b"Names")
+            .and_then(Object::as_dict)
+            .and_then(|names| names.get(b"EmbeddedFiles"))
+            .and_then(Object::as_dict)
+            .and_then(|files| files.get(b"Names"))
+            .and_then(Object::as_array)
+            .unwrap();
+
+        assert_eq!(names.len(), 4);
+        assert!(matches!(&names[0], Object::String(name, _) if name == b"a.txt"));
+        assert_eq!(
+            catalog.get(b"AF").and_then(Object::as_array).unwrap().len(),
+            2
+        );
+
+        let specification = names[3]
+            .as_reference()
+            .and_then(|id| document.get_dictionary(id))
+            .unwrap();
+        assert!(matches!(
+            specification.get(b"AFRelationship"),
+            Ok(Object::Name(name)) if name == b"Alternative"
+        ));
+
+        let xmp = catalog
+            .get(b"Metadata")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let xmp = String::from_utf8(xmp.content.clone()).unwrap();
+
+        assert!(xmp.contains("<fx:ConformanceLevel>EN 16931</fx:ConformanceLevel>"));
+        assert!(xmp.contains("<pdfaSchema:prefix>fx</pdfaSchema:prefix>"));
+
+        // PDF/A-2 only allows attachments that are PDF/A documents themselves.
+        let mut pdf = build();
+        pdf.attachments.add(invoice);
+        pdf.conformance = Some(Conformance::PdfA2b);
+
+        assert!(matches!(pdf.to_bytes(), Err(Error::Conformance(_))));
+    }
+}
@@ -0,0 +1,337 @@
+use lopdf::{Dictionary, Document, Object, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    metadata::{add_description, element_content, replace_element, update_xmp},
+    output::{catalog_mut, info_mut},
+    Error, Result,
+};
+
+/// The PDF/A archival standards a document can be written in. Both allow transparency, so the
+/// alpha of shapes doesn't need to be restricted like for PDF/A-1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Conformance {
+    PdfA2b,
+
+    /// Like [Conformance::PdfA2b], but allows embedded files in any format.
+    PdfA3b,
+}
+
+const OUTPUT_CONDITION: &str = "sRGB IEC61966-2.1";
+
+impl Conformance {
+    fn part(self) -> u8 {
+        match self {
+            Conformance::PdfA2b => 2,
+            Conformance::PdfA3b => 3,
+        }
+    }
+
+    /// Makes a saved document conform. Violations that can't be fixed here, like fonts that aren't
+    /// embedded, are returned as errors.
+    pub fn write(self, doc: &mut Document) -> Result<()> {
+        check_fonts(doc)?;
+
+        if self == Conformance::PdfA2b && has_embedded_files(doc) {
+            return Err(Error::Conformance(
+                "PDF/A-2 doesn't allow attaching files that aren't PDF/A".to_string(),
+            ));
+        }
+
+        info_mut(doc)?.remove(b"GTS_PDFXVersion");
+
+        let description = format!(
+            concat!(
+                "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">",
+                "<pdfaid:part>{}</pdfaid:part>",
+                "<pdfaid:conformance>B</pdfaid:conformance>",
+                "</rdf:Description>",
+            ),
+            self.part(),
+        );
+
+        let updated = update_xmp(doc, |xmp| {
+            // printpdf writes the PDF/X version, which isn't part of the schemas PDF/A allows
+            // without an extension schema, and dates in the format of the info dictionary.
+            let mut xmp = replace_element(xmp, "pdfxid:GTS_PDFXVersion", "");
+            xmp = replace_element(&xmp, "pdfx:GTS_PDFXVersion", "");
+
+            for tag in ["xmp:CreateDate", "xmp:ModifyDate", "xmp:MetadataDate"] {
+                if let Some(date) = element_content(&xmp, tag).map(xmp_date) {
+                    xmp = replace_element(&xmp, tag, &format!("<{0}>{1}</{0}>", tag, date));
+                }
+            }
+
+            add_description(&xmp, &description)
+        })?;
+
+        if !updated {
+            return Err(Error::Conformance("missing XMP metadata".to_string()));
+        }
+
+        let profile = doc.add_object(Stream::new(
+            Dictionary::from_iter(vec![("N", Object::Integer(3))]),
+            srgb_profile(),
+        ));
+
+        let output_intent = Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"OutputIntent".to_vec())),
+            ("S", Object::Name(b"GTS_PDFA1".to_vec())),
+            (
+                "OutputConditionIdentifier",
+                Object::string_literal(OUTPUT_CONDITION),
+            ),
+            ("Info", Object::string_literal(OUTPUT_CONDITION)),
+            ("DestOutputProfile", Object::Reference(profile)),
+        ]);
+
+        let catalog = catalog_mut(doc)?;
+        catalog.set("OutputIntents", vec![Object::Dictionary(output_intent)]);
+
+        // The layers printpdf writes need a named default configuration.
+        if let Ok(Object::Dictionary(configuration)) = catalog
+            .get_mut(b"OCProperties")
+            .and_then(Object::as_dict_mut)
+            .and_then(|properties| properties.get_mut(b"D"))
+        {
+            configuration.set("Name", Object::string_literal("Default"));
+        }
+
+        // Annotations have to be printed.
+        for object in doc.objects.values_mut() {
+            if let Object::Dictionary(dictionary) = object {
+                if is_name(dictionary.get(b"Type"), b"Annot") {
+                    dictionary.set("F", Object::Integer(4));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects fonts without an embedded font file, which are the builtin fonts.
+fn check_fonts(doc: &Document) -> Result<()> {
+    for object in doc.objects.values() {
+        let font = match object {
+            Object::Dictionary(font) if is_name(font.get(b"Type"), b"Font") => font,
+            _ => continue,
+        };
+
+        // Composite fonts are embedded through their descendant fonts.
+        if is_name(font.get(b"Subtype"), b"Type0") || is_name(font.get(b"Subtype"), b"Type3") {
+            continue;
+        }
+
+        let descriptor = font
+            .get(b"FontDescriptor")
+            .and_then(|descriptor| match descriptor {
+                Object::Reference(id) => doc.get_dictionary(*id),
+                descriptor => descriptor.as_dict(),
+            })
+            .ok();
+
+        let embedded = descriptor.map_or(false, |descriptor| {
+            [&b"FontFile"[..], b"FontFile2", b"FontFile3"]
+                .iter()
+                .any(|key| descriptor.has(key))
+        });
+
+        if !embedded {
+            let name = match font.get(b"BaseFont") {
+                Ok(Object::Name(name)) => String::from_utf8_lossy(name).into_owned(),
+                _ => String::new(),
+            };
+
+            return Err(Error::Conformance(format!(
+                "the font {} isn't embedded, builtin fonts can't be used",
+                name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn has_embedded_files(doc: &Document) -> bool {
+    doc.objects.values().any(|object| match object {
+        Object::Stream(stream) => is_name(stream.dict.get(b"Type"), b"EmbeddedFile"),
+        _ => false,
+    })
+}
+
+fn is_name(object: lopdf::Result<&Object>, name: &[u8]) -> bool {
+    matches!(object, Ok(Object::Name(n)) if n == name)
+}
+
+/// Converts a date like `D:2024-01-31T12:00:00+00'00'` to the XMP format.
+fn xmp_date(date: &str) -> String {
+    let date = date.trim_start_matches("D:");
+
+    match date.strip_suffix('\'') {
+        Some(date) => date.replace('\'', ":"),
+        None => date.to_string(),
+    }
+}
+
+/// A minimal ICC profile for the sRGB color space, with the D50 adapted primaries and a sampled
+/// transfer curve.
+fn srgb_profile() -> Vec<u8> {
+    fn tag_data(signature: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut data = signature.to_vec();
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(content);
+        data
+    }
+
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        [x, y, z]
+            .iter()
+            .flat_map(|v| ((v * 65536.).round() as i32).to_be_bytes())
+            .collect()
+    }
+
+    let mut description = Vec::new();
+    description.extend_from_slice(&(OUTPUT_CONDITION.len() as u32 + 1).to_be_bytes());
+    description.extend_from_slice(OUTPUT_CONDITION.as_bytes());
+    description.push(0);
+    // Empty Unicode and ScriptCode descriptions.
+    description.extend_from_slice(&[0; 8 + 3 + 67]);
+
+    let curve_len = 1024;
+    let mut curve = (curve_len as u32).to_be_bytes().to_vec();
+
+    for i in 0..curve_len {
+        let v = i as f64 / (curve_len - 1) as f64;
+
+        let linear = if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        };
+
+        curve.extend_from_slice(&((linear * 65535.).round() as u16).to_be_bytes());
+    }
+
+    let curve = tag_data(b"curv", &curve);
+
+    let tags: Vec<(&[u8; 4], Vec<u8>)> = vec![
+        (b"desc", tag_data(b"desc", &description)),
+        (b"cprt", tag_data(b"text", b"No copyright, use freely\0")),
+        (b"wtpt", tag_data(b"XYZ ", &xyz(0.9642, 1., 0.8249))),
+        (b"rXYZ", tag_data(b"XYZ ", &xyz(0.4361, 0.2225, 0.0139))),
+        (b"gXYZ", tag_data(b"XYZ ", &xyz(0.3851, 0.7169, 0.0971))),
+        (b"bXYZ", tag_data(b"XYZ ", &xyz(0.1431, 0.0606, 0.7141))),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let data_start = 128 + 4 + tags.len() * 12;
+
+    for (signature, content) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(content.len() as u32).to_be_bytes());
+
+        data.extend_from_slice(content);
+
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+
+    let size = data_start + data.len();
+
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]);
+    profile.extend_from_slice(&[2, 0x10, 0, 0]);
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    // The creation date, 2000-01-01.
+    profile.extend_from_slice(&[0x07, 0xd0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0]);
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 24]);
+    // Perceptual rendering intent.
+    profile.extend_from_slice(&[0; 4]);
+    profile.extend_from_slice(&xyz(0.9642, 1., 0.8249));
+    profile.resize(128, 0);
+
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pdf, fonts::builtin::BuiltinFont, test_utils::FakeText};
+
+    #[test]
+    fn test_srgb_profile() {
+        let profile = srgb_profile();
+
+        assert_eq!(
+            u32::from_be_bytes(profile[..4].try_into().unwrap()) as usize,
+            profile.len()
+        );
+        assert_eq!(&profile[36..40], b"acsp");
+        assert_eq!(u32::from_be_bytes(profile[128..132].try_into().unwrap()), 9);
+    }
+
+    #[test]
+    fn test_xmp_date() {
+        assert_eq!(
+            xmp_date("D:2024-01-31T12:00:00+00'00'"),
+            "2024-01-31T12:00:00+00:00"
+        );
+        assert_eq!(xmp_date("2024-01-31T12:00:00Z"), "2024-01-31T12:00:00Z");
+    }
+
+    #[test]
+    fn test_conformance() {
+        let text = || FakeText {
+            lines: 1,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let mut pdf = build_pdf("test", (10., 10.), |_| Ok(()), |_: &()| text()).unwrap();
+        pdf.conformance = Some(Conformance::PdfA3b);
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+        let catalog = document.catalog().unwrap();
+
+        let output_intents = catalog
+            .get(b"OutputIntents")
+            .and_then(Object::as_array)
+            .unwrap();
+        assert_eq!(output_intents.len(), 1);
+
+        let xmp = catalog
+            .get(b"Metadata")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let xmp = String::from_utf8(xmp.content.clone()).unwrap();
+
+        assert!(xmp.contains("<pdfaid:part>3</pdfaid:part>"));
+        assert!(!xmp.contains("GTS_PDFXVersion"));
+        assert!(!xmp.contains("D:"));
+
+        let mut pdf = build_pdf(
+            "test",
+            (10., 10.),
+            BuiltinFont::helvetica,
+            |_: &BuiltinFont| text(),
+        )
+        .unwrap();
+        pdf.conformance = Some(Conformance::PdfA2b);
+
+        assert!(matches!(pdf.to_bytes(), Err(Error::Conformance(_))));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod align_location_bottom;
 pub mod align_preferred_height_bottom;
+pub mod bookmark;
 pub mod break_list;
 pub mod break_whole;
 pub mod center_in_preferred_height;
@@ -8,10 +9,15 @@ pub mod circle;
 pub mod column;
 pub mod debug;
 pub mod expand_to_preferred_height;
+pub mod file_attachment;
 pub mod force_break;
+pub mod form_fields;
 pub mod h_align;
 pub mod image;
+pub mod imported_page;
 pub mod line;
+pub mod link;
+pub mod mark;
 pub mod min_first_height;
 pub mod none;
 pub mod padding;
@@ -27,7 +33,9 @@ pub mod shrink_to_fit;
 pub mod stack;
 pub mod styled_box;
 pub mod svg;
+pub mod table_of_contents;
 pub mod table_row;
+pub mod tagged;
 pub mod text;
 pub mod title_or_break;
 pub mod titled;
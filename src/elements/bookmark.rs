@@ -0,0 +1,50 @@
+use crate::{links::Destination, *};
+
+use super::link::{draw_recording_locations, start_location};
+
+/// Adds an entry to the document outline that points to where the element starts. To put a
+/// section heading into the outline, wrap the title of a [super::titled::Titled] or the first
+/// title of a [super::changing_title::ChangingTitle] in this.
+pub struct Bookmark<'a, E: Element> {
+    pub element: &'a E,
+    pub title: &'a str,
+
+    /// Zero for the top level. Bookmarks become children of the closest bookmark before them with
+    /// a lower level.
+    pub level: u8,
+
+    /// Also makes the position an anchor for links.
+    pub name: Option<&'a str>,
+}
+
+impl<'a, E: Element> Element for Bookmark<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let (size, locations) = draw_recording_locations(
+            self.element,
+            DrawCtx {
+                pdf: &mut *ctx.pdf,
+                ..ctx
+            },
+        );
+
+        if let Some(location) = start_location(&locations) {
+            let destination = Destination::from_location(location);
+
+            ctx.pdf.outline.add(self.title, self.level, destination);
+
+            if let Some(name) = self.name {
+                ctx.pdf.links.add_anchor(name, destination);
+            }
+        }
+
+        size
+    }
+}
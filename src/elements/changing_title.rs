@@ -371,7 +371,7 @@ mod tests {
     #[test]
     fn test() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let first = Text::basic("first", &font, 12.);
             let first = first.debug(1);
@@ -404,7 +404,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let first = Text::basic("first", &font, 12.);
                 let first = first.debug(1);
@@ -433,7 +433,7 @@ mod tests {
     #[test]
     fn test_collapse() {
         let bytes = test_element_bytes(TestElementParams::unbreakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let first = Text::basic("first", &font, 12.);
             let first = first.debug(1);
@@ -461,7 +461,7 @@ mod tests {
     #[test]
     fn test_no_collapse() {
         let bytes = test_element_bytes(TestElementParams::unbreakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let first = Text::basic("first", &font, 12.);
             let first = first.debug(1);
@@ -489,7 +489,7 @@ mod tests {
     #[test]
     fn test_multipage_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let first = Text::basic("first", &font, 12.);
             let first = first.debug(1);
@@ -532,7 +532,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let title = Text::basic("title", &font, 12.);
                 let title = &title.debug(1);
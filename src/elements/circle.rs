@@ -1,6 +1,6 @@
 use printpdf::{utils::calculate_points_for_circle, Line};
 
-use crate::{utils::*, *};
+use crate::{structure::draw_artifact, utils::*, *};
 
 pub struct Circle {
     pub radius: f64,
@@ -16,7 +16,13 @@ impl Element for Circle {
         size(self)
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_artifact(ctx, |ctx| self.draw_untagged(ctx))
+    }
+}
+
+impl Circle {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         let outline_thickness = outline_thickness(self);
         ctx.break_if_appropriate_for_min_height(self.radius * 2. + outline_thickness);
 
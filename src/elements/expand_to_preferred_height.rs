@@ -101,7 +101,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let content = Text::basic(LOREM_IPSUM, &font, 32.);
                 let content = content.debug(1);
@@ -124,7 +124,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let content = Text::basic(LOREM_IPSUM, &font, 12.);
                 let content = content.debug(1);
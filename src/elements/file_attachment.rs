@@ -0,0 +1,100 @@
+use crate::{attachments::Attachment, elements::link::draw_recording_locations, *};
+
+/// Attaches a file to the area of the element, where viewers show it as a clickable annotation.
+/// The area is the part of the element on the location it starts on.
+pub struct FileAttachment<'a, E: Element> {
+    pub element: &'a E,
+    pub attachment: &'a Attachment,
+}
+
+impl<'a, E: Element> Element for FileAttachment<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let (size, locations) = draw_recording_locations(
+            self.element,
+            DrawCtx {
+                pdf: &mut *ctx.pdf,
+                ..ctx
+            },
+        );
+
+        let start = locations.iter().find(|(_, height)| height.is_some());
+
+        if let (Some(width), Some((location, Some(height)))) = (size.width, start) {
+            ctx.pdf
+                .attachments
+                .add_annotation(location, (width, *height), self.attachment.clone());
+        }
+
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Object;
+
+    use super::*;
+    use crate::{
+        attachments::Relationship,
+        sections::{Orientation, Section},
+        test_utils::FakeText,
+    };
+
+    #[test]
+    fn test_file_attachment() {
+        let attachment = Attachment {
+            file_name: "data.csv".to_string(),
+            description: None,
+            mime_type: "text/csv".to_string(),
+            relationship: Relationship::Data,
+            data: b"a,b".to_vec(),
+        };
+
+        let element = FileAttachment {
+            element: &FakeText {
+                lines: 6,
+                line_height: 5.,
+                width: 3.,
+            },
+            attachment: &attachment,
+        };
+
+        let pdf = build_pdf_sections(
+            "test",
+            |_| Ok(()),
+            |sections, _| {
+                sections.add(&Section {
+                    page_size: (10., 20.),
+                    orientation: Orientation::Portrait,
+                    restart_page_numbers: None,
+                    element: &element,
+                });
+            },
+        )
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let annotations: Vec<usize> = document
+            .get_pages()
+            .into_values()
+            .map(|page| {
+                document
+                    .get_dictionary(page)
+                    .and_then(|page| page.get(b"Annots"))
+                    .and_then(Object::as_array)
+                    .map_or(0, |annots| annots.len())
+            })
+            .collect();
+
+        assert_eq!(annotations, [1, 0]);
+    }
+}
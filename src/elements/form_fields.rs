@@ -0,0 +1,671 @@
+use printpdf::{utils::calculate_points_for_circle, Line, Point};
+
+use crate::{
+    elements::{rectangle::Rectangle, text::Text},
+    fonts::Font,
+    forms::{begin_appearance, end_appearance, Field, OFF_STATE},
+    structure::draw_artifact,
+    utils::{mm_to_pt, pt_to_mm, u32_to_color_and_alpha},
+    *,
+};
+
+/// The look of a form field. The outline is drawn inside of the field.
+#[derive(Copy, Clone, Debug)]
+pub struct FieldStyle {
+    pub fill: Option<u32>,
+    pub outline: Option<(f64, u32)>,
+
+    /// The space between the edge of the field and the text.
+    pub padding: f64,
+}
+
+impl Default for FieldStyle {
+    fn default() -> Self {
+        FieldStyle {
+            fill: None,
+            outline: Some((0.2, 0x00_00_00_FF)),
+            padding: 1.,
+        }
+    }
+}
+
+/// A field for entering text. It's as wide as the available width.
+pub struct TextField<'a, F: Font> {
+    pub name: &'a str,
+    pub value: &'a str,
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+
+    /// The height of the field in lines. Fields with more than one line allow line breaks.
+    pub lines: u32,
+
+    pub max_length: Option<u32>,
+    pub style: FieldStyle,
+}
+
+impl<'a, F: Font> TextField<'a, F> {
+    pub fn basic(name: &'a str, font: &'a F, size: f64) -> Self {
+        TextField {
+            name,
+            value: "",
+            font,
+            size,
+            color: 0x00_00_00_FF,
+            lines: 1,
+            max_length: None,
+            style: FieldStyle::default(),
+        }
+    }
+
+    fn height(&self) -> f64 {
+        text_height(self.font, self.size, self.lines, self.style)
+    }
+}
+
+impl<'a, F: Font> Element for TextField<'a, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_location_usage(ctx, self.height())
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.height());
+
+        ElementSize {
+            width: Some(ctx.width.max),
+            height: Some(self.height()),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let size = (ctx.width.max, self.height());
+
+        let field = Field::Text {
+            value: self.value.to_string(),
+            multiline: self.lines > 1,
+            max_length: self.max_length,
+        };
+
+        draw_widget(ctx, size, self.name, field, |pdf, location, _| {
+            draw_box(pdf, location, size, self.style);
+            draw_text(
+                pdf,
+                location,
+                size,
+                self.style,
+                Text {
+                    color: self.color,
+                    ..Text::basic(self.value, self.font, self.size)
+                },
+            );
+        })
+    }
+}
+
+/// A field for choosing one of the options. It shows the selected option and is as wide as the
+/// available width.
+pub struct Dropdown<'a, F: Font> {
+    pub name: &'a str,
+    pub options: &'a [&'a str],
+    pub selected: Option<usize>,
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+    pub style: FieldStyle,
+}
+
+impl<'a, F: Font> Dropdown<'a, F> {
+    fn height(&self) -> f64 {
+        text_height(self.font, self.size, 1, self.style)
+    }
+}
+
+impl<'a, F: Font> Element for Dropdown<'a, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_location_usage(ctx, self.height())
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.height());
+
+        ElementSize {
+            width: Some(ctx.width.max),
+            height: Some(self.height()),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let size = (ctx.width.max, self.height());
+
+        let field = Field::Dropdown {
+            options: self
+                .options
+                .iter()
+                .map(|option| option.to_string())
+                .collect(),
+            selected: self.selected,
+        };
+
+        let value = self
+            .selected
+            .and_then(|selected| self.options.get(selected))
+            .copied()
+            .unwrap_or("");
+
+        draw_widget(ctx, size, self.name, field, |pdf, location, _| {
+            draw_box(pdf, location, size, self.style);
+            draw_text(
+                pdf,
+                location,
+                size,
+                self.style,
+                Text {
+                    color: self.color,
+                    ..Text::basic(value, self.font, self.size)
+                },
+            );
+        })
+    }
+}
+
+/// A square box that can be checked.
+pub struct Checkbox<'a> {
+    pub name: &'a str,
+    pub checked: bool,
+
+    /// The width and height in mm.
+    pub size: f64,
+
+    /// The color of the check mark.
+    pub color: u32,
+
+    pub style: FieldStyle,
+}
+
+impl<'a> Element for Checkbox<'a> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_location_usage(ctx, self.size)
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.size);
+
+        ElementSize {
+            width: Some(self.size),
+            height: Some(self.size),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let size = (self.size, self.size);
+        let field = Field::Checkbox {
+            checked: self.checked,
+        };
+
+        draw_widget(ctx, size, self.name, field, |pdf, location, state| {
+            draw_box(pdf, location, size, self.style);
+
+            if state == Some(OFF_STATE) {
+                return;
+            }
+
+            let (x, y) = location.pos;
+            let point = |dx: f64, dy: f64| {
+                (
+                    Point::new(Mm(x + dx * self.size), Mm(y - dy * self.size)),
+                    false,
+                )
+            };
+
+            let layer = &location.layer;
+            layer.save_graphics_state();
+            layer.set_outline_color(u32_to_color_and_alpha(self.color).0);
+            layer.set_outline_thickness(mm_to_pt(self.size * 0.12));
+            layer.add_shape(Line {
+                points: vec![point(0.22, 0.52), point(0.42, 0.74), point(0.78, 0.28)],
+                is_closed: false,
+                has_fill: false,
+                has_stroke: true,
+                is_clipping_path: false,
+            });
+            layer.restore_graphics_state();
+        })
+    }
+}
+
+/// A round button of a radio group, which are the buttons with the same group name. Selecting one
+/// of them sets the group to the value of the button.
+pub struct RadioButton<'a> {
+    pub group: &'a str,
+    pub value: &'a str,
+    pub selected: bool,
+
+    /// The diameter in mm.
+    pub size: f64,
+
+    /// The color of the dot of the selected button.
+    pub color: u32,
+
+    pub style: FieldStyle,
+}
+
+impl<'a> Element for RadioButton<'a> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_location_usage(ctx, self.size)
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.size);
+
+        ElementSize {
+            width: Some(self.size),
+            height: Some(self.size),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let size = (self.size, self.size);
+        let field = Field::RadioButton {
+            value: self.value.to_string(),
+            selected: self.selected,
+        };
+
+        draw_widget(ctx, size, self.group, field, |_, location, state| {
+            let radius = self.size / 2.;
+            let center = (location.pos.0 + radius, location.pos.1 - radius);
+
+            let circle =
+                |radius: f64| calculate_points_for_circle(Mm(radius), Mm(center.0), Mm(center.1));
+
+            let layer = &location.layer;
+            layer.save_graphics_state();
+
+            if let Some(color) = self.style.fill {
+                let (color, alpha) = u32_to_color_and_alpha(color);
+                layer.set_fill_color(color);
+                layer.set_fill_alpha(alpha);
+            }
+
+            let outline_thickness = if let Some((thickness, color)) = self.style.outline {
+                layer.set_outline_color(u32_to_color_and_alpha(color).0);
+                layer.set_outline_thickness(mm_to_pt(thickness));
+                thickness
+            } else {
+                0.
+            };
+
+            layer.add_shape(Line {
+                points: circle(radius - outline_thickness / 2.),
+                is_closed: true,
+                has_fill: self.style.fill.is_some(),
+                has_stroke: self.style.outline.is_some(),
+                is_clipping_path: false,
+            });
+
+            if state != Some(OFF_STATE) {
+                let (color, alpha) = u32_to_color_and_alpha(self.color);
+                layer.set_fill_color(color);
+                layer.set_fill_alpha(alpha);
+
+                layer.add_shape(Line {
+                    points: circle(radius / 2.),
+                    is_closed: true,
+                    has_fill: true,
+                    has_stroke: false,
+                    is_clipping_path: false,
+                });
+            }
+
+            layer.restore_graphics_state();
+        })
+    }
+}
+
+/// A field that a signature can be added to, as wide as the available width. To show the
+/// signature of [crate::signature::Signature] in it, use its name as the signature's field.
+///
+/// The text is shown in the field, for example the name of the signer and the date of the
+/// signature on separate lines. Without it the field is an empty box.
+pub struct SignatureField<'a, F: Font> {
+    pub name: &'a str,
+    pub text: &'a str,
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+    pub height: f64,
+    pub style: FieldStyle,
+}
+
+impl<'a, F: Font> Element for SignatureField<'a, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_location_usage(ctx, self.height)
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.height);
+
+        ElementSize {
+            width: Some(ctx.width.max),
+            height: Some(self.height),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let size = (ctx.width.max, self.height);
+
+        draw_widget(
+            ctx,
+            size,
+            self.name,
+            Field::Signature,
+            |pdf, location, _| {
+                draw_box(pdf, location, size, self.style);
+
+                if !self.text.is_empty() {
+                    draw_text(
+                        pdf,
+                        location,
+                        size,
+                        self.style,
+                        Text {
+                            color: self.color,
+                            ..Text::basic(self.text, self.font, self.size)
+                        },
+                    );
+                }
+            },
+        )
+    }
+}
+
+fn first_location_usage(ctx: FirstLocationUsageCtx, height: f64) -> FirstLocationUsage {
+    if ctx.break_appropriate_for_min_height(height) {
+        FirstLocationUsage::WillSkip
+    } else {
+        FirstLocationUsage::WillUse
+    }
+}
+
+fn text_height<F: Font>(font: &F, size: f64, lines: u32, style: FieldStyle) -> f64 {
+    let line_height =
+        pt_to_mm(font.general_metrics().line_height * size / font.units_per_em() as f64);
+
+    line_height * lines.max(1) as f64 + style.padding * 2.
+}
+
+/// Adds the widget and draws its appearance for each of its states. They are all drawn in the same
+/// place and get moved into the appearance streams when the document is saved.
+fn draw_widget(
+    mut ctx: DrawCtx,
+    size: (f64, f64),
+    name: &str,
+    field: Field,
+    draw_state: impl Fn(&mut Pdf, &Location, Option<&str>),
+) -> ElementSize {
+    ctx.break_if_appropriate_for_min_height(size.1);
+
+    let widget = ctx.pdf.forms.add(&ctx.location, size, name, field.clone());
+
+    let states: Vec<Option<&str>> = match field.states() {
+        states if states.is_empty() => vec![None],
+        states => states.into_iter().map(Some).collect(),
+    };
+
+    draw_artifact(
+        DrawCtx {
+            pdf: ctx.pdf,
+            breakable: None,
+            ..ctx
+        },
+        |ctx| {
+            for state in states {
+                begin_appearance(&ctx.location.layer, widget, state);
+                draw_state(ctx.pdf, &ctx.location, state);
+                end_appearance(&ctx.location.layer);
+            }
+
+            ElementSize {
+                width: Some(size.0),
+                height: Some(size.1),
+            }
+        },
+    )
+}
+
+fn draw_box(pdf: &mut Pdf, location: &Location, size: (f64, f64), style: FieldStyle) {
+    let thickness = style.outline.map_or(0., |(thickness, _)| thickness);
+
+    Rectangle {
+        size: (size.0 - thickness, size.1 - thickness),
+        fill: style.fill,
+        outline: style.outline,
+    }
+    .draw(DrawCtx {
+        pdf,
+        location: location.clone(),
+        width: WidthConstraint {
+            max: size.0,
+            expand: false,
+        },
+        first_height: size.1,
+        preferred_height: None,
+        breakable: None,
+    });
+}
+
+/// Draws the value of a field inside of the padding. The font is also set before the text, so that
+/// the font of the field is known even if it's empty.
+fn draw_text<F: Font>(
+    pdf: &mut Pdf,
+    location: &Location,
+    size: (f64, f64),
+    style: FieldStyle,
+    text: Text<F>,
+) {
+    let layer = &location.layer;
+    layer.set_fill_color(u32_to_color_and_alpha(text.color).0);
+    layer.set_font(text.font.indirect_font_ref(), text.size);
+
+    text.draw(DrawCtx {
+        pdf,
+        location: Location {
+            pos: (
+                location.pos.0 + style.padding,
+                location.pos.1 - style.padding,
+            ),
+            ..location.clone()
+        },
+        width: WidthConstraint {
+            max: size.0 - style.padding * 2.,
+            expand: false,
+        },
+        first_height: size.1 - style.padding * 2.,
+        preferred_height: None,
+        breakable: None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Object;
+
+    use super::*;
+    use crate::{
+        elements::column::Column,
+        fonts::{builtin::BuiltinFont, truetype::TruetypeFont},
+    };
+
+    const DEJAVU_SANS: &[u8] = include_bytes!("../test_utils/DejaVuSans.ttf");
+
+    #[test]
+    fn test_form_fields() {
+        let pdf = build_pdf(
+            "test",
+            (100., 100.),
+            BuiltinFont::helvetica,
+            |font: &BuiltinFont| Column {
+                content: move |content| {
+                    content
+                        .add(&TextField {
+                            value: "Jane",
+                            ..TextField::basic("name", font, 10.)
+                        })?
+                        .add(&Checkbox {
+                            name: "agree",
+                            checked: true,
+                            size: 5.,
+                            color: 0x00_00_00_FF,
+                            style: FieldStyle::default(),
+                        })?;
+
+                    None
+                },
+                gap: 2.,
+                collapse: true,
+            },
+        )
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let fields = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"AcroForm"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .and_then(|form| form.get(b"Fields"))
+            .and_then(Object::as_array)
+            .unwrap();
+        assert_eq!(fields.len(), 2);
+
+        let page = *document.get_pages().values().next().unwrap();
+
+        // The appearances are moved from the page into the widgets.
+        let content = document.get_page_content(page).unwrap();
+        assert!(!String::from_utf8_lossy(&content).contains("Tf"));
+
+        let text_field = fields[0]
+            .as_reference()
+            .and_then(|id| document.get_dictionary(id))
+            .unwrap();
+        assert!(text_field.has(b"DA"));
+
+        let appearance = text_field
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .and_then(|kids| document.get_dictionary(kids[0].as_reference()?))
+            .and_then(|widget| widget.get(b"AP"))
+            .and_then(Object::as_dict)
+            .and_then(|appearances| appearances.get(b"N"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        assert!(String::from_utf8_lossy(&appearance.content).contains("/Tx BMC"));
+    }
+
+    #[test]
+    fn test_signature_field() {
+        let pdf = build_pdf(
+            "test",
+            (100., 100.),
+            BuiltinFont::helvetica,
+            |font: &BuiltinFont| SignatureField {
+                name: "signature",
+                text: "Jane Doe\n2026-10-18",
+                font,
+                size: 10.,
+                color: 0x00_00_00_FF,
+                height: 20.,
+                style: FieldStyle::default(),
+            },
+        )
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let field = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"AcroForm"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .and_then(|form| form.get(b"Fields"))
+            .and_then(Object::as_array)
+            .and_then(|fields| document.get_dictionary(fields[0].as_reference()?))
+            .unwrap();
+
+        // The text isn't variable text that viewers would replace.
+        assert!(!field.has(b"DA"));
+
+        let appearance = field
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .and_then(|kids| document.get_dictionary(kids[0].as_reference()?))
+            .and_then(|widget| widget.get(b"AP"))
+            .and_then(Object::as_dict)
+            .and_then(|appearances| appearances.get(b"N"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let content = String::from_utf8_lossy(&appearance.content);
+
+        assert!(content.contains("Jane Doe") && content.contains("2026-10-18"));
+        assert!(!content.contains("/Tx BMC"));
+    }
+
+    #[test]
+    fn test_form_font_not_subset() {
+        let pdf = build_pdf(
+            "test",
+            (100., 100.),
+            |doc| TruetypeFont::new(doc, DEJAVU_SANS),
+            |font: &TruetypeFont<&[u8]>| TextField {
+                value: "Jane",
+                ..TextField::basic("name", font, 10.)
+            },
+        )
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let fonts = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"AcroForm"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .and_then(|form| form.get(b"DR"))
+            .and_then(Object::as_dict)
+            .and_then(|resources| resources.get(b"Font"))
+            .and_then(Object::as_dict)
+            .unwrap();
+        assert_eq!(fonts.len(), 1);
+
+        let font = fonts
+            .iter()
+            .next()
+            .and_then(|(_, font)| document.get_dictionary(font.as_reference().ok()?).ok())
+            .unwrap();
+
+        // Text typed into the field can use any glyph, so the font is embedded in full.
+        let base_font = font.get(b"BaseFont").and_then(Object::as_name).unwrap();
+        assert!(!base_font.contains(&b'+'));
+
+        let file = font
+            .get(b"DescendantFonts")
+            .and_then(Object::as_array)
+            .and_then(|fonts| document.get_dictionary(fonts[0].as_reference()?))
+            .and_then(|font| font.get(b"FontDescriptor"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .and_then(|descriptor| descriptor.get(b"FontFile2"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        assert_eq!(
+            file.dict.get(b"Length1").and_then(Object::as_i64).unwrap(),
+            DEJAVU_SANS.len() as i64,
+        );
+    }
+}
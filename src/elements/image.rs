@@ -1,6 +1,10 @@
 use printpdf::image::{DynamicImage, GenericImageView};
 
-use crate::{image::Image, *};
+use crate::{
+    image::Image,
+    structure::{draw_tagged, StructureType},
+    *,
+};
 
 use super::svg::Svg;
 
@@ -8,12 +12,19 @@ const INCH_TO_MM: f64 = 25.4;
 
 pub struct ImageElement<'a> {
     pub image: &'a Image,
+
+    /// A description of the image for tagged documents.
+    pub alt: Option<&'a str>,
 }
 
 impl<'a> Element for ImageElement<'a> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
         match self.image {
-            Image::Svg(svg) => Svg { data: svg }.first_location_usage(ctx),
+            Image::Svg(svg) => Svg {
+                data: svg,
+                alt: self.alt,
+            }
+            .first_location_usage(ctx),
             Image::Pixel(image) => {
                 let (height, _, _) = calculate_size(image, ctx.width);
 
@@ -28,7 +39,11 @@ impl<'a> Element for ImageElement<'a> {
 
     fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
         match self.image {
-            Image::Svg(svg) => Svg { data: svg }.measure(ctx),
+            Image::Svg(svg) => Svg {
+                data: svg,
+                alt: self.alt,
+            }
+            .measure(ctx),
             Image::Pixel(image) => {
                 let (height, _, element_size) = calculate_size(image, ctx.width);
 
@@ -39,9 +54,22 @@ impl<'a> Element for ImageElement<'a> {
         }
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_tagged(ctx, StructureType::Figure, self.alt, |ctx| {
+            self.draw_untagged(ctx)
+        })
+    }
+}
+
+impl<'a> ImageElement<'a> {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         match self.image {
-            Image::Svg(svg) => Svg { data: svg }.draw(ctx),
+            // Drawn untagged since the whole image is already a figure.
+            Image::Svg(svg) => Svg {
+                data: svg,
+                alt: self.alt,
+            }
+            .draw_untagged(ctx),
             Image::Pixel(image) => {
                 let (height, scale, element_size) = calculate_size(image, ctx.width);
 
@@ -0,0 +1,221 @@
+use crate::{
+    import::PdfPage,
+    structure::{draw_artifact, draw_tagged, StructureType},
+    *,
+};
+
+/// Draws a page of another document, scaled to the available width like
+/// [super::image::ImageElement].
+pub struct ImportedPage<'a> {
+    pub page: &'a PdfPage,
+
+    /// A description of the page for tagged documents. Without it the page is drawn as an
+    /// artifact, since the structure of the other document isn't imported.
+    pub alt: Option<&'a str>,
+}
+
+impl<'a> Element for ImportedPage<'a> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        let (height, _, _) = calculate_size(self.page, ctx.width);
+
+        if ctx.break_appropriate_for_min_height(height) {
+            FirstLocationUsage::WillSkip
+        } else {
+            FirstLocationUsage::WillUse
+        }
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let (height, _, element_size) = calculate_size(self.page, ctx.width);
+
+        ctx.break_if_appropriate_for_min_height(height);
+
+        element_size
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        match self.alt {
+            Some(alt) => draw_tagged(ctx, StructureType::Figure, Some(alt), |ctx| {
+                self.draw_untagged(ctx)
+            }),
+            None => draw_artifact(ctx, |ctx| self.draw_untagged(ctx)),
+        }
+    }
+}
+
+impl<'a> ImportedPage<'a> {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
+        let (height, scale, element_size) = calculate_size(self.page, ctx.width);
+
+        ctx.break_if_appropriate_for_min_height(height);
+
+        self.page.draw(ctx.pdf, &ctx.location, scale);
+
+        element_size
+    }
+}
+
+fn calculate_size(page: &PdfPage, width: WidthConstraint) -> (f64, f64, ElementSize) {
+    let dimensions = page.size();
+
+    let width = width.constrain(dimensions.0);
+
+    let size = (width, dimensions.1 * width / dimensions.0);
+    let scale = width / dimensions.0;
+
+    (
+        size.1,
+        scale,
+        ElementSize {
+            width: Some(size.0),
+            height: Some(size.1),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Object;
+
+    use super::*;
+    use crate::{
+        elements::{
+            column::Column,
+            page::{DecorationElements, Page},
+            rectangle::Rectangle,
+        },
+        sections::{Orientation, Section},
+    };
+
+    fn source() -> Vec<u8> {
+        build_pdf(
+            "source",
+            (100., 200.),
+            |_| Ok(()),
+            |_: &()| Rectangle {
+                size: (50., 50.),
+                fill: Some(0xFF_00_00_FF),
+                outline: None,
+            },
+        )
+        .unwrap()
+        .to_bytes()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_imported_page() {
+        let pages = PdfPage::load(&source()).unwrap();
+        assert_eq!(pages.len(), 1);
+
+        let (width, height) = pages[0].size();
+        assert!((width - 100.).abs() < 0.01 && (height - 200.).abs() < 0.01);
+
+        let element = ImportedPage {
+            page: &pages[0],
+            alt: None,
+        };
+
+        let column = Column {
+            content: |content| {
+                content.add(&element)?.add(&element)?;
+                None
+            },
+            gap: 0.,
+            collapse: true,
+        };
+
+        let page = Page {
+            primary: &column,
+            border_left: 5.,
+            border_right: 5.,
+            border_top: 5.,
+            border_bottom: 5.,
+            decoration_elements: |content: &mut DecorationElements, _, _| {
+                content.add_background(&pages[0]);
+            },
+        };
+
+        let pdf = build_pdf_sections(
+            "test",
+            |_| Ok(()),
+            |sections, _| {
+                sections.add(&Section {
+                    page_size: (60., 150.),
+                    orientation: Orientation::Portrait,
+                    restart_page_numbers: None,
+                    element: &page,
+                });
+            },
+        )
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+        let pages = document.get_pages();
+
+        // Two pages of 100 mm and the background on both.
+        assert_eq!(pages.len(), 2);
+
+        let forms = document
+            .objects
+            .values()
+            .filter(|object| match object {
+                Object::Stream(stream) => stream.dict.type_is(b"XObject"),
+                _ => false,
+            })
+            .count();
+        assert_eq!(forms, 1);
+
+        for &page in pages.values() {
+            let content = String::from_utf8(document.get_page_content(page).unwrap()).unwrap();
+            assert!(content.contains("/LaserPdfImport0 Do"));
+
+            let resources = document
+                .get_dictionary(page)
+                .and_then(|page| page.get(b"Resources"))
+                .and_then(Object::as_reference)
+                .and_then(|id| document.get_dictionary(id))
+                .and_then(|resources| resources.get(b"XObject"))
+                .and_then(Object::as_reference)
+                .and_then(|id| document.get_dictionary(id))
+                .unwrap();
+            assert!(resources.has(b"LaserPdfImport0"));
+        }
+    }
+
+    #[test]
+    fn test_tagged_imported_page() {
+        let pages = PdfPage::load(&source()).unwrap();
+
+        let pdf = build_pdf_sections(
+            "test",
+            |_| Ok(()),
+            |sections, _| {
+                sections.tagged().add(&Section {
+                    page_size: (100., 200.),
+                    orientation: Orientation::Portrait,
+                    restart_page_numbers: None,
+                    element: &ImportedPage {
+                        page: &pages[0],
+                        alt: Some("A red square"),
+                    },
+                });
+            },
+        )
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let figure = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .find(|element| element.get(b"S").and_then(Object::as_name_str).ok() == Some("Figure"))
+            .unwrap();
+        assert!(figure.has(b"Alt"));
+
+        let page = *document.get_pages().values().next().unwrap();
+        let content = String::from_utf8(document.get_page_content(page).unwrap()).unwrap();
+        assert!(content.contains("/MCID 0"));
+    }
+}
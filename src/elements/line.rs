@@ -1,6 +1,6 @@
 use printpdf::Point;
 
-use crate::{utils::*, *};
+use crate::{structure::draw_artifact, utils::*, *};
 
 pub struct Line {
     pub style: LineStyle,
@@ -26,7 +26,13 @@ impl Element for Line {
         size(self, ctx.width)
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_artifact(ctx, |ctx| self.draw_untagged(ctx))
+    }
+}
+
+impl Line {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         ctx.break_if_appropriate_for_min_height(self.style.thickness);
 
         if ctx.width.expand {
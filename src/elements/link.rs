@@ -0,0 +1,225 @@
+use crate::{
+    links::{Destination, LinkTarget},
+    *,
+};
+
+/// Makes the element clickable. A rectangle with the width of the element is added for every
+/// location the element uses.
+pub struct Link<'a, E: Element> {
+    pub element: &'a E,
+    pub target: LinkTarget,
+}
+
+impl<'a, E: Element> Element for Link<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let (size, locations) = draw_recording_locations(
+            self.element,
+            DrawCtx {
+                pdf: &mut *ctx.pdf,
+                ..ctx
+            },
+        );
+
+        if let Some(width) = size.width {
+            for (location, height) in locations {
+                if let Some(height) = height {
+                    ctx.pdf
+                        .links
+                        .add(&location, (width, height), self.target.clone());
+                }
+            }
+        }
+
+        size
+    }
+}
+
+/// Gives the position where the element starts a name that [LinkTarget::Anchor] can refer to.
+pub struct Anchor<'a, E: Element> {
+    pub element: &'a E,
+    pub name: &'a str,
+}
+
+impl<'a, E: Element> Element for Anchor<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let (size, locations) = draw_recording_locations(
+            self.element,
+            DrawCtx {
+                pdf: &mut *ctx.pdf,
+                ..ctx
+            },
+        );
+
+        if let Some(location) = start_location(&locations) {
+            ctx.pdf
+                .links
+                .add_anchor(self.name, Destination::from_location(location));
+        }
+
+        size
+    }
+}
+
+/// The location the element actually starts on, which isn't the first one if the element broke
+/// before drawing anything.
+pub(crate) fn start_location(locations: &[(Location, Option<f64>)]) -> Option<&Location> {
+    locations
+        .iter()
+        .find(|(_, height)| height.is_some())
+        .or(locations.first())
+        .map(|(location, _)| location)
+}
+
+/// Draws the element and returns each location it was drawn on together with the height it used
+/// there. The heights come from the breaks and from the returned size for the last location.
+pub(crate) fn draw_recording_locations<E: Element + ?Sized>(
+    element: &E,
+    ctx: DrawCtx,
+) -> (ElementSize, Vec<(Location, Option<f64>)>) {
+    let mut locations: Vec<Option<(Location, Option<f64>)>> =
+        vec![Some((ctx.location.clone(), None))];
+
+    let size = if let Some(breakable) = ctx.breakable {
+        element.draw(DrawCtx {
+            pdf: ctx.pdf,
+            location: ctx.location,
+            breakable: Some(BreakableDraw {
+                do_break: &mut |pdf, location_idx, height| {
+                    let new_location = (breakable.do_break)(pdf, location_idx, height);
+                    let idx = location_idx as usize;
+
+                    if locations.len() < idx + 2 {
+                        locations.resize(idx + 2, None);
+                    }
+
+                    // The same break can be performed multiple times, but only the first one
+                    // counts.
+                    if locations[idx + 1].is_none() {
+                        if let Some((_, ref mut previous_height)) = locations[idx] {
+                            *previous_height = height;
+                        }
+
+                        locations[idx + 1] = Some((new_location.clone(), None));
+                    }
+
+                    new_location
+                },
+                ..breakable
+            }),
+            ..ctx
+        })
+    } else {
+        element.draw(ctx)
+    };
+
+    if let Some(Some((_, height))) = locations.last_mut() {
+        *height = size.height;
+    }
+
+    (size, locations.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::{
+        indices::{PdfLayerIndex, PdfPageIndex},
+        PdfDocument,
+    };
+
+    use super::*;
+    use crate::test_utils::FranticJumper;
+
+    #[test]
+    fn test_draw_recording_locations() {
+        let (doc, page, layer) = PdfDocument::new("test", Mm(10.), Mm(10.), "Layer 0");
+
+        let mut pdf = Pdf::new(doc, (10., 10.));
+
+        let layer = pdf.document.get_page(page).get_layer(layer);
+        let mut page_count = 1;
+
+        let element = FranticJumper {
+            jumps: vec![(0, Some(1.)), (0, Some(1.)), (2, Some(3.))],
+            size: ElementSize {
+                width: Some(4.),
+                height: Some(5.),
+            },
+        };
+
+        let (size, locations) = draw_recording_locations(
+            &element,
+            DrawCtx {
+                pdf: &mut pdf,
+                location: Location {
+                    layer,
+                    pos: (1., 8.),
+                    scale_factor: 1.,
+                },
+                width: WidthConstraint {
+                    max: 10.,
+                    expand: false,
+                },
+                first_height: 8.,
+                preferred_height: None,
+                breakable: Some(BreakableDraw {
+                    full_height: 10.,
+                    preferred_height_break_count: 0,
+                    do_break: &mut |pdf, location_idx, _| {
+                        while page_count <= location_idx + 1 {
+                            pdf.document.add_page(Mm(10.), Mm(10.), "Layer 0");
+                            page_count += 1;
+                        }
+
+                        Location {
+                            layer: pdf
+                                .document
+                                .get_page(PdfPageIndex(location_idx as usize + 1))
+                                .get_layer(PdfLayerIndex(0)),
+                            pos: (0., 10.),
+                            scale_factor: 1.,
+                        }
+                    },
+                }),
+            },
+        );
+
+        assert_eq!(size.height, Some(5.));
+
+        let recorded: Vec<(usize, (f64, f64), Option<f64>)> = locations
+            .iter()
+            .map(|(location, height)| (location.layer.page.0, location.pos, *height))
+            .collect();
+
+        // Nothing breaks from the second location, so its height is unknown and the location
+        // after it isn't there at all.
+        assert_eq!(
+            recorded,
+            [
+                (0, (1., 8.), Some(1.)),
+                (1, (0., 10.), None),
+                (3, (0., 10.), Some(5.)),
+            ]
+        );
+
+        assert_eq!(
+            start_location(&locations).map(|location| location.layer.page.0),
+            Some(0)
+        );
+    }
+}
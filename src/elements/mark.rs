@@ -0,0 +1,40 @@
+use crate::{links::Destination, *};
+
+use super::link::{draw_recording_locations, start_location};
+
+/// Sets a value for running headers where the element starts, for example the title of the
+/// current chapter. The decorations of [super::page::Page] can show the first or the last value
+/// of a mark on their page.
+pub struct Mark<'a, E: Element> {
+    pub element: &'a E,
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+impl<'a, E: Element> Element for Mark<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let (size, locations) = draw_recording_locations(
+            self.element,
+            DrawCtx {
+                pdf: &mut *ctx.pdf,
+                ..ctx
+            },
+        );
+
+        if let Some(location) = start_location(&locations) {
+            ctx.pdf
+                .marks
+                .add(self.name, self.value, Destination::from_location(location));
+        }
+
+        size
+    }
+}
@@ -269,7 +269,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let content = Text::basic(LOREM_IPSUM, &font, 12.);
                 let content = &content.debug(1).show_max_width();
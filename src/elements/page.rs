@@ -1,4 +1,6 @@
-use crate::*;
+use crate::{import::PdfPage, structure::draw_artifact, *};
+
+use super::imported_page::ImportedPage;
 
 pub struct Page<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> {
     pub primary: &'a P,
@@ -6,6 +8,14 @@ pub struct Page<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> {
     pub border_right: f64,
     pub border_top: f64,
     pub border_bottom: f64,
+
+    /// Called for every page with the index of the page and the number of pages of this [Page].
+    ///
+    /// The decorations are drawn after the whole primary content of this [Page]. Content and
+    /// sections after this [Page] element aren't drawn yet, so [DecorationElements::page_count] is
+    /// `None` unless the document is built with [crate::build_pdf_two_pass] or
+    /// [crate::build_pdf_sections_two_pass]. The number of pages passed to the closure is always
+    /// known, but only covers this [Page] and not the whole document.
     pub decoration_elements: D,
 }
 
@@ -202,8 +212,36 @@ pub enum Y {
 }
 
 impl<'a> DecorationElements<'a> {
+    /// The number of the page in the document, which can differ from the index the decoration
+    /// closure gets, as that only counts the pages of this [Page].
+    pub fn page_number(&self) -> usize {
+        self.pdf.page_numbers.number(self.page())
+    }
+
+    /// The number of pages in the document. This is only known when the document is built with
+    /// [crate::build_pdf_two_pass], as the decorations are drawn before the rest of the document.
+    pub fn page_count(&self) -> Option<usize> {
+        self.pdf.page_numbers.resolve_page_count()
+    }
+
+    /// The first value of a [super::mark::Mark] on this page, or the last one before it if there
+    /// is none on the page.
+    pub fn first_mark(&self, name: &str) -> Option<String> {
+        self.pdf.marks.first(name, self.page()).map(str::to_string)
+    }
+
+    /// The last value of a [super::mark::Mark] on this page, or the last one before it if there is
+    /// none on the page.
+    pub fn last_mark(&self, name: &str) -> Option<String> {
+        self.pdf.marks.last(name, self.page()).map(str::to_string)
+    }
+
+    fn page(&self) -> usize {
+        self.location.layer.page.0
+    }
+
     pub fn add(&mut self, element: &impl Element, pos: (X, Y), width: Option<f64>) {
-        element.draw(DrawCtx {
+        let ctx = DrawCtx {
             pdf: self.pdf,
             location: Location {
                 layer: self.location.layer.clone(),
@@ -232,7 +270,20 @@ impl<'a> DecorationElements<'a> {
             },
             preferred_height: None,
             breakable: None,
-        });
+        };
+
+        // Decorations repeat on every page, so they aren't part of the content.
+        draw_artifact(ctx, |ctx| element.draw(ctx));
+    }
+
+    /// Draws a page of another document behind the content, like a letterhead. It's scaled to the
+    /// width of the page, ignoring the borders.
+    pub fn add_background(&mut self, page: &PdfPage) {
+        self.add(
+            &ImportedPage { page, alt: None },
+            (X::Left(0.), Y::Top(0.)),
+            Some(self.width),
+        );
     }
 }
 
@@ -369,4 +420,91 @@ mod tests {
 
         assert_debug_snapshot!(output);
     }
+
+    #[test]
+    fn test_page_numbers_and_marks() {
+        use std::cell::RefCell;
+
+        use crate::{
+            elements::{column::Column, mark::Mark},
+            sections::{Orientation, Section},
+        };
+
+        let first = FakeText {
+            lines: 6,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let second = FakeText {
+            lines: 1,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let primary = Column {
+            content: |content| {
+                content
+                    .add(&Mark {
+                        element: &first,
+                        name: "chapter",
+                        value: "1",
+                    })?
+                    .add(&Mark {
+                        element: &second,
+                        name: "chapter",
+                        value: "2",
+                    })?;
+
+                None
+            },
+            gap: 0.,
+            collapse: true,
+        };
+
+        let pages = RefCell::new(Vec::new());
+
+        let page = Page {
+            primary: &primary,
+            border_left: 0.,
+            border_right: 0.,
+            border_top: 0.,
+            border_bottom: 0.,
+            decoration_elements: |content: &mut DecorationElements, _, _| {
+                pages.borrow_mut().push((
+                    content.page_number(),
+                    content.page_count(),
+                    content.first_mark("chapter"),
+                    content.last_mark("chapter"),
+                ));
+            },
+        };
+
+        crate::build_pdf_sections_two_pass(
+            "test",
+            |_| Ok(()),
+            |sections, _| {
+                sections.add(&Section {
+                    page_size: (10., 20.),
+                    orientation: Orientation::Portrait,
+                    restart_page_numbers: None,
+                    element: &page,
+                });
+            },
+        )
+        .unwrap();
+
+        let mark = |value: &str| Some(value.to_string());
+
+        // The first pass doesn't know the page count yet.
+        assert_eq!(
+            pages.into_inner(),
+            [
+                (1, None, mark("1"), mark("1")),
+                (2, None, mark("2"), mark("2")),
+                (1, Some(2), mark("1"), mark("1")),
+                (2, Some(2), mark("2"), mark("2")),
+            ]
+        );
+    }
 }
@@ -225,7 +225,7 @@ mod tests {
     #[test]
     fn test() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = Text::basic(LOREM_IPSUM, &font, 32.);
             let content = content.debug(1);
@@ -249,7 +249,7 @@ mod tests {
     #[test]
     fn test_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = NoneElement;
             let content = content.debug(1);
@@ -273,7 +273,7 @@ mod tests {
     #[test]
     fn test_no_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = NoneElement;
             let content = content.debug(1);
@@ -302,7 +302,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let content = NoneElement;
                 let content = content.debug(1);
@@ -327,7 +327,7 @@ mod tests {
     #[test]
     fn test_multipage_no_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = FranticJumper {
                 jumps: vec![(0, None), (0, None), (2, Some(32.)), (3, Some(55.))],
@@ -357,7 +357,7 @@ mod tests {
     #[test]
     fn test_multipage_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = FranticJumper {
                 jumps: vec![(1, None), (1, None), (3, Some(32.)), (4, None)],
@@ -392,7 +392,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let title = Text::basic("title", &font, 12.);
                 let title = &title.debug(1);
 
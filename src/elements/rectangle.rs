@@ -1,6 +1,6 @@
 use printpdf::{utils::calculate_points_for_rect, Line};
 
-use crate::{utils::*, *};
+use crate::{structure::draw_artifact, utils::*, *};
 
 pub struct Rectangle {
     pub size: (f64, f64),
@@ -25,7 +25,13 @@ impl Element for Rectangle {
         size(self)
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_artifact(ctx, |ctx| self.draw_untagged(ctx))
+    }
+}
+
+impl Rectangle {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         let outline_thickness = outline_thickness(self);
         ctx.break_if_appropriate_for_min_height(self.size.1 + outline_thickness);
 
@@ -291,7 +291,7 @@ mod tests {
     #[test]
     fn test() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = Text::basic(LOREM_IPSUM, &font, 32.);
             let content = content.debug(1);
@@ -315,7 +315,7 @@ mod tests {
     #[test]
     fn test_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = NoneElement;
             let content = content.debug(1);
@@ -339,7 +339,7 @@ mod tests {
     #[test]
     fn test_no_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = NoneElement;
             let content = content.debug(1);
@@ -368,7 +368,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
 
                 let content = NoneElement;
                 let content = content.debug(1);
@@ -393,7 +393,7 @@ mod tests {
     #[test]
     fn test_multipage_no_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = FranticJumper {
                 jumps: vec![(0, None), (0, None), (2, Some(32.)), (3, Some(55.))],
@@ -423,7 +423,7 @@ mod tests {
     #[test]
     fn test_multipage_collapse() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = FranticJumper {
                 jumps: vec![(1, None), (1, None), (3, Some(32.)), (4, None)],
@@ -458,7 +458,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let title = Text::basic("title", &font, 12.);
                 let title = &title.debug(1);
 
@@ -1,10 +1,19 @@
+use std::borrow::Cow;
+
+use crate::elements::text::{TextAlign, TextDirection};
+use crate::fonts::family::{FontFamily, FontStretch, FontStyle, BOLD_WEIGHT, NORMAL_WEIGHT};
 use crate::fonts::Font;
 use crate::fonts::GeneralMetrics;
+use crate::hyphenation::Hyphenator;
+use crate::links::LinkTarget;
+use crate::structure::{draw_tagged, StructureType};
 use crate::text::remove_non_trailing_soft_hyphens;
 use crate::text::*;
 use crate::utils::*;
 use crate::{text::text_width, *};
 
+use itertools::Itertools;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -14,6 +23,35 @@ pub struct Span {
     pub italic: bool,
     pub underline: bool,
     pub color: u32,
+
+    /// Overrides the weight implied by `bold`, for families with light, medium or semibold faces.
+    #[serde(default)]
+    pub weight: Option<u16>,
+
+    #[serde(default)]
+    pub stretch: FontStretch,
+
+    /// Makes the text of the span a link to the URL.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl Span {
+    pub fn weight(&self) -> u16 {
+        self.weight.unwrap_or(if self.bold {
+            BOLD_WEIGHT
+        } else {
+            NORMAL_WEIGHT
+        })
+    }
+
+    pub fn style(&self) -> FontStyle {
+        if self.italic {
+            FontStyle::Italic
+        } else {
+            FontStyle::Normal
+        }
+    }
 }
 
 pub struct RichText<'a, F: Font> {
@@ -21,7 +59,13 @@ pub struct RichText<'a, F: Font> {
     pub size: f64,
     pub small_size: f64,
     pub extra_line_height: f64,
-    pub fonts: FontSet<'a, F>,
+    pub fonts: FontFamily<'a, F>,
+    pub align: TextAlign,
+    pub direction: TextDirection,
+
+    /// When set, soft hyphens are inserted at the hyphenation points of the words in the spans
+    /// before breaking them into lines.
+    pub hyphenation: Option<&'a Hyphenator>,
 }
 
 pub struct LineFragment<'a, F: Font> {
@@ -36,8 +80,13 @@ pub struct LineFragment<'a, F: Font> {
     bold: bool,
     underline: bool,
     color: u32,
+    url: Option<&'a str>,
     ascent: f64,
     new_line: bool,
+
+    // whether the previous line ended with a mandatory break
+    after_hard_break: bool,
+
     x_offset: f64,
 }
 
@@ -63,13 +112,29 @@ pub struct LineFragmentTrimmed<'a, F: Font> {
 
     underline: bool,
     color: u32,
+    url: Option<&'a str>,
     ascent: f64,
     new_line: bool,
+    after_hard_break: bool,
     x_offset: f64,
 }
 
 impl<'a, F: Font> RichText<'a, F> {
-    fn pieces(&'a self, width: f64) -> (impl Iterator<Item = LineFragment<'a, F>> + 'a, f64) {
+    fn span_texts(&self) -> Vec<Cow<'a, str>> {
+        self.spans
+            .iter()
+            .map(|span| match self.hyphenation {
+                Some(hyphenator) => hyphenator.hyphenate(&span.text),
+                None => Cow::Borrowed(&span.text[..]),
+            })
+            .collect()
+    }
+
+    fn pieces<'b>(
+        &'b self,
+        texts: &'b [Cow<'b, str>],
+        width: f64,
+    ) -> (impl Iterator<Item = LineFragment<'b, F>> + 'b, f64) {
         #[derive(Copy, Clone)]
         struct FontVars {
             ascent: f64,
@@ -99,18 +164,13 @@ impl<'a, F: Font> RichText<'a, F> {
             LineGenerator::new(text, text_width)
         }
 
-        let regular_vars = font_vars(self.fonts.regular, self.size as f64);
-        let bold_vars = font_vars(self.fonts.bold, self.size as f64);
-        let italic_vars = font_vars(self.fonts.italic, self.size as f64);
-        let bold_italic_vars = font_vars(self.fonts.bold_italic, self.size as f64);
+        let line_height = self
+            .fonts
+            .fonts()
+            .map(|font| font_vars(font, self.size).line_height)
+            .fold(0., f64::max);
 
-        let line_height = regular_vars
-            .line_height
-            .max(bold_vars.line_height)
-            .max(italic_vars.line_height)
-            .max(bold_italic_vars.line_height);
-
-        let mut spans = self.spans.iter();
+        let mut spans = self.spans.iter().zip(texts);
         let mut generator = None;
 
         #[derive(PartialEq, Eq)]
@@ -125,40 +185,37 @@ impl<'a, F: Font> RichText<'a, F> {
         let mut line_state = FirstLine;
 
         let mut x_offset = 0.;
+        let mut hard_break = false;
 
         (
             std::iter::from_fn(move || {
                 loop {
                     match generator {
                         None => {
-                            if let Some(span) = spans.next() {
+                            if let Some((span, text)) = spans.next() {
                                 // this way we make sure the generator has at least one item
-                                if span.text.len() > 0 {
-                                    let (font, font_vars): (&F, FontVars) =
-                                        match (span.bold, span.italic) {
-                                            (false, false) => (self.fonts.regular, regular_vars),
-                                            (false, true) => (self.fonts.italic, italic_vars),
-                                            (true, false) => (self.fonts.bold, bold_vars),
-                                            (true, true) => {
-                                                (self.fonts.bold_italic, bold_italic_vars)
-                                            }
-                                        };
+                                if text.len() > 0 {
+                                    let font = self.fonts.resolve(
+                                        span.weight(),
+                                        span.style(),
+                                        span.stretch,
+                                    );
 
                                     generator = Some((
-                                        mk_gen(&span.text, font, self.size),
+                                        mk_gen(text, font, self.size),
                                         font,
-                                        font_vars,
-                                        span.bold,
-                                        span.italic,
+                                        font_vars(font, self.size),
+                                        span.weight() >= 600,
                                         span.underline,
                                         span.color,
+                                        span.url.as_deref(),
                                     ));
                                 }
                             } else {
                                 break None;
                             }
                         }
-                        Some((ref mut gen, font, font_vars, bold, _italic, underline, color)) => {
+                        Some((ref mut gen, font, font_vars, bold, underline, color, url)) => {
                             let next = if let FirstLine | LineDone = line_state {
                                 gen.next(mm_to_pt(width), false)
                             } else {
@@ -169,6 +226,9 @@ impl<'a, F: Font> RichText<'a, F> {
                                 let new_line = line_state == LineDone;
                                 line_state = LineDone;
 
+                                let after_hard_break = hard_break;
+                                hard_break = gen.paragraph_end() && !gen.done();
+
                                 let trimmed = next.trim_end();
                                 let length_trimmed =
                                     pt_to_mm(text_width(trimmed, self.size, font, 0., 0.));
@@ -200,8 +260,10 @@ impl<'a, F: Font> RichText<'a, F> {
                                     bold,
                                     underline,
                                     color,
+                                    url,
                                     ascent: font_vars.ascent,
                                     new_line,
+                                    after_hard_break,
                                     x_offset: ret_x_offset,
                                 });
                             } else {
@@ -217,11 +279,12 @@ impl<'a, F: Font> RichText<'a, F> {
         )
     }
 
-    fn pieces_trimmed(
-        &'a self,
+    fn pieces_trimmed<'b>(
+        &'b self,
+        texts: &'b [Cow<'b, str>],
         width: f64,
-    ) -> (impl Iterator<Item = LineFragmentTrimmed<'a, F>> + 'a, f64) {
-        let (mut iter, line_height) = self.pieces(width);
+    ) -> (impl Iterator<Item = LineFragmentTrimmed<'b, F>> + 'b, f64) {
+        let (mut iter, line_height) = self.pieces(texts, width);
 
         let mut last = iter.next();
 
@@ -253,8 +316,10 @@ impl<'a, F: Font> RichText<'a, F> {
                         bold: last_frag.bold,
                         underline: last_frag.underline,
                         color: last_frag.color,
+                        url: last_frag.url,
                         ascent: last_frag.ascent,
                         new_line: last_frag.new_line,
+                        after_hard_break: last_frag.after_hard_break,
                         x_offset: last_frag.x_offset,
                     })
                 } else {
@@ -264,11 +329,143 @@ impl<'a, F: Font> RichText<'a, F> {
             line_height,
         )
     }
+
+    /// Returns whether each line is right-to-left.
+    fn line_directions(&self, lines: &[&[LineFragmentTrimmed<F>]]) -> Vec<bool> {
+        let starts_paragraph =
+            |line: &&[LineFragmentTrimmed<F>]| line.first().map_or(false, |f| f.after_hard_break);
+
+        let mut rtl = false;
+
+        (0..lines.len())
+            .map(|i| {
+                if i == 0 || starts_paragraph(&lines[i]) {
+                    rtl = match self.direction {
+                        TextDirection::Auto => lines[i..]
+                            .iter()
+                            .enumerate()
+                            .take_while(|&(j, line)| j == 0 || !starts_paragraph(line))
+                            .flat_map(|(_, line)| line.iter())
+                            .find_map(|frag| paragraph_direction(frag.text))
+                            .unwrap_or(false),
+                        TextDirection::LeftToRight => false,
+                        TextDirection::RightToLeft => true,
+                    };
+                }
+
+                rtl
+            })
+            .collect()
+    }
+
+    /// Returns the x offset and the extra width per space for each line.
+    fn line_alignments(&self, frags: &[LineFragmentTrimmed<F>], width: f64) -> Vec<(f64, f64)> {
+        let lines = split_lines(frags);
+        let directions = self.line_directions(&lines);
+
+        lines
+            .iter()
+            .zip(directions)
+            .enumerate()
+            .map(|(i, (line, rtl))| {
+                let last = match line.last() {
+                    Some(last) => last,
+                    None => return (0., 0.),
+                };
+
+                let line_width = last.x_offset + last.length;
+
+                let paragraph_end = lines.get(i + 1).map_or(true, |next| {
+                    next.first().map_or(true, |f| f.after_hard_break)
+                });
+                let space_count: usize =
+                    line.iter().map(|frag| frag.text.matches(' ').count()).sum();
+
+                if self.align == TextAlign::Justify
+                    && !paragraph_end
+                    && space_count > 0
+                    && width > line_width
+                {
+                    return (0., (width - line_width) / space_count as f64);
+                }
+
+                let x_offset = match self.align.resolve(rtl) {
+                    TextAlign::Center => (width - line_width) / 2.,
+                    TextAlign::Right => width - line_width,
+                    TextAlign::Justify if rtl => width - line_width,
+                    _ => 0.,
+                };
+
+                (x_offset, 0.)
+            })
+            .collect()
+    }
+}
+
+/// Splits the fragments into lines. The first line is empty if the first fragment starts a new
+/// line.
+fn split_lines<'c, 'b, F: Font>(
+    frags: &'c [LineFragmentTrimmed<'b, F>],
+) -> Vec<&'c [LineFragmentTrimmed<'b, F>]> {
+    let line_starts = std::iter::once(0).chain(frags.iter().positions(|frag| frag.new_line));
+    let line_ends = frags
+        .iter()
+        .positions(|frag| frag.new_line)
+        .chain(std::iter::once(frags.len()));
+
+    line_starts
+        .zip(line_ends)
+        .map(|(start, end)| &frags[start..end])
+        .collect()
+}
+
+/// Splits a line into pieces that have a single fragment and direction, in visual order.
+fn visual_pieces<'c, 'b, F: Font>(
+    line: &'c [LineFragmentTrimmed<'b, F>],
+    rtl: bool,
+) -> Vec<(&'c LineFragmentTrimmed<'b, F>, String, bool)> {
+    let texts: Vec<String> = line
+        .iter()
+        .map(|frag| remove_non_trailing_soft_hyphens(frag.text))
+        .collect();
+
+    let line_text = texts.concat();
+
+    let mut starts = Vec::with_capacity(texts.len());
+    let mut offset = 0;
+
+    for text in &texts {
+        starts.push(offset);
+        offset += text.len();
+    }
+
+    let mut pieces = Vec::new();
+
+    for (run, run_rtl) in visual_runs(&line_text, rtl) {
+        let run_pieces =
+            line.iter()
+                .zip(&texts)
+                .zip(&starts)
+                .filter_map(|((frag, text), &start)| {
+                    let from = run.start.max(start);
+                    let to = run.end.min(start + text.len());
+
+                    (from < to).then(|| (frag, line_text[from..to].to_string(), run_rtl))
+                });
+
+        if run_rtl {
+            pieces.extend(run_pieces.rev());
+        } else {
+            pieces.extend(run_pieces);
+        }
+    }
+
+    pieces
 }
 
 impl<'a, F: Font> Element for RichText<'a, F> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
-        let (_, line_height) = self.pieces_trimmed(ctx.width.max);
+        let (_, line_height) = self.pieces_trimmed(&[], ctx.width.max);
         let line_height = line_height + self.extra_line_height;
 
         if ctx.first_height < line_height {
@@ -281,7 +478,8 @@ impl<'a, F: Font> Element for RichText<'a, F> {
     fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
         let mut max_width = ctx.width.constrain(0.);
 
-        let (iter, line_height) = self.pieces_trimmed(ctx.width.max);
+        let texts = self.span_texts();
+        let (iter, line_height) = self.pieces_trimmed(&texts, ctx.width.max);
         let line_height = line_height + self.extra_line_height;
 
         let mut height_available = ctx.first_height;
@@ -322,12 +520,45 @@ impl<'a, F: Font> Element for RichText<'a, F> {
         }
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_tagged(ctx, StructureType::Paragraph, None, |ctx| {
+            self.draw_untagged(ctx)
+        })
+    }
+}
+
+impl<'a, F: Font> RichText<'a, F> {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         let mut max_width = ctx.width.constrain(0.);
 
-        let (iter, line_height) = self.pieces_trimmed(ctx.width.max);
+        let texts = self.span_texts();
+        let (iter, line_height) = self.pieces_trimmed(&texts, ctx.width.max);
         let line_height = line_height + self.extra_line_height;
 
+        let frags: Vec<_> = iter.collect();
+
+        // Like in Text, left alignment doesn't need the line widths.
+        let left_aligned = match self.align {
+            TextAlign::Left => true,
+            TextAlign::Start => !texts.iter().any(|text| self.direction.may_be_rtl(text)),
+            _ => false,
+        };
+
+        let width = if ctx.width.expand {
+            ctx.width.max
+        } else if left_aligned {
+            0.
+        } else {
+            frags
+                .iter()
+                .map(|frag| frag.x_offset + frag.length)
+                .fold(0., f64::max)
+        };
+
+        let lines = split_lines(&frags);
+        let directions = self.line_directions(&lines);
+        let alignments = self.line_alignments(&frags, width);
+
         let mut x = ctx.location.pos.0;
         let mut y = ctx.location.pos.1;
 
@@ -348,14 +579,19 @@ impl<'a, F: Font> Element for RichText<'a, F> {
 
         let mut line_count = 1;
 
-        for frag in iter {
-            let pdf_font = &frag.font.indirect_font_ref();
-
-            let line_width = frag.length;
+        for ((line, &rtl), &(line_offset, extra_space_width)) in
+            lines.iter().zip(&directions).zip(&alignments)
+        {
+            let first = match line.first() {
+                Some(first) => first,
+                None => continue,
+            };
 
-            max_width = max_width.max(frag.x_offset + line_width);
+            for frag in line.iter() {
+                max_width = max_width.max(frag.x_offset + frag.length);
+            }
 
-            if frag.new_line {
+            if first.new_line {
                 match ctx.breakable {
                     Some(ref mut breakable) if height_available < 2. * line_height => {
                         let new_location = (breakable.do_break)(
@@ -379,36 +615,72 @@ impl<'a, F: Font> Element for RichText<'a, F> {
                 }
             }
 
-            ctx.location.layer.save_graphics_state();
-            ctx.location
-                .layer
-                .set_fill_color(u32_to_color_and_alpha(frag.color).0);
-            ctx.location.layer.use_text(
-                &remove_non_trailing_soft_hyphens(frag.text),
-                frag.size,
-                Mm(x + frag.x_offset),
-                Mm(y - frag.ascent),
-                pdf_font,
-            );
-
-            // This isn't quite correct currently. The truetype format has underline position and
-            // thickness information in the `post` table. This information is however not
-            // exposed in the `stb_truetype` crate. To get this information we'll have to switch
-            // to another crate, such as `ttf-parser`, which exposes the `post` table and the
-            // underline information. For now we'll just use some hard-coded values that look
-            // mostly right.
-            if frag.underline {
+            let mut frag_x = x + line_offset;
+
+            for (frag, text, run_rtl) in visual_pieces(line, rtl) {
+                let units_per_em = frag.font.units_per_em() as f64;
+
+                let glyphs = frag.font.shape(&text, run_rtl);
+                let space_count = text.matches(' ').count();
+
+                let advance: f64 = glyphs.iter().map(|glyph| glyph.x_advance).sum();
+                let piece_width = pt_to_mm(advance * frag.size / units_per_em)
+                    + space_count as f64 * extra_space_width;
+
+                ctx.location.layer.save_graphics_state();
+                ctx.location
+                    .layer
+                    .set_fill_color(u32_to_color_and_alpha(frag.color).0);
+
+                ctx.location.layer.begin_text_section();
                 ctx.location
                     .layer
-                    .set_outline_color(u32_to_color_and_alpha(frag.color).0);
-                crate::utils::line(
+                    .set_font(frag.font.indirect_font_ref(), frag.size);
+                ctx.location
+                    .layer
+                    .set_text_cursor(Mm(frag_x), Mm(y - frag.ascent));
+                frag.font.write_glyphs(
+                    ctx.pdf,
                     &ctx.location.layer,
-                    [x + frag.x_offset, y - frag.ascent - 1.0],
-                    pt_to_mm(text_width(frag.text, frag.size, frag.font, 0., 0.)),
-                    pt_to_mm(if frag.bold { 1.0 } else { 0.5 }),
+                    frag.size,
+                    &text,
+                    &glyphs,
+                    mm_to_pt(extra_space_width) * units_per_em / frag.size,
                 );
+                ctx.location.layer.end_text_section();
+
+                // This isn't quite correct currently. The truetype format has underline position
+                // and thickness information in the `post` table. This information is however not
+                // exposed in the `stb_truetype` crate. To get this information we'll have to
+                // switch to another crate, such as `ttf-parser`, which exposes the `post` table
+                // and the underline information. For now we'll just use some hard-coded values
+                // that look mostly right.
+                if frag.underline {
+                    ctx.location
+                        .layer
+                        .set_outline_color(u32_to_color_and_alpha(frag.color).0);
+                    crate::utils::line(
+                        &ctx.location.layer,
+                        [frag_x, y - frag.ascent - 1.0],
+                        piece_width,
+                        pt_to_mm(if frag.bold { 1.0 } else { 0.5 }),
+                    );
+                }
+                ctx.location.layer.restore_graphics_state();
+
+                if let Some(url) = frag.url {
+                    ctx.pdf.links.add(
+                        &Location {
+                            pos: (frag_x, y),
+                            ..ctx.location.clone()
+                        },
+                        (piece_width, line_height),
+                        LinkTarget::Uri(url.to_string()),
+                    );
+                }
+
+                frag_x += piece_width;
             }
-            ctx.location.layer.restore_graphics_state();
         }
 
         ElementSize {
@@ -424,7 +696,10 @@ mod tests {
 
     use crate::{
         fonts::builtin::BuiltinFont,
-        test_utils::{ElementProxy, ElementTestParams},
+        test_utils::{
+            binary_snapshots::{shown_text, test_element_bytes, TestElementParams},
+            ElementProxy, ElementTestParams,
+        },
     };
 
     use super::*;
@@ -434,6 +709,11 @@ mod tests {
         // A fake document for adding the fonts to.
         let doc = PdfDocument::empty("i contain a font");
 
+        let regular = BuiltinFont::courier(&doc).unwrap();
+        let bold = BuiltinFont::courier_bold(&doc).unwrap();
+        let italic = BuiltinFont::courier_oblique(&doc).unwrap();
+        let bold_italic = BuiltinFont::courier_bold_oblique(&doc).unwrap();
+
         let text_element = RichText {
             spans: &[
                 Span {
@@ -442,6 +722,9 @@ mod tests {
                     italic: false,
                     underline: false,
                     color: 0,
+                    weight: None,
+                    stretch: FontStretch::Normal,
+                    url: None,
                 },
                 Span {
                     text: "sum dol ".to_string(),
@@ -449,6 +732,9 @@ mod tests {
                     italic: true,
                     underline: false,
                     color: 0,
+                    weight: None,
+                    stretch: FontStretch::Normal,
+                    url: None,
                 },
                 Span {
                     text: "or sit amet".to_string(),
@@ -456,17 +742,24 @@ mod tests {
                     italic: true,
                     underline: false,
                     color: 0,
+                    weight: None,
+                    stretch: FontStretch::Normal,
+                    url: None,
                 },
             ],
             size: 12.,
             small_size: 12.,
             extra_line_height: 12.,
             fonts: FontSet {
-                regular: &BuiltinFont::courier(&doc),
-                bold: &BuiltinFont::courier_bold(&doc),
-                italic: &BuiltinFont::courier_oblique(&doc),
-                bold_italic: &BuiltinFont::courier_bold_oblique(&doc),
-            },
+                regular: &regular,
+                bold: &bold,
+                italic: &italic,
+                bold_italic: &bold_italic,
+            }
+            .into(),
+            align: TextAlign::Left,
+            direction: TextDirection::Auto,
+            hyphenation: None,
         };
 
         // Should be broken into lines like this:
@@ -524,4 +817,150 @@ mod tests {
             });
         }
     }
+
+    #[test]
+    fn test_line_alignments() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc).unwrap();
+
+        let spans = [Span {
+            text: "aa bb cc\ndd ee".to_string(),
+            bold: false,
+            italic: false,
+            underline: false,
+            color: 0,
+            weight: None,
+            stretch: FontStretch::Normal,
+            url: None,
+        }];
+
+        let letter_width = 2.5400016;
+        let width = letter_width * 7.;
+
+        let alignments = |align, direction| {
+            let text = RichText {
+                spans: &spans,
+                size: 12.,
+                small_size: 12.,
+                extra_line_height: 0.,
+                fonts: FontSet {
+                    regular: &font,
+                    bold: &font,
+                    italic: &font,
+                    bold_italic: &font,
+                }
+                .into(),
+                align,
+                direction,
+                hyphenation: None,
+            };
+
+            let texts = text.span_texts();
+            let frags: Vec<_> = text.pieces_trimmed(&texts, width).0.collect();
+            text.line_alignments(&frags, width)
+        };
+
+        let assert_close = |actual: Vec<(f64, f64)>, expected: &[(f64, f64)]| {
+            assert_eq!(actual.len(), expected.len());
+
+            for (a, e) in actual.iter().zip(expected) {
+                assert!((a.0 - e.0).abs() < 1e-6 && (a.1 - e.1).abs() < 1e-6);
+            }
+        };
+
+        // The lines are "aa bb", "cc" and "dd ee".
+        let ltr = TextDirection::Auto;
+        let rtl = TextDirection::RightToLeft;
+
+        assert_close(alignments(TextAlign::Left, ltr), &[(0., 0.); 3]);
+        assert_close(alignments(TextAlign::Start, ltr), &[(0., 0.); 3]);
+        assert_close(alignments(TextAlign::End, rtl), &[(0., 0.); 3]);
+
+        let right = [
+            (letter_width * 2., 0.),
+            (letter_width * 5., 0.),
+            (letter_width * 2., 0.),
+        ];
+
+        assert_close(alignments(TextAlign::Start, rtl), &right);
+        assert_close(
+            alignments(TextAlign::Right, ltr),
+            &[
+                (letter_width * 2., 0.),
+                (letter_width * 5., 0.),
+                (letter_width * 2., 0.),
+            ],
+        );
+        assert_close(
+            alignments(TextAlign::Center, ltr),
+            &[
+                (letter_width, 0.),
+                (letter_width * 2.5, 0.),
+                (letter_width, 0.),
+            ],
+        );
+
+        // The last line of each paragraph isn't stretched.
+        assert_close(
+            alignments(TextAlign::Justify, ltr),
+            &[(0., letter_width * 2.), (0., 0.), (0., 0.)],
+        );
+        assert_close(
+            alignments(TextAlign::Justify, rtl),
+            &[
+                (0., letter_width * 2.),
+                (letter_width * 5., 0.),
+                (letter_width * 2., 0.),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_hyphenation() {
+        let hyphenator =
+            Hyphenator::from_patterns("\\patterns{} \\hyphenation{do-nau-dampf-schiff-fahrt}");
+
+        let spans = [Span {
+            text: "Donaudampfschifffahrt".to_string(),
+            bold: false,
+            italic: false,
+            underline: false,
+            color: 0,
+            weight: None,
+            stretch: FontStretch::Normal,
+            url: None,
+        }];
+
+        // A Courier character is 2.54mm wide at this size, so a line has room for eleven of them.
+        let params = TestElementParams {
+            width: WidthConstraint {
+                max: 30.,
+                expand: false,
+            },
+            ..TestElementParams::unbreakable()
+        };
+
+        let bytes = test_element_bytes(params, |callback| {
+            let font = BuiltinFont::courier(callback.document()).unwrap();
+
+            callback.call(&RichText {
+                spans: &spans,
+                size: 12.,
+                small_size: 12.,
+                extra_line_height: 0.,
+                fonts: FontSet {
+                    regular: &font,
+                    bold: &font,
+                    italic: &font,
+                    bold_italic: &font,
+                }
+                .into(),
+                align: TextAlign::Left,
+                direction: TextDirection::Auto,
+                hyphenation: Some(&hyphenator),
+            });
+        });
+
+        assert_eq!(shown_text(&bytes), ["Donaudampf-", "schifffahrt"]);
+    }
 }
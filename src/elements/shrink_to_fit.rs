@@ -172,7 +172,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let text = Text::basic("TEST", &font, 100.);
                 let text = &text
                     .debug(1)
@@ -202,7 +202,7 @@ mod tests {
                 ..TestElementParams::unbreakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let text = Text::basic("TEST", &font, 100.);
                 let text = &text
                     .debug(1)
@@ -232,7 +232,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let text = Text::basic("T E S T", &font, 1024.);
                 let text = &text
                     .debug(1)
@@ -262,7 +262,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let text = Text::basic("Test", &font, 20.);
                 let text = &text
                     .debug(1)
@@ -295,7 +295,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let text = Text::basic("Test", &font, 100.);
                 let text = &text
                     .debug(1)
@@ -336,7 +336,7 @@ mod tests {
                 ..TestElementParams::breakable()
             },
             |callback| {
-                let font = BuiltinFont::courier(callback.document());
+                let font = BuiltinFont::courier(callback.document()).unwrap();
                 let text = Text::basic("Test", &font, 100.);
                 let text = &text
                     .debug(1)
@@ -1,9 +1,17 @@
 use printpdf::CurTransMat;
 
-use crate::{utils::pt_to_mm, *};
+use crate::{
+    structure::{draw_artifact, draw_tagged, StructureType},
+    utils::pt_to_mm,
+    *,
+};
 
 pub struct Svg<'a> {
     pub data: &'a usvg::Tree,
+
+    /// A description of the graphic for tagged documents. Without it the graphic is drawn as an
+    /// artifact, which is skipped by assistive technology.
+    pub alt: Option<&'a str>,
 }
 
 impl<'a> Element for Svg<'a> {
@@ -25,7 +33,18 @@ impl<'a> Element for Svg<'a> {
         element_size
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        match self.alt {
+            Some(alt) => draw_tagged(ctx, StructureType::Figure, Some(alt), |ctx| {
+                self.draw_untagged(ctx)
+            }),
+            None => draw_artifact(ctx, |ctx| self.draw_untagged(ctx)),
+        }
+    }
+}
+
+impl<'a> Svg<'a> {
+    pub(crate) fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         let (height, scale_factor, element_size) = calculate_size(self.data, ctx.width);
 
         ctx.break_if_appropriate_for_min_height(height);
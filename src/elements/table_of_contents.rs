@@ -0,0 +1,340 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    elements::{
+        column::Column,
+        link::Link,
+        padding::Padding,
+        row::{Flex, Row},
+        text::{Text, TextAlign},
+    },
+    fonts::{Font, GeneralMetrics},
+    links::LinkTarget,
+    text::text_width,
+    utils::pt_to_mm,
+    *,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TocEntry {
+    pub title: String,
+
+    /// The name of the [super::link::Anchor] or [super::bookmark::Bookmark] the entry points to.
+    pub anchor: String,
+
+    #[serde(default)]
+    pub level: u8,
+}
+
+/// Lists the entries with dotted leaders and the number of the page each anchor is on. Every
+/// entry links to its anchor.
+///
+/// The page numbers are only known once the anchors are drawn, so anchors that come after the
+/// table of contents need [crate::build_pdf_two_pass]. The layout doesn't depend on the numbers,
+/// which keeps the pages the same between the passes.
+pub struct TableOfContents<'a, F: Font> {
+    pub entries: &'a [TocEntry],
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+
+    /// The indentation per level in mm.
+    pub indent: f64,
+
+    /// The vertical gap between the entries in mm.
+    pub gap: f64,
+}
+
+impl<'a, F: Font> CompositeElement for TableOfContents<'a, F> {
+    fn element(&self, callback: impl CompositeElementCallback) {
+        // Wide enough for four digits, which the numbers are right aligned in.
+        let number_width = pt_to_mm(text_width("0000", self.size, self.font, 0., 0.));
+
+        callback.call(&Column {
+            content: |mut content| {
+                for entry in self.entries {
+                    let title = Text {
+                        color: self.color,
+                        ..Text::basic(&entry.title, self.font, self.size)
+                    };
+
+                    let leader = Leader { toc: self };
+                    let page_number = PageNumber {
+                        toc: self,
+                        anchor: &entry.anchor,
+                    };
+
+                    let row = Row {
+                        gap: pt_to_mm(self.size) / 2.,
+                        expand: true,
+                        collapse: false,
+                        content: |content| {
+                            content.add(&title, Flex::SelfSized);
+                            content.add(&leader, Flex::Expand(1));
+                            content.add(&page_number, Flex::Fixed(number_width));
+                        },
+                    };
+
+                    content = content.add(&Padding {
+                        left: self.indent * entry.level as f64,
+                        right: 0.,
+                        top: 0.,
+                        bottom: 0.,
+                        element: &Link {
+                            element: &row,
+                            target: LinkTarget::Anchor(entry.anchor.clone()),
+                        },
+                    })?;
+                }
+
+                None
+            },
+            gap: self.gap,
+            collapse: true,
+        });
+    }
+}
+
+impl<'a, F: Font> TableOfContents<'a, F> {
+    fn line_height(&self) -> f64 {
+        let GeneralMetrics { line_height, .. } = self.font.general_metrics();
+
+        pt_to_mm(line_height * self.size / self.font.units_per_em() as f64)
+    }
+
+    /// Draws a single line of text. The size is the same as in [Self::measure_line], so that it
+    /// doesn't depend on the text.
+    fn draw_line(&self, text: &str, ctx: DrawCtx) -> ElementSize {
+        let width = ctx.width.max;
+
+        if !text.is_empty() {
+            Text {
+                color: self.color,
+                align: TextAlign::Right,
+                ..Text::basic(text, self.font, self.size)
+            }
+            .draw(DrawCtx {
+                width: WidthConstraint {
+                    max: width,
+                    expand: true,
+                },
+                ..ctx
+            });
+        }
+
+        ElementSize {
+            width: Some(width),
+            height: Some(self.line_height()),
+        }
+    }
+
+    fn measure_line(&self, ctx: MeasureCtx) -> ElementSize {
+        let line_height = self.line_height();
+
+        ctx.break_if_appropriate_for_min_height(line_height);
+
+        ElementSize {
+            width: Some(ctx.width.max),
+            height: Some(line_height),
+        }
+    }
+}
+
+/// The dots between a title and its page number.
+struct Leader<'a, 'b, F: Font> {
+    toc: &'b TableOfContents<'a, F>,
+}
+
+impl<'a, 'b, F: Font> Element for Leader<'a, 'b, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_line_usage(self.toc.line_height(), ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.toc.measure_line(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let dot_width = pt_to_mm(text_width(". ", self.toc.size, self.toc.font, 0., 0.));
+        let count = (ctx.width.max / dot_width).floor().max(0.) as usize;
+
+        self.toc.draw_line(&" .".repeat(count), ctx)
+    }
+}
+
+struct PageNumber<'a, 'b, F: Font> {
+    toc: &'b TableOfContents<'a, F>,
+    anchor: &'b str,
+}
+
+impl<'a, 'b, F: Font> Element for PageNumber<'a, 'b, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        first_line_usage(self.toc.line_height(), ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.toc.measure_line(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let number = ctx
+            .pdf
+            .links
+            .resolve_anchor(self.anchor)
+            .map(|destination| ctx.pdf.page_numbers.number(destination.page).to_string())
+            .unwrap_or_default();
+
+        self.toc.draw_line(&number, ctx)
+    }
+}
+
+fn first_line_usage(line_height: f64, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+    if ctx.break_appropriate_for_min_height(line_height) {
+        FirstLocationUsage::WillSkip
+    } else {
+        FirstLocationUsage::WillUse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::PdfDocument;
+
+    use super::*;
+    use crate::{
+        elements::{force_break::ForceBreak, link::Anchor},
+        fonts::builtin::BuiltinFont,
+        sections::{Orientation, Section},
+        test_utils::{binary_snapshots::shown_text, *},
+    };
+
+    #[test]
+    fn test_table_of_contents() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc).unwrap();
+
+        let entries = [
+            TocEntry {
+                title: "Introduction".to_string(),
+                anchor: "intro".to_string(),
+                level: 0,
+            },
+            TocEntry {
+                title: "Details".to_string(),
+                anchor: "details".to_string(),
+                level: 1,
+            },
+        ];
+
+        let toc = TableOfContents {
+            entries: &entries,
+            font: &font,
+            size: 12.,
+            color: 0x00_00_00_FF,
+            indent: 5.,
+            gap: 1.,
+        };
+
+        let line_height = toc.line_height();
+
+        let element = ElementProxy {
+            before_draw: &|ctx: &mut DrawCtx| {
+                ctx.pdf
+                    .document
+                    .add_builtin_font(printpdf::BuiltinFont::Courier)
+                    .unwrap();
+            },
+            ..ElementProxy::new(toc)
+        };
+
+        for output in (ElementTestParams {
+            first_height: 20.,
+            full_height: 20.,
+            width: 100.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            let size = output.size;
+
+            assert!((size.width.unwrap() - 100.).abs() < 1e-9);
+            assert!((size.height.unwrap() - (line_height * 2. + 1.)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_page_numbers() {
+        let entries = [
+            TocEntry {
+                title: "Introduction".to_string(),
+                anchor: "intro".to_string(),
+                level: 0,
+            },
+            TocEntry {
+                title: "Details".to_string(),
+                anchor: "details".to_string(),
+                level: 1,
+            },
+        ];
+
+        let pdf = build_pdf_sections_two_pass("test", BuiltinFont::courier, |sections, font| {
+            let toc = TableOfContents {
+                entries: &entries,
+                font,
+                size: 12.,
+                color: 0x00_00_00_FF,
+                indent: 5.,
+                gap: 1.,
+            };
+
+            let introduction = Text::basic("Introduction", font, 12.);
+            let details = Text::basic("Details", font, 12.);
+
+            sections.add(&Section {
+                page_size: (100., 50.),
+                orientation: Orientation::Portrait,
+                restart_page_numbers: None,
+                element: &Column {
+                    content: |content| {
+                        content
+                            .add(&toc)?
+                            .add(&ForceBreak)?
+                            .add(&Anchor {
+                                element: &introduction,
+                                name: "intro",
+                            })?
+                            .add(&ForceBreak)?
+                            .add(&Anchor {
+                                element: &details,
+                                name: "details",
+                            })?;
+
+                        None
+                    },
+                    gap: 0.,
+                    collapse: true,
+                },
+            });
+        })
+        .unwrap();
+
+        let shown = shown_text(&pdf.to_bytes().unwrap());
+
+        let dots = |leader: &str| {
+            assert!(leader.chars().all(|c| c == ' ' || c == '.'));
+            leader.matches('.').count()
+        };
+
+        // A dot with its space is 5.08 mm wide in Courier at 12 pt. The leaders get what the title,
+        // the four digits of the number and the two gaps of 2.12 mm leave of the 100 mm, which is
+        // 55.13 mm for the first entry and 62.83 mm for the indented one.
+        assert_eq!(shown.len(), 8);
+        assert_eq!(shown[0], "Introduction");
+        assert_eq!(dots(&shown[1]), 10);
+        assert_eq!(shown[2], "2");
+        assert_eq!(shown[3], "Details");
+        assert_eq!(dots(&shown[4]), 12);
+        assert_eq!(shown[5], "3");
+        assert_eq!(shown[6..], ["Introduction", "Details"]);
+    }
+}
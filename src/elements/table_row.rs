@@ -1,5 +1,7 @@
 use crate::{
+    elements::tagged::Tagged,
     flex::{DrawLayout, MeasureLayout},
+    structure::{draw_grouped, StructureType},
     utils::{max_optional_size, mm_to_pt, u32_to_color_and_alpha},
     *,
 };
@@ -63,7 +65,13 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
         }
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_grouped(ctx, StructureType::TableRow, |ctx| self.draw_untagged(ctx))
+    }
+}
+
+impl<F: Fn(&mut RowContent)> TableRow<F> {
+    fn draw_untagged(&self, mut ctx: DrawCtx) -> ElementSize {
         let mut measure_layout = MeasureLayout::new(ctx.width.max, self.line_style.thickness);
 
         let mut max_height = None;
@@ -388,7 +396,11 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                     0.
                 };
 
-                let size = element.draw(DrawCtx {
+                let size = Tagged {
+                    element,
+                    structure_type: StructureType::TableDataCell,
+                }
+                .draw(DrawCtx {
                     pdf,
                     location: Location {
                         pos: (location.pos.0 + x_offset, location.pos.1),
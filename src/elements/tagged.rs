@@ -0,0 +1,27 @@
+use crate::{
+    structure::{draw_grouped, StructureType},
+    *,
+};
+
+/// Groups the structure elements of the content into a new one of the type in a tagged document,
+/// for example a custom type from the role map.
+pub struct Tagged<'a, E: Element> {
+    pub element: &'a E,
+    pub structure_type: StructureType,
+}
+
+impl<'a, E: Element> Element for Tagged<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_grouped(ctx, self.structure_type.clone(), |ctx| {
+            self.element.draw(ctx)
+        })
+    }
+}
@@ -1,8 +1,13 @@
-use printpdf::types::pdf_layer::GappedTextElement;
+use std::borrow::Cow;
 
 use crate::{
     fonts::{Font, GeneralMetrics},
-    text::{break_text_into_lines, remove_non_trailing_soft_hyphens, text_width},
+    hyphenation::Hyphenator,
+    structure::{draw_tagged, StructureType},
+    text::{
+        break_text_into_lines, has_rtl, paragraph_direction, remove_non_trailing_soft_hyphens,
+        shape_line, subslice_offset, text_width,
+    },
     utils::{mm_to_pt, pt_to_mm, u32_to_color_and_alpha},
     *,
 };
@@ -12,6 +17,59 @@ pub enum TextAlign {
     Left,
     Center,
     Right,
+
+    /// Stretches the spaces so that every line fills the width, except for the last line of each
+    /// paragraph, which is aligned like [TextAlign::Start]. Only the regular space (U+0020) is
+    /// stretched, so lines without one, for example with only no-break spaces, are aligned like
+    /// the last line.
+    Justify,
+
+    /// Left for left-to-right paragraphs and right for right-to-left ones.
+    Start,
+
+    /// Right for left-to-right paragraphs and left for right-to-left ones.
+    End,
+}
+
+/// The base direction of the paragraphs, which determines the order of runs with different
+/// directions and what [TextAlign::Start] and [TextAlign::End] mean.
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextDirection {
+    /// Determined for each paragraph from its first strong character.
+    Auto,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl TextDirection {
+    /// Whether the paragraph starting at the beginning of `text` is right-to-left.
+    pub fn is_rtl(self, text: &str) -> bool {
+        match self {
+            TextDirection::Auto => paragraph_direction(text).unwrap_or(false),
+            TextDirection::LeftToRight => false,
+            TextDirection::RightToLeft => true,
+        }
+    }
+
+    /// Whether any paragraph in the text could be right-to-left.
+    pub fn may_be_rtl(self, text: &str) -> bool {
+        match self {
+            TextDirection::Auto => has_rtl(text),
+            TextDirection::LeftToRight => false,
+            TextDirection::RightToLeft => true,
+        }
+    }
+}
+
+impl TextAlign {
+    /// Resolves [TextAlign::Start] and [TextAlign::End] for a paragraph direction.
+    pub fn resolve(self, rtl: bool) -> TextAlign {
+        match (self, rtl) {
+            (TextAlign::Start, false) | (TextAlign::End, true) => TextAlign::Left,
+            (TextAlign::Start, true) | (TextAlign::End, false) => TextAlign::Right,
+            (align, _) => align,
+        }
+    }
 }
 
 pub struct Text<'a, F: Font> {
@@ -24,6 +82,11 @@ pub struct Text<'a, F: Font> {
     pub extra_word_spacing: f64,
     pub extra_line_height: f64,
     pub align: TextAlign,
+    pub direction: TextDirection,
+
+    /// When set, soft hyphens are inserted at the hyphenation points of the words before breaking
+    /// the text into lines.
+    pub hyphenation: Option<&'a Hyphenator>,
 }
 
 struct FontMetrics {
@@ -31,6 +94,20 @@ struct FontMetrics {
     line_height: f64,
 }
 
+/// A line of text as it's drawn, with the lengths in mm.
+struct LineLayout {
+    text: String,
+    width: f64,
+
+    /// Whether the paragraph of the line is right-to-left.
+    rtl: bool,
+
+    x_offset: f64,
+
+    /// The extra width of each space in justified lines.
+    space_width: f64,
+}
+
 impl<'a, F: Font> Text<'a, F> {
     pub fn basic(text: &'a str, font: &'a F, size: f64) -> Self {
         Text {
@@ -42,7 +119,16 @@ impl<'a, F: Font> Text<'a, F> {
             extra_character_spacing: 0.,
             extra_word_spacing: 0.,
             extra_line_height: 0.,
-            align: TextAlign::Left,
+            align: TextAlign::Start,
+            direction: TextDirection::Auto,
+            hyphenation: None,
+        }
+    }
+
+    fn hyphenated_text(&self) -> Cow<'a, str> {
+        match self.hyphenation {
+            Some(hyphenator) => hyphenator.hyphenate(self.text),
+            None => Cow::Borrowed(self.text),
         }
     }
 
@@ -61,8 +147,9 @@ impl<'a, F: Font> Text<'a, F> {
     }
 
     #[inline(always)]
-    fn render_lines<'b, L: Iterator<Item = &'b str>>(
+    fn render_lines<'b, L: Iterator<Item = (&'b str, bool)>>(
         &self,
+        text: &str,
         lines: L,
         mut ctx: DrawCtx,
         ascent: f64,
@@ -76,22 +163,11 @@ impl<'a, F: Font> Text<'a, F> {
 
         let mut height_available = ctx.first_height;
 
-        let pdf_font = &self.font.indirect_font_ref();
-
         let mut line_count = 0;
         let mut draw_rect = 0;
 
-        for line in lines {
-            let line: &str = &remove_non_trailing_soft_hyphens(line);
-
-            let line_width = pt_to_mm(text_width(
-                line,
-                self.size,
-                self.font,
-                self.extra_character_spacing,
-                self.extra_word_spacing,
-            ));
-            max_width = max_width.max(line_width);
+        for line in self.line_layouts(text, lines, width) {
+            max_width = max_width.max(line.width);
 
             if height_available < line_height {
                 if let Some(ref mut breakable) = ctx.breakable {
@@ -124,40 +200,36 @@ impl<'a, F: Font> Text<'a, F> {
                     .set_character_spacing(self.extra_character_spacing);
             }
 
-            let x_offset = match self.align {
-                TextAlign::Left => 0.,
-                TextAlign::Center => (width - line_width) / 2.,
-                TextAlign::Right => width - line_width,
-            };
-
-            let x = x + x_offset;
+            let x = x + line.x_offset;
+            let extra_word_spacing = self.extra_word_spacing + mm_to_pt(line.space_width);
 
-            if self.extra_word_spacing != 0. {
-                ctx.location.layer.begin_text_section();
-                ctx.location.layer.set_font(pdf_font, self.size);
-                ctx.location.layer.set_text_cursor(Mm(x), Mm(y));
-
-                let word_spacing = self.extra_word_spacing * 1000. / self.size;
-
-                ctx.location.layer.write_gapped_text(
-                    line.split_inclusive(" ").flat_map(|s| {
-                        std::iter::once(GappedTextElement::Text(s)).chain(if s.ends_with(' ') {
-                            Some(GappedTextElement::Gap(word_spacing))
-                        } else {
-                            None
-                        })
-                    }),
-                    pdf_font,
-                );
-                ctx.location.layer.end_text_section();
-            } else {
-                ctx.location
-                    .layer
-                    .use_text(line, self.size, Mm(x), Mm(y), pdf_font);
-            }
+            ctx.location.layer.begin_text_section();
+            ctx.location
+                .layer
+                .set_font(self.font.indirect_font_ref(), self.size);
+            ctx.location.layer.set_text_cursor(Mm(x), Mm(y));
+            self.font.write_glyphs(
+                ctx.pdf,
+                &ctx.location.layer,
+                self.size,
+                &line.text,
+                &shape_line(self.font, &line.text, line.rtl),
+                extra_word_spacing * self.font.units_per_em() as f64 / self.size,
+            );
+            ctx.location.layer.end_text_section();
 
             if self.underline {
-                crate::utils::line(&ctx.location.layer, [x, y - 1.0], line_width, pt_to_mm(2.0));
+                let underline_width = if line.space_width > 0. {
+                    width
+                } else {
+                    line.width
+                };
+                crate::utils::line(
+                    &ctx.location.layer,
+                    [x, y - 1.0],
+                    underline_width,
+                    pt_to_mm(2.0),
+                );
             }
             ctx.location.layer.restore_graphics_state();
             y -= line_height;
@@ -168,8 +240,64 @@ impl<'a, F: Font> Text<'a, F> {
         (max_width, line_count as f64 * line_height)
     }
 
+    /// Lays out the lines for drawing them in the given width.
+    fn line_layouts<'b, L: Iterator<Item = (&'b str, bool)>>(
+        &self,
+        text: &str,
+        lines: L,
+        width: f64,
+    ) -> Vec<LineLayout> {
+        let mut rtl = false;
+        let mut paragraph_start = true;
+
+        lines
+            .map(|(line, paragraph_end)| {
+                if paragraph_start {
+                    rtl = self.direction.is_rtl(&text[subslice_offset(text, line)..]);
+                }
+                paragraph_start = paragraph_end;
+
+                let line = remove_non_trailing_soft_hyphens(line);
+
+                let line_width = pt_to_mm(text_width(
+                    &line,
+                    self.size,
+                    self.font,
+                    self.extra_character_spacing,
+                    self.extra_word_spacing,
+                ));
+
+                // The word spacing is only added after U+0020, like in `text_width`.
+                let space_count = line.matches(' ').count();
+                let justify = self.align == TextAlign::Justify
+                    && !paragraph_end
+                    && space_count > 0
+                    && width > line_width;
+
+                let x_offset = match self.align.resolve(rtl) {
+                    TextAlign::Justify if justify || !rtl => 0.,
+                    TextAlign::Left => 0.,
+                    TextAlign::Center => (width - line_width) / 2.,
+                    _ => width - line_width,
+                };
+
+                LineLayout {
+                    text: line,
+                    width: line_width,
+                    rtl,
+                    x_offset,
+                    space_width: if justify {
+                        (width - line_width) / space_count as f64
+                    } else {
+                        0.
+                    },
+                }
+            })
+            .collect()
+    }
+
     #[inline(always)]
-    fn layout_lines<'b, L: Iterator<Item = &'b str>>(
+    fn layout_lines<'b, L: Iterator<Item = (&'b str, bool)>>(
         &self,
         lines: L,
         line_height: f64,
@@ -186,7 +314,7 @@ impl<'a, F: Font> Text<'a, F> {
             f64::INFINITY
         };
 
-        for line in lines {
+        for (line, _) in lines {
             if let Some(&mut MeasureCtx {
                 breakable: Some(ref mut breakable),
                 ..
@@ -214,8 +342,12 @@ impl<'a, F: Font> Text<'a, F> {
         (max_width, line_count as f64 * line_height)
     }
 
-    fn break_into_lines(&'a self, width: f64) -> impl Iterator<Item = &'a str> + Clone {
-        break_text_into_lines(self.text, mm_to_pt(width), move |text| {
+    fn break_into_lines<'b>(
+        &'b self,
+        text: &'b str,
+        width: f64,
+    ) -> impl Iterator<Item = (&'b str, bool)> + Clone {
+        break_text_into_lines(text, mm_to_pt(width), move |text| {
             text_width(
                 text,
                 self.size,
@@ -224,6 +356,7 @@ impl<'a, F: Font> Text<'a, F> {
                 self.extra_word_spacing,
             )
         })
+        .with_paragraph_ends()
     }
 }
 
@@ -244,8 +377,10 @@ impl<'a, F: Font> Element for Text<'a, F> {
     fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
         let FontMetrics { line_height, .. } = self.compute_font_metrics();
 
+        let text = self.hyphenated_text();
+
         let size = self.layout_lines(
-            self.break_into_lines(ctx.width.max),
+            self.break_into_lines(&text, ctx.width.max),
             line_height,
             Some(&mut ctx),
         );
@@ -257,25 +392,40 @@ impl<'a, F: Font> Element for Text<'a, F> {
     }
 
     fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_tagged(ctx, StructureType::Paragraph, None, |ctx| {
+            self.draw_untagged(ctx)
+        })
+    }
+}
+
+impl<'a, F: Font> Text<'a, F> {
+    fn draw_untagged(&self, ctx: DrawCtx) -> ElementSize {
         let FontMetrics {
             ascent,
             line_height,
         } = self.compute_font_metrics();
 
-        let lines = self.break_into_lines(ctx.width.max);
+        let text = self.hyphenated_text();
+        let lines = self.break_into_lines(&text, ctx.width.max);
 
         // For left alignment we don't need to pre-layout because the
         // x offset is always zero.
+        let left_aligned = match self.align {
+            TextAlign::Left => true,
+            TextAlign::Start => !self.direction.may_be_rtl(&text),
+            _ => false,
+        };
+
         let width = if ctx.width.expand {
             ctx.width.max
-        } else if self.align == TextAlign::Left {
+        } else if left_aligned {
             0.
         } else {
             self.layout_lines(lines.clone(), line_height, None).0
         };
 
         let width_constraint = ctx.width;
-        let size = self.render_lines(lines, ctx, ascent, line_height, width);
+        let size = self.render_lines(&text, lines, ctx, ascent, line_height, width);
 
         ElementSize {
             width: Some(width_constraint.constrain(size.0)),
@@ -301,7 +451,7 @@ mod tests {
     #[test]
     fn test_multi_page() {
         let bytes = test_element_bytes(TestElementParams::breakable(), |callback| {
-            let font = BuiltinFont::courier(callback.document());
+            let font = BuiltinFont::courier(callback.document()).unwrap();
 
             let content = Text::basic(LOREM_IPSUM, &font, 32.);
             let content = content.debug(0);
@@ -316,7 +466,7 @@ mod tests {
         // A fake document for adding the font to.
         let doc = PdfDocument::empty("i contain a font");
 
-        let font = BuiltinFont::helvetica(&doc);
+        let font = BuiltinFont::helvetica(&doc).unwrap();
 
         let text_element = Text {
             ..Text::basic("i am a line\nso am i", &font, 12.)
@@ -364,4 +514,93 @@ mod tests {
             });
         }
     }
+
+    #[test]
+    fn test_hyphenation() {
+        let hyphenator =
+            Hyphenator::from_patterns("\\patterns{} \\hyphenation{do-nau-dampf-schiff-fahrt}");
+
+        // A Courier character is 2.54mm wide at this size, so a line has room for eleven of them.
+        let params = TestElementParams {
+            width: WidthConstraint {
+                max: 30.,
+                expand: false,
+            },
+            ..TestElementParams::unbreakable()
+        };
+
+        let bytes = test_element_bytes(params, |callback| {
+            let font = BuiltinFont::courier(callback.document()).unwrap();
+
+            callback.call(&Text {
+                hyphenation: Some(&hyphenator),
+                ..Text::basic("Donaudampfschifffahrt", &font, 12.)
+            });
+        });
+
+        assert_eq!(shown_text(&bytes), ["Donaudampf-", "schifffahrt"]);
+    }
+
+    #[test]
+    fn test_line_alignments() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc).unwrap();
+
+        let letter_width = 2.5400016;
+        let width = letter_width * 7.;
+
+        let alignments = |text: &str, align, direction| {
+            let text = Text {
+                align,
+                direction,
+                ..Text::basic(text, &font, 12.)
+            };
+
+            let lines = text.break_into_lines(text.text, width);
+            text.line_layouts(text.text, lines, width)
+                .into_iter()
+                .map(|line| (line.x_offset, line.space_width))
+                .collect::<Vec<_>>()
+        };
+
+        let assert_close = |actual: Vec<(f64, f64)>, expected: &[(f64, f64)]| {
+            assert_eq!(actual.len(), expected.len());
+
+            for (a, e) in actual.iter().zip(expected) {
+                assert!((a.0 - e.0).abs() < 1e-6 && (a.1 - e.1).abs() < 1e-6);
+            }
+        };
+
+        // The lines are "aa bb", "cc" and "dd ee".
+        let text = "aa bb cc\ndd ee";
+        let ltr = TextDirection::Auto;
+        let rtl = TextDirection::RightToLeft;
+
+        assert_close(
+            alignments(text, TextAlign::Center, ltr),
+            &[
+                (letter_width, 0.),
+                (letter_width * 2.5, 0.),
+                (letter_width, 0.),
+            ],
+        );
+
+        // The last line of each paragraph isn't stretched.
+        assert_close(
+            alignments(text, TextAlign::Justify, ltr),
+            &[(0., letter_width * 2.), (0., 0.), (0., 0.)],
+        );
+        assert_close(
+            alignments(text, TextAlign::Justify, rtl),
+            &[
+                (0., letter_width * 2.),
+                (letter_width * 5., 0.),
+                (letter_width * 2., 0.),
+            ],
+        );
+
+        // Only regular spaces are stretched.
+        let no_break = alignments("aa\u{a0}bb cc dd", TextAlign::Justify, ltr);
+        assert_close(no_break[..1].to_vec(), &[(0., 0.)]);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::{
+    structure::{draw_grouped, draw_into, StructureType},
     utils::{add_optional_size_with_gap, max_optional_size},
     *,
 };
@@ -107,6 +108,12 @@ impl<'a, T: Element, C: Element> Element for Titled<'a, T, C> {
     }
 
     fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        draw_grouped(ctx, StructureType::Section, |ctx| self.draw_untagged(ctx))
+    }
+}
+
+impl<'a, T: Element, C: Element> Titled<'a, T, C> {
+    fn draw_untagged(&self, ctx: DrawCtx) -> ElementSize {
         let title_first_height = ctx
             .breakable
             .as_ref()
@@ -119,6 +126,10 @@ impl<'a, T: Element, C: Element> Element for Titled<'a, T, C> {
         });
         let y_offset = self.y_offset(title_size);
 
+        // The heading comes before the content in the structure tree even though the title is
+        // drawn after it.
+        let heading = ctx.pdf.structure.reserve(StructureType::Heading, None);
+
         let content_size;
         let location;
         let mut break_count = 0;
@@ -195,21 +206,25 @@ impl<'a, T: Element, C: Element> Element for Titled<'a, T, C> {
         let collapse = self.collapse(break_count, content_size);
 
         if !collapse {
-            self.title.draw(DrawCtx {
-                pdf: ctx.pdf,
-                location: location.clone(),
-                width: ctx.width,
-                first_height: title_first_height,
-                preferred_height: None,
-                breakable: None,
-            });
+            draw_into(
+                DrawCtx {
+                    pdf: ctx.pdf,
+                    location: location.clone(),
+                    width: ctx.width,
+                    first_height: title_first_height,
+                    preferred_height: None,
+                    breakable: None,
+                },
+                heading,
+                |ctx| self.title.draw(ctx),
+            );
+        } else if let Some(heading) = heading {
+            ctx.pdf.structure.remove(heading);
         }
 
         self.size(title_size, content_size, break_count, collapse)
     }
-}
 
-impl<'a, T: Element, C: Element> Titled<'a, T, C> {
     fn y_offset(&self, title_size: ElementSize) -> f64 {
         title_size.height.map(|h| h + self.gap).unwrap_or(0.)
     }
@@ -0,0 +1,682 @@
+use aes::{
+    cipher::{
+        block_padding::{NoPadding, Pkcs7},
+        generic_array::GenericArray,
+        BlockEncrypt, BlockEncryptMut, KeyInit, KeyIvInit,
+    },
+    Aes128, Aes256,
+};
+use lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+use md5::Md5;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::{Error, Result};
+
+/// Pads passwords for the key derivation of 128 bit keys.
+const PASSWORD_PADDING: [u8; 32] = [
+    0x28, 0xbf, 0x4e, 0x5e, 0x4e, 0x75, 0x8a, 0x41, 0x64, 0x00, 0x4e, 0x56, 0xff, 0xfa, 0x01, 0x08,
+    0x2e, 0x2e, 0x00, 0xb6, 0xd0, 0x68, 0x3e, 0x80, 0x2f, 0x0c, 0xa9, 0xfe, 0x64, 0x53, 0x69, 0x7a,
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EncryptionMethod {
+    /// AES with 128 bit keys, which is supported by readers of PDF 1.6 and later.
+    Aes128,
+
+    /// AES with 256 bit keys, which needs a reader that supports PDF 2.0 or Acrobat X.
+    Aes256,
+}
+
+/// What readers allow when the document is opened with the user password. With the owner password
+/// everything is allowed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Permissions {
+    pub print: bool,
+    pub copy: bool,
+    pub modify: bool,
+    pub annotate: bool,
+    pub fill_forms: bool,
+}
+
+impl Permissions {
+    pub fn all() -> Self {
+        Permissions {
+            print: true,
+            copy: true,
+            modify: true,
+            annotate: true,
+            fill_forms: true,
+        }
+    }
+
+    /// The `P` entry of the encryption dictionary. Bits 7, 8 and 13 to 32 are reserved and have to
+    /// be set. Extracting text for accessibility is always allowed.
+    fn flags(self) -> i32 {
+        let mut flags: u32 = 0xffff_f000 | 0xc0 | 1 << 9;
+
+        if self.print {
+            // Printing in high quality.
+            flags |= 1 << 2 | 1 << 11;
+        }
+
+        if self.modify {
+            // Assembling the document, like inserting and rotating pages.
+            flags |= 1 << 3 | 1 << 10;
+        }
+
+        if self.copy {
+            flags |= 1 << 4;
+        }
+
+        if self.annotate {
+            flags |= 1 << 5;
+        }
+
+        if self.fill_forms {
+            flags |= 1 << 8;
+        }
+
+        flags as i32
+    }
+}
+
+/// Encrypts the document with the standard security handler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Encryption {
+    pub method: EncryptionMethod,
+
+    /// The password needed to open the document. When it's empty the document opens without
+    /// asking for one, but the permissions still apply.
+    pub user_password: String,
+
+    /// The password that lifts the permissions. Without it a random one is used, so that the
+    /// permissions can't be lifted.
+    pub owner_password: Option<String>,
+
+    pub permissions: Permissions,
+}
+
+impl Encryption {
+    /// Encrypts the strings and streams of a saved document, so it has to be the last change.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        let permissions = self.permissions.flags();
+
+        let (mut dictionary, key) = match self.method {
+            EncryptionMethod::Aes128 => {
+                let id = document_id(doc)?;
+                self.aes_128(&id, permissions)?
+            }
+            EncryptionMethod::Aes256 => self.aes_256(permissions)?,
+        };
+
+        dictionary.set("Filter", Object::Name(b"Standard".to_vec()));
+        dictionary.set("P", Object::Integer(permissions as i64));
+        dictionary.set("StmF", Object::Name(b"StdCF".to_vec()));
+        dictionary.set("StrF", Object::Name(b"StdCF".to_vec()));
+        dictionary.set("EncryptMetadata", Object::Boolean(true));
+
+        for (&id, object) in doc.objects.iter_mut() {
+            encrypt_object(object, &object_key(self.method, &key, id))?;
+        }
+
+        // AES-256 is an extension of PDF 1.7.
+        if doc.version.as_str() < "1.7" {
+            doc.version = "1.7".to_string();
+        }
+
+        if self.method == EncryptionMethod::Aes256 {
+            let catalog = doc.trailer.get(b"Root").and_then(Object::as_reference).ok();
+
+            if let Some(Ok(Object::Dictionary(catalog))) = catalog.map(|id| doc.get_object_mut(id))
+            {
+                catalog.set(
+                    "Extensions",
+                    Dictionary::from_iter(vec![(
+                        "ADBE",
+                        Object::Dictionary(Dictionary::from_iter(vec![
+                            ("BaseVersion", Object::Name(b"1.7".to_vec())),
+                            ("ExtensionLevel", Object::Integer(8)),
+                        ])),
+                    )]),
+                );
+            }
+        }
+
+        let dictionary = doc.add_object(dictionary);
+        doc.trailer.set("Encrypt", Object::Reference(dictionary));
+
+        Ok(())
+    }
+
+    /// Revision 4 of the standard security handler, with an RC4 and MD5 based key derivation.
+    fn aes_128(&self, id: &[u8], permissions: i32) -> Result<(Dictionary, Vec<u8>)> {
+        let user_password = pad_password(&self.user_password);
+
+        let owner_password = match self.owner_password {
+            Some(ref password) => pad_password(password),
+            None => random()?,
+        };
+
+        let mut owner_key = Md5::digest(owner_password).to_vec();
+
+        for _ in 0..50 {
+            owner_key = Md5::digest(&owner_key).to_vec();
+        }
+
+        let mut owner = user_password.to_vec();
+        rc4_rounds(&owner_key, &mut owner);
+
+        let mut key = Md5::new()
+            .chain_update(user_password)
+            .chain_update(&owner)
+            .chain_update(permissions.to_le_bytes())
+            .chain_update(id)
+            .finalize()
+            .to_vec();
+
+        for _ in 0..50 {
+            key = Md5::digest(&key).to_vec();
+        }
+
+        let mut user = Md5::new()
+            .chain_update(PASSWORD_PADDING)
+            .chain_update(id)
+            .finalize()
+            .to_vec();
+
+        rc4_rounds(&key, &mut user);
+        user.resize(32, 0);
+
+        let dictionary = Dictionary::from_iter(vec![
+            ("V", Object::Integer(4)),
+            ("R", Object::Integer(4)),
+            ("Length", Object::Integer(128)),
+            ("CF", crypt_filters(b"AESV2", 16)),
+            ("O", Object::String(owner, StringFormat::Hexadecimal)),
+            ("U", Object::String(user, StringFormat::Hexadecimal)),
+        ]);
+
+        Ok((dictionary, key))
+    }
+
+    /// Revision 6 of the standard security handler, where the key is random and encrypted with
+    /// keys derived from the passwords.
+    fn aes_256(&self, permissions: i32) -> Result<(Dictionary, Vec<u8>)> {
+        let key: [u8; 32] = random()?;
+
+        let user_password = utf8_password(&self.user_password);
+
+        let owner_password = match self.owner_password {
+            Some(ref password) => utf8_password(password).to_vec(),
+            None => random::<32>()?.to_vec(),
+        };
+
+        // The validation and key salts of the user and owner passwords.
+        let salts: [u8; 32] = random()?;
+
+        let mut user = hash_r6(user_password, &salts[0..8], &[]);
+        user.extend_from_slice(&salts[0..16]);
+
+        let user_key = hash_r6(user_password, &salts[8..16], &[]);
+
+        let mut owner = hash_r6(&owner_password, &salts[16..24], &user);
+        owner.extend_from_slice(&salts[16..32]);
+
+        let owner_key = hash_r6(&owner_password, &salts[24..32], &user);
+
+        let mut perms = [0; 16];
+        perms[..4].copy_from_slice(&permissions.to_le_bytes());
+        perms[4..8].copy_from_slice(&[0xff; 4]);
+        perms[8..12].copy_from_slice(b"Tadb");
+        perms[12..].copy_from_slice(&random::<4>()?);
+
+        Aes256::new(GenericArray::from_slice(&key))
+            .encrypt_block(GenericArray::from_mut_slice(&mut perms));
+
+        let string = |bytes: Vec<u8>| Object::String(bytes, StringFormat::Hexadecimal);
+
+        let dictionary = Dictionary::from_iter(vec![
+            ("V", Object::Integer(5)),
+            ("R", Object::Integer(6)),
+            ("Length", Object::Integer(256)),
+            ("CF", crypt_filters(b"AESV3", 32)),
+            ("O", string(owner)),
+            ("U", string(user)),
+            ("OE", string(encrypt_key(&owner_key, &key))),
+            ("UE", string(encrypt_key(&user_key, &key))),
+            ("Perms", string(perms.to_vec())),
+        ]);
+
+        Ok((dictionary, key.to_vec()))
+    }
+}
+
+fn crypt_filters(method: &[u8], length: i64) -> Object {
+    Object::Dictionary(Dictionary::from_iter(vec![(
+        "StdCF",
+        Object::Dictionary(Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"CryptFilter".to_vec())),
+            ("CFM", Object::Name(method.to_vec())),
+            ("AuthEvent", Object::Name(b"DocOpen".to_vec())),
+            ("Length", Object::Integer(length)),
+        ])),
+    )]))
+}
+
+/// The first part of the file identifier in the trailer, which is added if it's missing.
+fn document_id(doc: &mut Document) -> Result<Vec<u8>> {
+    let id = doc
+        .trailer
+        .get(b"ID")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|ids| ids.first())
+        .and_then(|id| id.as_str().ok());
+
+    if let Some(id) = id {
+        return Ok(id.to_vec());
+    }
+
+    let id = random::<16>()?.to_vec();
+    let string = Object::String(id.clone(), StringFormat::Hexadecimal);
+    doc.trailer.set("ID", vec![string.clone(), string]);
+
+    Ok(id)
+}
+
+/// Passwords for 128 bit keys are encoded in PDFDocEncoding, which matches Latin-1 for the
+/// printable characters.
+fn pad_password(password: &str) -> [u8; 32] {
+    let mut padded = PASSWORD_PADDING;
+    let encoded = password.chars().map(|c| u8::try_from(c).unwrap_or(b'?'));
+
+    for (i, byte) in encoded.take(32).enumerate() {
+        padded[i] = byte;
+        padded[i + 1..].copy_from_slice(&PASSWORD_PADDING[..31 - i]);
+    }
+
+    padded
+}
+
+fn utf8_password(password: &str) -> &[u8] {
+    let mut end = password.len().min(127);
+
+    while !password.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &password.as_bytes()[..end]
+}
+
+/// The hash of revision 6 that derives keys from passwords.
+fn hash_r6(password: &[u8], salt: &[u8], user: &[u8]) -> Vec<u8> {
+    let mut hash = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(user)
+        .finalize()
+        .to_vec();
+
+    let mut round = 0;
+
+    loop {
+        let data = [password, &hash, user].concat().repeat(64);
+
+        let encrypted = cbc::Encryptor::<Aes128>::new(
+            GenericArray::from_slice(&hash[..16]),
+            GenericArray::from_slice(&hash[16..32]),
+        )
+        .encrypt_padded_vec_mut::<NoPadding>(&data);
+
+        // The first 16 bytes as a number modulo 3, which is the sum of the bytes modulo 3.
+        hash = match encrypted[..16].iter().map(|&b| b as u32).sum::<u32>() % 3 {
+            0 => Sha256::digest(&encrypted).to_vec(),
+            1 => Sha384::digest(&encrypted).to_vec(),
+            _ => Sha512::digest(&encrypted).to_vec(),
+        };
+
+        round += 1;
+
+        if round >= 64 && *encrypted.last().unwrap() as usize + 32 <= round {
+            break;
+        }
+    }
+
+    hash.truncate(32);
+    hash
+}
+
+/// Encrypts the file key for the `OE` and `UE` entries.
+fn encrypt_key(key: &[u8], file_key: &[u8; 32]) -> Vec<u8> {
+    cbc::Encryptor::<Aes256>::new(GenericArray::from_slice(key), &GenericArray::default())
+        .encrypt_padded_vec_mut::<NoPadding>(file_key)
+}
+
+/// The key for the strings and streams of an object. With 128 bit keys it depends on the object.
+fn object_key(method: EncryptionMethod, key: &[u8], id: ObjectId) -> Vec<u8> {
+    match method {
+        EncryptionMethod::Aes128 => Md5::new()
+            .chain_update(key)
+            .chain_update(&id.0.to_le_bytes()[..3])
+            .chain_update(&id.1.to_le_bytes()[..2])
+            .chain_update(b"sAlT")
+            .finalize()
+            .to_vec(),
+        EncryptionMethod::Aes256 => key.to_vec(),
+    }
+}
+
+fn encrypt_object(object: &mut Object, key: &[u8]) -> Result<()> {
+    match object {
+        Object::String(bytes, format) => {
+            *bytes = encrypt(key, bytes)?;
+            *format = StringFormat::Hexadecimal;
+        }
+        Object::Array(array) => {
+            for object in array {
+                encrypt_object(object, key)?;
+            }
+        }
+        Object::Dictionary(dictionary) => encrypt_dictionary(dictionary, key)?,
+        Object::Stream(stream) => {
+            encrypt_dictionary(&mut stream.dict, key)?;
+
+            let content = encrypt(key, &stream.content)?;
+            stream.set_content(content);
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+fn encrypt_dictionary(dictionary: &mut Dictionary, key: &[u8]) -> Result<()> {
+    // The signature covers the encrypted document, so it isn't encrypted itself.
+    let signature = matches!(dictionary.get(b"Type"), Ok(Object::Name(name)) if name == b"Sig");
+
+    for (name, object) in dictionary.iter_mut() {
+        if !(signature && name == b"Contents") {
+            encrypt_object(object, key)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Encrypts with AES in CBC mode, with the random initialization vector in front.
+fn encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let iv: [u8; 16] = random()?;
+
+    let encrypted = if key.len() == 32 {
+        cbc::Encryptor::<Aes256>::new(GenericArray::from_slice(key), &iv.into())
+            .encrypt_padded_vec_mut::<Pkcs7>(data)
+    } else {
+        cbc::Encryptor::<Aes128>::new(GenericArray::from_slice(key), &iv.into())
+            .encrypt_padded_vec_mut::<Pkcs7>(data)
+    };
+
+    Ok([&iv[..], &encrypted].concat())
+}
+
+/// Encrypts the data with RC4 twenty times, with the key xored with the round.
+fn rc4_rounds(key: &[u8], data: &mut [u8]) {
+    for round in 0..20 {
+        let key: Vec<u8> = key.iter().map(|&b| b ^ round).collect();
+        rc4(&key, data);
+    }
+}
+
+fn rc4(key: &[u8], data: &mut [u8]) {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+
+    for byte in data {
+        i = i.wrapping_add(1);
+        j = j.wrapping_add(state[i as usize]);
+        state.swap(i as usize, j as usize);
+        *byte ^= state[state[i as usize].wrapping_add(state[j as usize]) as usize];
+    }
+}
+
+fn random<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::getrandom(&mut bytes).map_err(|error| Error::Pdf(error.to_string()))?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use aes::cipher::{BlockDecrypt, BlockDecryptMut};
+
+    use super::*;
+    use crate::{build_pdf, output::SaveOptions, test_utils::FakeText};
+
+    /// Checks the password like a reader opening the document and returns the file key, or `None`
+    /// if it's neither the user nor the owner password.
+    fn open(encrypt: &Dictionary, id: &[u8], password: &str) -> Option<Vec<u8>> {
+        let string = |key: &[u8]| encrypt.get(key).and_then(Object::as_str).unwrap().to_vec();
+        let (owner, user) = (string(b"O"), string(b"U"));
+
+        match encrypt.get(b"R").and_then(Object::as_i64).unwrap() {
+            4 => {
+                let permissions = encrypt.get(b"P").and_then(Object::as_i64).unwrap() as i32;
+
+                let open_with_user_password = |padded: &[u8]| {
+                    let mut key = Md5::new()
+                        .chain_update(padded)
+                        .chain_update(&owner)
+                        .chain_update(permissions.to_le_bytes())
+                        .chain_update(id)
+                        .finalize()
+                        .to_vec();
+
+                    for _ in 0..50 {
+                        key = Md5::digest(&key).to_vec();
+                    }
+
+                    let mut check = Md5::new()
+                        .chain_update(PASSWORD_PADDING)
+                        .chain_update(id)
+                        .finalize()
+                        .to_vec();
+                    rc4_rounds(&key, &mut check);
+
+                    (check == user[..16]).then_some(key)
+                };
+
+                open_with_user_password(&pad_password(password)).or_else(|| {
+                    // The owner password decrypts the user password from `O`.
+                    let mut owner_key = Md5::digest(pad_password(password)).to_vec();
+
+                    for _ in 0..50 {
+                        owner_key = Md5::digest(&owner_key).to_vec();
+                    }
+
+                    let mut user_password = owner.clone();
+
+                    for round in (0..20).rev() {
+                        let key: Vec<u8> = owner_key.iter().map(|&b| b ^ round).collect();
+                        rc4(&key, &mut user_password);
+                    }
+
+                    open_with_user_password(&user_password)
+                })
+            }
+            6 => {
+                let password = utf8_password(password);
+
+                let decrypt_key = |hash: Vec<u8>, encrypted: Vec<u8>| {
+                    cbc::Decryptor::<Aes256>::new(
+                        GenericArray::from_slice(&hash),
+                        &GenericArray::default(),
+                    )
+                    .decrypt_padded_vec_mut::<NoPadding>(&encrypted)
+                    .unwrap()
+                };
+
+                let key = if hash_r6(password, &user[32..40], &[]) == user[..32] {
+                    decrypt_key(hash_r6(password, &user[40..48], &[]), string(b"UE"))
+                } else if hash_r6(password, &owner[32..40], &user[..48]) == owner[..32] {
+                    decrypt_key(
+                        hash_r6(password, &owner[40..48], &user[..48]),
+                        string(b"OE"),
+                    )
+                } else {
+                    return None;
+                };
+
+                // The permissions are encrypted with the file key to detect tampering.
+                let mut perms = string(b"Perms");
+                Aes256::new(GenericArray::from_slice(&key))
+                    .decrypt_block(GenericArray::from_mut_slice(&mut perms));
+                assert_eq!(&perms[8..12], b"Tadb");
+
+                Some(key)
+            }
+            _ => None,
+        }
+    }
+
+    fn decrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let (iv, data) = data.split_at(16);
+
+        if key.len() == 32 {
+            cbc::Decryptor::<Aes256>::new(GenericArray::from_slice(key), iv.into())
+                .decrypt_padded_vec_mut::<Pkcs7>(data)
+                .unwrap()
+        } else {
+            cbc::Decryptor::<Aes128>::new(GenericArray::from_slice(key), iv.into())
+                .decrypt_padded_vec_mut::<Pkcs7>(data)
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_permissions() {
+        assert_eq!(Permissions::default().flags(), -3392);
+        assert_eq!(Permissions::all().flags(), -4);
+        assert_eq!(
+            Permissions {
+                print: true,
+                ..Default::default()
+            }
+            .flags(),
+            -1340
+        );
+    }
+
+    #[test]
+    fn test_rc4() {
+        let mut data = b"Plaintext".to_vec();
+        rc4(b"Key", &mut data);
+
+        assert_eq!(data, [0xbb, 0xf3, 0x16, 0xe8, 0xd9, 0x40, 0xaf, 0x0a, 0xd3]);
+    }
+
+    #[test]
+    fn test_pad_password() {
+        assert_eq!(pad_password(""), PASSWORD_PADDING);
+        assert_eq!(&pad_password("ab")[..4], [b'a', b'b', 0x28, 0xbf]);
+        assert_eq!(pad_password(&"x".repeat(40)), [b'x'; 32]);
+    }
+
+    #[test]
+    fn test_encryption() {
+        for method in [EncryptionMethod::Aes128, EncryptionMethod::Aes256] {
+            let mut pdf = build_pdf(
+                "test",
+                (10., 10.),
+                |_| Ok(()),
+                |_: &()| FakeText {
+                    lines: 1,
+                    line_height: 5.,
+                    width: 3.,
+                },
+            )
+            .unwrap();
+
+            pdf.metadata.title = Some("Payroll".to_string());
+
+            let bytes = pdf
+                .to_bytes_with_options(&SaveOptions {
+                    encryption: Some(Encryption {
+                        method,
+                        user_password: "user".to_string(),
+                        owner_password: Some("owner".to_string()),
+                        permissions: Permissions::default(),
+                    }),
+                    ..Default::default()
+                })
+                .unwrap();
+
+            let (revision, filter) = match method {
+                EncryptionMethod::Aes128 => ("/R 4", "/CFM/AESV2"),
+                EncryptionMethod::Aes256 => ("/R 6", "/CFM/AESV3"),
+            };
+
+            let output = String::from_utf8_lossy(&bytes);
+
+            assert!(!output.contains("Payroll"));
+            assert!(output.contains("/Encrypt "));
+            assert!(output.contains(revision));
+            assert!(output.contains(filter));
+        }
+    }
+
+    #[test]
+    fn test_passwords() {
+        for method in [EncryptionMethod::Aes128, EncryptionMethod::Aes256] {
+            let mut doc = Document::with_version("1.5");
+
+            let info = doc.add_object(Dictionary::from_iter(vec![(
+                "Title",
+                Object::string_literal("Payroll"),
+            )]));
+            doc.trailer.set("Info", Object::Reference(info));
+
+            Encryption {
+                method,
+                user_password: "user".to_string(),
+                owner_password: Some("owner".to_string()),
+                permissions: Permissions::default(),
+            }
+            .write(&mut doc)
+            .unwrap();
+
+            let encrypt = doc
+                .trailer
+                .get(b"Encrypt")
+                .and_then(Object::as_reference)
+                .and_then(|id| doc.get_dictionary(id))
+                .unwrap();
+
+            let id = doc
+                .trailer
+                .get(b"ID")
+                .and_then(Object::as_array)
+                .map(|ids| ids[0].as_str().unwrap().to_vec())
+                .unwrap_or_default();
+
+            let title = doc
+                .get_dictionary(info)
+                .and_then(|info| info.get(b"Title"))
+                .and_then(Object::as_str)
+                .unwrap();
+
+            for password in ["user", "owner"] {
+                let key = open(encrypt, &id, password).unwrap();
+                assert_eq!(decrypt(&object_key(method, &key, info), title), b"Payroll");
+            }
+
+            assert_eq!(open(encrypt, &id, "wrong"), None);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+
+/// The errors of loading the fonts and images of a document and of writing it.
+#[derive(Debug)]
+pub enum Error {
+    /// A font file couldn't be parsed or embedded.
+    Font(String),
+
+    /// A pixel image couldn't be decoded.
+    Image(String),
+
+    /// An SVG couldn't be parsed.
+    Svg(String),
+
+    /// A page of another document couldn't be imported.
+    Import(String),
+
+    /// The document couldn't be written or modified after it was written.
+    Pdf(String),
+
+    /// The document violates the conformance level it should be written in.
+    Conformance(String),
+
+    /// The signing certificate couldn't be loaded or the document couldn't be signed.
+    Signature(String),
+
+    /// A serialized element or document is invalid.
+    Deserialize(String),
+
+    /// A file couldn't be read or the document couldn't be written to the target.
+    Io(std::io::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Font(message) => write!(f, "invalid font: {}", message),
+            Error::Image(message) => write!(f, "invalid image: {}", message),
+            Error::Svg(message) => write!(f, "invalid svg: {}", message),
+            Error::Import(message) => write!(f, "invalid pdf to import: {}", message),
+            Error::Pdf(message) => write!(f, "failed to write pdf: {}", message),
+            Error::Conformance(message) => write!(f, "nonconforming document: {}", message),
+            Error::Signature(message) => write!(f, "failed to sign pdf: {}", message),
+            Error::Deserialize(message) => write!(f, "failed to deserialize: {}", message),
+            Error::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<lopdf::Error> for Error {
+    fn from(error: lopdf::Error) -> Self {
+        Error::Pdf(error.to_string())
+    }
+}
+
+impl From<printpdf::image::ImageError> for Error {
+    fn from(error: printpdf::image::ImageError) -> Self {
+        Error::Image(error.to_string())
+    }
+}
+
+impl From<usvg::Error> for Error {
+    fn from(error: usvg::Error) -> Self {
+        Error::Svg(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Deserialize(error.to_string())
+    }
+}
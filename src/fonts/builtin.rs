@@ -4,8 +4,13 @@ use afm::{CharMetric, FontMetrics, Ligature};
 use pdf_core_14_font_afms::*;
 use printpdf::{BuiltinFont::*, IndirectFontRef, PdfDocumentReference};
 
+use crate::{Error, Result};
+
 use super::Font;
 
+/// One of the 14 standard fonts of PDF, which aren't embedded. Its text isn't kerned and has no
+/// ligatures, since it's shaped with the default [Font::shape], which ignores the kerning pairs of
+/// the font metrics. Use a [super::truetype::TruetypeFont] for text that needs them.
 pub struct BuiltinFont {
     font_ref: IndirectFontRef,
     metrics: FontMetrics,
@@ -13,12 +18,18 @@ pub struct BuiltinFont {
 }
 
 impl BuiltinFont {
-    fn add(document: &PdfDocumentReference, font: printpdf::BuiltinFont, afm: &str) -> Self {
+    fn add(
+        document: &PdfDocumentReference,
+        font: printpdf::BuiltinFont,
+        afm: &str,
+    ) -> Result<Self> {
         let parser = afm::afm();
 
         let mut input = pom::DataInput::new(afm.as_bytes());
 
-        let metrics = parser.parse(&mut input).unwrap();
+        let metrics = parser
+            .parse(&mut input)
+            .map_err(|error| Error::Font(error.to_string()))?;
 
         let mut char_metrics_by_codepoint = HashMap::new();
 
@@ -42,66 +53,68 @@ impl BuiltinFont {
             );
         }
 
-        BuiltinFont {
-            font_ref: document.add_builtin_font(font).unwrap(),
+        Ok(BuiltinFont {
+            font_ref: document
+                .add_builtin_font(font)
+                .map_err(|error| Error::Font(error.to_string()))?,
             metrics,
             char_metrics_by_codepoint,
-        }
+        })
     }
 
-    pub fn courier(document: &PdfDocumentReference) -> Self {
+    pub fn courier(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, Courier, COURIER)
     }
 
-    pub fn courier_bold(document: &PdfDocumentReference) -> Self {
+    pub fn courier_bold(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, CourierBold, COURIER_BOLD)
     }
 
-    pub fn courier_oblique(document: &PdfDocumentReference) -> Self {
+    pub fn courier_oblique(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, CourierOblique, COURIER_OBLIQUE)
     }
 
-    pub fn courier_bold_oblique(document: &PdfDocumentReference) -> Self {
+    pub fn courier_bold_oblique(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, CourierBoldOblique, COURIER_BOLD_OBLIQUE)
     }
 
-    pub fn helvetica(document: &PdfDocumentReference) -> Self {
+    pub fn helvetica(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, Helvetica, HELVETICA)
     }
 
-    pub fn helvetica_bold(document: &PdfDocumentReference) -> Self {
+    pub fn helvetica_bold(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, HelveticaBold, HELVETICA_BOLD)
     }
 
-    pub fn helvetica_oblique(document: &PdfDocumentReference) -> Self {
+    pub fn helvetica_oblique(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, HelveticaOblique, HELVETICA_OBLIQUE)
     }
 
-    pub fn helvetica_bold_oblique(document: &PdfDocumentReference) -> Self {
+    pub fn helvetica_bold_oblique(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, HelveticaBoldOblique, HELVETICA_BOLD_OBLIQUE)
     }
 
-    pub fn times_roman(document: &PdfDocumentReference) -> Self {
+    pub fn times_roman(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, TimesRoman, TIMES_ROMAN)
     }
 
-    pub fn times_bold(document: &PdfDocumentReference) -> Self {
+    pub fn times_bold(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, TimesBold, HELVETICA_BOLD)
     }
 
-    pub fn times_italic(document: &PdfDocumentReference) -> Self {
+    pub fn times_italic(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, TimesItalic, TIMES_ITALIC)
     }
 
-    pub fn times_bold_italic(document: &PdfDocumentReference) -> Self {
+    pub fn times_bold_italic(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, TimesBoldItalic, TIMES_BOLD_ITALIC)
     }
 
-    pub fn symbol(document: &PdfDocumentReference) -> Self {
+    pub fn symbol(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, Symbol, SYMBOL)
     }
 
-    pub fn zapf_dingbats(document: &PdfDocumentReference) -> Self {
+    pub fn zapf_dingbats(document: &PdfDocumentReference) -> Result<Self> {
         Self::add(document, ZapfDingbats, ZAPF_DINGBATS)
     }
 }
@@ -112,10 +125,18 @@ impl Font for BuiltinFont {
     }
 
     fn codepoint_h_metrics(&self, codepoint: u32) -> super::HMetrics {
-        let metrics = self.char_metrics_by_codepoint.get(&codepoint).unwrap();
+        // Characters the font doesn't have are left out when the text gets encoded, so they take
+        // up no space, like a .notdef glyph without a width. Soft hyphens are drawn as hyphens.
+        let codepoint = match codepoint {
+            0xad => '-' as u32,
+            codepoint => codepoint,
+        };
 
         super::HMetrics {
-            advance_width: metrics.wx,
+            advance_width: self
+                .char_metrics_by_codepoint
+                .get(&codepoint)
+                .map_or(0., |metrics| metrics.wx),
         }
     }
 
@@ -123,6 +144,10 @@ impl Font for BuiltinFont {
         1000
     }
 
+    fn has_glyph(&self, c: char) -> bool {
+        self.char_metrics_by_codepoint.contains_key(&(c as u32))
+    }
+
     fn general_metrics(&self) -> super::GeneralMetrics {
         let bbox = self.metrics.font_bbox;
 
@@ -148,22 +173,38 @@ mod tests {
     fn test_no_panic() {
         let doc = PdfDocument::empty("");
 
-        BuiltinFont::courier(&doc);
-        BuiltinFont::courier_bold(&doc);
-        BuiltinFont::courier_oblique(&doc);
-        BuiltinFont::courier_bold_oblique(&doc);
+        BuiltinFont::courier(&doc).unwrap();
+        BuiltinFont::courier_bold(&doc).unwrap();
+        BuiltinFont::courier_oblique(&doc).unwrap();
+        BuiltinFont::courier_bold_oblique(&doc).unwrap();
+
+        BuiltinFont::helvetica(&doc).unwrap();
+        BuiltinFont::helvetica_bold(&doc).unwrap();
+        BuiltinFont::helvetica_oblique(&doc).unwrap();
+        BuiltinFont::helvetica_bold_oblique(&doc).unwrap();
+
+        BuiltinFont::times_roman(&doc).unwrap();
+        BuiltinFont::times_bold(&doc).unwrap();
+        BuiltinFont::times_italic(&doc).unwrap();
+        BuiltinFont::times_bold_italic(&doc).unwrap();
+
+        BuiltinFont::symbol(&doc).unwrap();
+        BuiltinFont::zapf_dingbats(&doc).unwrap();
+    }
+
+    #[test]
+    fn test_shape() {
+        let doc = PdfDocument::empty("");
+        let font = BuiltinFont::courier(&doc).unwrap();
 
-        BuiltinFont::helvetica(&doc);
-        BuiltinFont::helvetica_bold(&doc);
-        BuiltinFont::helvetica_oblique(&doc);
-        BuiltinFont::helvetica_bold_oblique(&doc);
+        let glyphs = font.shape("a b", false);
 
-        BuiltinFont::times_roman(&doc);
-        BuiltinFont::times_bold(&doc);
-        BuiltinFont::times_italic(&doc);
-        BuiltinFont::times_bold_italic(&doc);
+        assert_eq!(
+            glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(glyphs.iter().all(|g| g.x_advance == 600.));
 
-        BuiltinFont::symbol(&doc);
-        BuiltinFont::zapf_dingbats(&doc);
+        assert_eq!(crate::text::text_width("a b", 10., &font, 0., 1.), 19.);
     }
 }
@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use itertools::Itertools;
+use printpdf::{IndirectFontRef, PdfLayerReference};
+
+use crate::Pdf;
+
+use super::{Font, GeneralMetrics, HMetrics, ShapedGlyph};
+
+/// A chain of fonts where characters missing from the primary font are taken from the first
+/// fallback that has them. The text is split into runs by glyph coverage and each run is shaped
+/// and drawn with its own font.
+///
+/// All metrics are in the units of the primary font, which is also where the line height and
+/// ascent come from.
+pub struct FontFallback<'a, F: Font + ?Sized> {
+    pub primary: &'a F,
+    pub fallbacks: Vec<&'a F>,
+}
+
+impl<'a, F: Font + ?Sized> FontFallback<'a, F> {
+    pub fn new(primary: &'a F, fallbacks: Vec<&'a F>) -> Self {
+        FontFallback { primary, fallbacks }
+    }
+
+    fn font(&self, index: usize) -> &'a F {
+        if index == 0 {
+            self.primary
+        } else {
+            self.fallbacks[index - 1]
+        }
+    }
+
+    fn fonts(&self) -> impl Iterator<Item = &'a F> + '_ {
+        std::iter::once(self.primary).chain(self.fallbacks.iter().copied())
+    }
+
+    /// Splits the text into runs for which a single font is used. Returns the byte ranges together
+    /// with the index of the font. Characters that no font has go to the primary font.
+    fn runs(&self, text: &str) -> Vec<(Range<usize>, usize)> {
+        let mut runs: Vec<(Range<usize>, usize)> = Vec::new();
+
+        for (start, c) in text.char_indices() {
+            let end = start + c.len_utf8();
+
+            let font = match runs.last_mut() {
+                Some((range, _)) if continues_cluster(c) => {
+                    range.end = end;
+                    continue;
+                }
+                _ => self.fonts().position(|font| font.has_glyph(c)).unwrap_or(0),
+            };
+
+            match runs.last_mut() {
+                Some((range, last)) if *last == font => range.end = end,
+                _ => runs.push((start..end, font)),
+            }
+        }
+
+        runs
+    }
+
+    /// The factor for converting the units of the font with the index into the primary units.
+    fn scale(&self, index: usize) -> f64 {
+        self.primary.units_per_em() as f64 / self.font(index).units_per_em() as f64
+    }
+}
+
+/// Characters that have to stay with the font of the character before them, such as combining
+/// marks, joiners and variation selectors.
+fn continues_cluster(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036f}'
+            | '\u{1ab0}'..='\u{1aff}'
+            | '\u{1dc0}'..='\u{1dff}'
+            | '\u{200c}'..='\u{200d}'
+            | '\u{20d0}'..='\u{20ff}'
+            | '\u{fe00}'..='\u{fe0f}'
+            | '\u{fe20}'..='\u{fe2f}'
+            | '\u{1f3fb}'..='\u{1f3ff}'
+            | '\u{e0020}'..='\u{e007f}'
+            | '\u{e0100}'..='\u{e01ef}'
+    )
+}
+
+fn scale_glyph(glyph: &ShapedGlyph, scale: f64) -> ShapedGlyph {
+    ShapedGlyph {
+        x_advance: glyph.x_advance * scale,
+        x_offset: glyph.x_offset * scale,
+        y_offset: glyph.y_offset * scale,
+        ..*glyph
+    }
+}
+
+impl<'a, F: Font + ?Sized> Font for FontFallback<'a, F> {
+    fn indirect_font_ref(&self) -> &IndirectFontRef {
+        self.primary.indirect_font_ref()
+    }
+
+    fn codepoint_h_metrics(&self, codepoint: u32) -> HMetrics {
+        let index = char::from_u32(codepoint)
+            .and_then(|c| self.fonts().position(|font| font.has_glyph(c)))
+            .unwrap_or(0);
+
+        HMetrics {
+            advance_width: self
+                .font(index)
+                .codepoint_h_metrics(codepoint)
+                .advance_width
+                * self.scale(index),
+        }
+    }
+
+    fn units_per_em(&self) -> u16 {
+        self.primary.units_per_em()
+    }
+
+    fn general_metrics(&self) -> GeneralMetrics {
+        self.primary.general_metrics()
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.fonts().any(|font| font.has_glyph(c))
+    }
+
+    fn shape(&self, text: &str, rtl: bool) -> Vec<ShapedGlyph> {
+        let mut runs: Vec<Vec<ShapedGlyph>> = self
+            .runs(text)
+            .into_iter()
+            .map(|(range, index)| {
+                let scale = self.scale(index);
+
+                self.font(index)
+                    .shape(&text[range.clone()], rtl)
+                    .iter()
+                    .map(|glyph| ShapedGlyph {
+                        cluster: glyph.cluster + range.start,
+                        ..scale_glyph(glyph, scale)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // The glyphs within the runs are already in visual order.
+        if rtl {
+            runs.reverse();
+        }
+
+        runs.concat()
+    }
+
+    fn write_glyphs(
+        &self,
+        pdf: &mut Pdf,
+        layer: &PdfLayerReference,
+        size: f64,
+        text: &str,
+        glyphs: &[ShapedGlyph],
+        word_spacing: f64,
+    ) {
+        let runs = self.runs(text);
+
+        let run_index = |cluster: usize| {
+            runs.iter()
+                .position(|(range, _)| range.contains(&cluster))
+                .unwrap_or(0)
+        };
+
+        // The caller has set the primary font.
+        let mut current = 0;
+
+        for (run, group) in &glyphs.iter().group_by(|glyph| run_index(glyph.cluster)) {
+            let (range, index) = runs[run].clone();
+
+            if index != current {
+                layer.set_font(self.font(index).indirect_font_ref(), size);
+                current = index;
+            }
+
+            let scale = 1. / self.scale(index);
+            let glyphs: Vec<ShapedGlyph> = group.map(|glyph| scale_glyph(glyph, scale)).collect();
+
+            // The text ends with the run, so that the last cluster doesn't extend into the next
+            // run.
+            self.font(index).write_glyphs(
+                pdf,
+                layer,
+                size,
+                &text[..range.end],
+                &glyphs,
+                word_spacing * scale,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::PdfDocument;
+
+    use crate::fonts::{builtin::BuiltinFont, truetype::TruetypeFont};
+
+    use super::*;
+
+    const DEJAVU_SANS: &[u8] = include_bytes!("../test_utils/DejaVuSans.ttf");
+
+    #[test]
+    fn test_runs() {
+        let doc = PdfDocument::empty("");
+
+        let helvetica = BuiltinFont::helvetica(&doc).unwrap();
+        let dejavu_sans = TruetypeFont::new(&doc, DEJAVU_SANS).unwrap();
+
+        let fallback: FontFallback<dyn Font> = FontFallback::new(&helvetica, vec![&dejavu_sans]);
+
+        // Helvetica doesn't have the omega. The combining mark stays with the character before
+        // it.
+        assert_eq!(
+            fallback.runs("ab\u{3a9}\u{301}c"),
+            vec![(0..2, 0), (2..6, 1), (6..7, 0)]
+        );
+
+        let glyphs = fallback.shape("ab\u{3a9}c", false);
+
+        assert_eq!(
+            glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(),
+            vec![0, 1, 2, 4]
+        );
+
+        // The advance is converted into the units of Helvetica.
+        assert_eq!(
+            glyphs[2].x_advance,
+            dejavu_sans.codepoint_h_metrics(0x3a9).advance_width * 1000. / 2048.
+        );
+
+        // Neither font has Tibetan, so the primary font is used, which doesn't panic and gives
+        // the character no width.
+        assert_eq!(fallback.runs("a\u{f00}"), vec![(0..4, 0)]);
+        assert_eq!(fallback.shape("a\u{f00}", false)[1].x_advance, 0.);
+    }
+}
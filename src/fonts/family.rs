@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+
+use crate::FontSet;
+
+use super::Font;
+
+pub const NORMAL_WEIGHT: u16 = 400;
+pub const BOLD_WEIGHT: u16 = 700;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+}
+
+/// The width of a face, like `font-stretch` in CSS.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FontStretch {
+    UltraCondensed,
+    ExtraCondensed,
+    Condensed,
+    SemiCondensed,
+    #[default]
+    Normal,
+    SemiExpanded,
+    Expanded,
+    ExtraExpanded,
+    UltraExpanded,
+}
+
+impl FontStretch {
+    /// The width relative to the normal width, in percent.
+    pub fn percentage(self) -> u16 {
+        match self {
+            FontStretch::UltraCondensed => 50,
+            FontStretch::ExtraCondensed => 62,
+            FontStretch::Condensed => 75,
+            FontStretch::SemiCondensed => 87,
+            FontStretch::Normal => 100,
+            FontStretch::SemiExpanded => 112,
+            FontStretch::Expanded => 125,
+            FontStretch::ExtraExpanded => 150,
+            FontStretch::UltraExpanded => 200,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FontFace<'a, F: Font> {
+    pub font: &'a F,
+
+    /// From 100 (thin) to 900 (black), 400 being regular and 700 bold.
+    pub weight: u16,
+
+    pub style: FontStyle,
+    pub stretch: FontStretch,
+}
+
+// These are manually implemented because the derive macro would otherwise put a Copy bound on F.
+impl<'a, F: Font> Clone for FontFace<'a, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, F: Font> Copy for FontFace<'a, F> {}
+
+/// The faces of a font family. A face is picked for a requested weight, style and stretch with
+/// the font matching algorithm of CSS, so a family doesn't need to have every combination.
+#[derive(Debug)]
+pub struct FontFamily<'a, F: Font> {
+    pub faces: Vec<FontFace<'a, F>>,
+}
+
+impl<'a, F: Font> Clone for FontFamily<'a, F> {
+    fn clone(&self) -> Self {
+        FontFamily {
+            faces: self.faces.clone(),
+        }
+    }
+}
+
+impl<'a, F: Font> FontFamily<'a, F> {
+    pub fn new(faces: Vec<FontFace<'a, F>>) -> Self {
+        FontFamily { faces }
+    }
+
+    /// Returns the font of the face that matches best. The stretch is matched first, then the
+    /// style and then the weight. Panics if the family has no faces.
+    pub fn resolve(&self, weight: u16, style: FontStyle, stretch: FontStretch) -> &'a F {
+        self.faces
+            .iter()
+            .min_by_key(|face| {
+                (
+                    stretch_key(stretch.percentage(), face.stretch.percentage()),
+                    face.style != style,
+                    weight_key(weight, face.weight),
+                )
+            })
+            .expect("font family without faces")
+            .font
+    }
+
+    pub fn fonts(&self) -> impl Iterator<Item = &'a F> + '_ {
+        self.faces.iter().map(|face| face.font)
+    }
+}
+
+impl<'a, F: Font> From<FontSet<'a, F>> for FontFamily<'a, F> {
+    fn from(set: FontSet<'a, F>) -> Self {
+        let face = |font, weight, style| FontFace {
+            font,
+            weight,
+            style,
+            stretch: FontStretch::Normal,
+        };
+
+        FontFamily::new(vec![
+            face(set.regular, NORMAL_WEIGHT, FontStyle::Normal),
+            face(set.bold, BOLD_WEIGHT, FontStyle::Normal),
+            face(set.italic, NORMAL_WEIGHT, FontStyle::Italic),
+            face(set.bold_italic, BOLD_WEIGHT, FontStyle::Italic),
+        ])
+    }
+}
+
+/// Narrower widths are preferred for normal and condensed requests, wider ones otherwise.
+fn stretch_key(desired: u16, available: u16) -> (bool, u16) {
+    if desired <= 100 {
+        (available > desired, available.abs_diff(desired))
+    } else {
+        (available < desired, available.abs_diff(desired))
+    }
+}
+
+/// For requests between 400 and 500 the weights up to 500 are tried first, then the lighter ones
+/// and then the heavier ones. Lighter requests prefer lighter weights and heavier requests prefer
+/// heavier weights.
+fn weight_key(desired: u16, available: u16) -> (u8, u16) {
+    let distance = available.abs_diff(desired);
+
+    if (400..=500).contains(&desired) {
+        if (desired..=500).contains(&available) {
+            (0, distance)
+        } else if available < desired {
+            (1, distance)
+        } else {
+            (2, distance)
+        }
+    } else if desired < 400 {
+        ((available > desired) as u8, distance)
+    } else {
+        ((available < desired) as u8, distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::PdfDocument;
+
+    use crate::fonts::builtin::BuiltinFont;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let doc = PdfDocument::empty("");
+
+        let light = BuiltinFont::helvetica(&doc).unwrap();
+        let regular = BuiltinFont::helvetica(&doc).unwrap();
+        let semibold = BuiltinFont::helvetica_bold(&doc).unwrap();
+        let italic = BuiltinFont::helvetica_oblique(&doc).unwrap();
+        let condensed = BuiltinFont::courier(&doc).unwrap();
+
+        let face = |font, weight, style, stretch| FontFace {
+            font,
+            weight,
+            style,
+            stretch,
+        };
+
+        let family = FontFamily::new(vec![
+            face(&light, 300, FontStyle::Normal, FontStretch::Normal),
+            face(&regular, 400, FontStyle::Normal, FontStretch::Normal),
+            face(&semibold, 600, FontStyle::Normal, FontStretch::Normal),
+            face(&italic, 400, FontStyle::Italic, FontStretch::Normal),
+            face(&condensed, 400, FontStyle::Normal, FontStretch::Condensed),
+        ]);
+
+        let resolve = |weight, style, stretch| family.resolve(weight, style, stretch) as *const _;
+
+        use FontStretch::*;
+        use FontStyle::*;
+
+        assert_eq!(resolve(400, Normal, Normal), &regular as *const _);
+        assert_eq!(resolve(500, Normal, Normal), &regular as *const _);
+        assert_eq!(resolve(700, Normal, Normal), &semibold as *const _);
+        assert_eq!(resolve(200, Normal, Normal), &light as *const _);
+        assert_eq!(resolve(350, Normal, Normal), &light as *const _);
+
+        // There's no bold italic, so the weight is given up for the style.
+        assert_eq!(resolve(700, Italic, Normal), &italic as *const _);
+
+        assert_eq!(resolve(700, Normal, SemiCondensed), &condensed as *const _);
+        assert_eq!(resolve(400, Normal, Expanded), &regular as *const _);
+    }
+
+    #[test]
+    fn test_weight_key() {
+        let order = |desired| {
+            let mut weights = vec![100, 300, 400, 450, 500, 600, 900];
+            weights.sort_by_key(|&weight| weight_key(desired, weight));
+            weights
+        };
+
+        assert_eq!(order(400), vec![400, 450, 500, 300, 100, 600, 900]);
+        assert_eq!(order(300), vec![300, 100, 400, 450, 500, 600, 900]);
+        assert_eq!(order(600), vec![600, 900, 500, 450, 400, 300, 100]);
+    }
+}
@@ -1,6 +1,12 @@
-use printpdf::IndirectFontRef;
+use printpdf::{types::pdf_layer::GappedTextElement, IndirectFontRef, PdfLayerReference};
+
+use crate::Pdf;
 
 pub mod builtin;
+pub mod fallback;
+pub mod family;
+pub mod sfnt;
+pub mod subset;
 pub mod truetype;
 
 pub struct HMetrics {
@@ -12,6 +18,21 @@ pub struct GeneralMetrics {
     pub line_height: f64,
 }
 
+/// A glyph produced by [Font::shape]. All lengths are in font units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph_id: u32,
+
+    /// The byte offset in the shaped text of the first character this glyph was produced from.
+    pub cluster: usize,
+
+    /// The advance including kerning.
+    pub x_advance: f64,
+
+    pub x_offset: f64,
+    pub y_offset: f64,
+}
+
 pub trait Font {
     fn indirect_font_ref(&self) -> &IndirectFontRef;
 
@@ -20,4 +41,84 @@ pub trait Font {
     fn units_per_em(&self) -> u16;
 
     fn general_metrics(&self) -> GeneralMetrics;
+
+    /// Whether the font has a glyph for the character. The default implementation assumes that it
+    /// has every character, so the font is never skipped in a [fallback::FontFallback].
+    fn has_glyph(&self, _c: char) -> bool {
+        true
+    }
+
+    /// Converts a run of text with a single direction into positioned glyphs in visual order. The
+    /// default implementation produces one glyph per character with the codepoint as the glyph id
+    /// and doesn't do kerning or ligatures.
+    fn shape(&self, text: &str, rtl: bool) -> Vec<ShapedGlyph> {
+        let glyphs = text.char_indices().map(|(cluster, c)| ShapedGlyph {
+            glyph_id: c as u32,
+            cluster,
+            x_advance: self.codepoint_h_metrics(c as u32).advance_width,
+            x_offset: 0.,
+            y_offset: 0.,
+        });
+
+        if rtl {
+            glyphs.rev().collect()
+        } else {
+            glyphs.collect()
+        }
+    }
+
+    /// Writes glyphs produced by [Font::shape] from `text` at the current text cursor. This has to
+    /// be called inside of a text section after the font has been set to
+    /// [Font::indirect_font_ref]. `word_spacing` is added after every space and is in font units.
+    ///
+    /// Implementations need to position the glyphs exactly as shaped, since that's what the text
+    /// gets measured with. Embedded fonts record the glyphs in [Pdf::glyph_usage] so they can be
+    /// subset when the document is saved.
+    fn write_glyphs(
+        &self,
+        _pdf: &mut Pdf,
+        layer: &PdfLayerReference,
+        _size: f64,
+        text: &str,
+        glyphs: &[ShapedGlyph],
+        word_spacing: f64,
+    ) {
+        // The default implementation uses codepoints as glyph ids. The text is split after every
+        // space so the word spacing can be inserted as gaps.
+        let mut segments = vec![String::new()];
+
+        for glyph in glyphs {
+            // Soft hyphens are only left at the end of a line, where they have to be visible. The
+            // encoding of the builtin fonts doesn't have them, so they are written as hyphens.
+            segments
+                .last_mut()
+                .unwrap()
+                .extend(char::from_u32(glyph.glyph_id).map(|c| match c {
+                    '\u{00ad}' => '-',
+                    c => c,
+                }));
+
+            if text[glyph.cluster..].starts_with(' ') {
+                segments.push(String::new());
+            }
+        }
+
+        if word_spacing == 0. {
+            layer.write_text(segments.concat(), self.indirect_font_ref());
+        } else {
+            let gap = word_spacing * 1000. / self.units_per_em() as f64;
+            let last = segments.len() - 1;
+
+            layer.write_gapped_text(
+                segments.iter().enumerate().flat_map(|(i, s)| {
+                    std::iter::once(GappedTextElement::Text(s.as_str())).chain(if i < last {
+                        Some(GappedTextElement::Gap(gap))
+                    } else {
+                        None
+                    })
+                }),
+                self.indirect_font_ref(),
+            );
+        }
+    }
 }
@@ -0,0 +1,606 @@
+use rustybuzz::{
+    ttf_parser::{self, GlyphId, OutlineBuilder, RawFace, Tag},
+    Variation,
+};
+
+/// Copies a face out of a font collection (.ttc/.otc) into a font file of its own, since a
+/// collection can't be embedded.
+pub fn extract_face(data: &[u8], index: u32) -> Option<Vec<u8>> {
+    let face = RawFace::parse(data, index).ok()?;
+
+    let tables = face
+        .table_records
+        .into_iter()
+        .map(|record| Some((record.tag, face.table(record.tag)?.to_vec())))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(write_font(tables))
+}
+
+/// Creates a static instance of a variable font with TrueType outlines at the given axis values.
+/// Axes that aren't given stay at their default. Returns `None` for fonts without `glyf` table
+/// and for unknown axes.
+///
+/// The outlines, advances and bounding boxes get the variations applied and the tables for
+/// varying them are removed. The weight and width classes in the `OS/2` table are set from the
+/// axis values, while the other metrics that `MVAR` would vary keep their default values. Hinting
+/// instructions are dropped.
+///
+/// The `fvar` table and the layout tables are kept, since their substitutions and positioning can
+/// depend on the variations too. So the instance is shaped with the same variations set again,
+/// which doesn't vary the metrics a second time: `gvar` is removed and `HVAR` is replaced by one
+/// without deltas, which only keeps shapers from estimating the advances from the outlines.
+pub fn instantiate(data: &[u8], variations: &[Variation]) -> Option<Vec<u8>> {
+    let raw_face = RawFace::parse(data, 0).ok()?;
+    let mut face = ttf_parser::Face::parse(data, 0).ok()?;
+
+    for variation in variations {
+        // Setting an axis the font doesn't have succeeds without doing anything.
+        face.variation_axes()
+            .into_iter()
+            .find(|axis| axis.tag == variation.tag)?;
+
+        face.set_variation(variation.tag, variation.value)?;
+    }
+
+    raw_face.table(tag(b"glyf"))?;
+
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    let mut hmtx = Vec::new();
+
+    let mut max_points = 0;
+    let mut max_contours = 0;
+    let mut bbox: Option<[i16; 4]> = None;
+
+    let mut advance_max = 0;
+    let mut min_left_side_bearing = i16::MAX;
+    let mut min_right_side_bearing = i16::MAX;
+    let mut x_max_extent = i16::MIN;
+
+    for id in 0..face.number_of_glyphs() {
+        let mut outline = Outline::default();
+        face.outline_glyph(GlyphId(id), &mut outline);
+
+        let advance = face.glyph_hor_advance(GlyphId(id)).unwrap_or(0);
+        advance_max = advance_max.max(advance);
+
+        loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+
+        let left_side_bearing = match outline.bbox() {
+            Some(glyph_bbox @ [x_min, _, x_max, _]) => {
+                outline.write(&mut glyf, glyph_bbox);
+
+                let points = outline.contours.iter().map(Vec::len).sum::<usize>();
+                max_points = max_points.max(points);
+                max_contours = max_contours.max(outline.contours.len());
+
+                bbox = Some(match bbox {
+                    Some([a, b, c, d]) => [
+                        a.min(glyph_bbox[0]),
+                        b.min(glyph_bbox[1]),
+                        c.max(glyph_bbox[2]),
+                        d.max(glyph_bbox[3]),
+                    ],
+                    None => glyph_bbox,
+                });
+
+                min_left_side_bearing = min_left_side_bearing.min(x_min);
+                min_right_side_bearing =
+                    min_right_side_bearing.min((advance as i32 - x_max as i32) as i16);
+                x_max_extent = x_max_extent.max(x_max);
+
+                x_min
+            }
+            None => 0,
+        };
+
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&left_side_bearing.to_be_bytes());
+    }
+
+    loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+
+    let mut head = raw_face.table(tag(b"head"))?.to_vec();
+    let mut hhea = raw_face.table(tag(b"hhea"))?.to_vec();
+    let mut maxp = raw_face.table(tag(b"maxp"))?.to_vec();
+
+    if head.len() < 54 || hhea.len() < 36 || maxp.len() < 32 {
+        return None;
+    }
+
+    if let Some(bbox) = bbox {
+        for (i, value) in bbox.iter().enumerate() {
+            set(&mut head, 36 + 2 * i, &value.to_be_bytes());
+        }
+    }
+
+    // Long offsets in `loca`.
+    set(&mut head, 50, &1u16.to_be_bytes());
+
+    set(&mut hhea, 10, &advance_max.to_be_bytes());
+
+    if bbox.is_some() {
+        set(&mut hhea, 12, &min_left_side_bearing.to_be_bytes());
+        set(&mut hhea, 14, &min_right_side_bearing.to_be_bytes());
+        set(&mut hhea, 16, &x_max_extent.to_be_bytes());
+    }
+
+    set(&mut hhea, 34, &face.number_of_glyphs().to_be_bytes());
+
+    set(&mut maxp, 6, &(max_points as u16).to_be_bytes());
+    set(&mut maxp, 8, &(max_contours as u16).to_be_bytes());
+
+    // The composite glyphs have been flattened and there are no instructions anymore.
+    for offset in [10, 12, 26, 28, 30] {
+        set(&mut maxp, offset, &0u16.to_be_bytes());
+    }
+
+    let mut tables: Vec<(Tag, Vec<u8>)> = vec![
+        (tag(b"glyf"), glyf),
+        (tag(b"loca"), loca),
+        (tag(b"hmtx"), hmtx),
+        (tag(b"head"), head),
+        (tag(b"hhea"), hhea),
+        (tag(b"maxp"), maxp),
+    ];
+
+    if let Some(os2) = raw_face.table(tag(b"OS/2")).filter(|os2| os2.len() >= 8) {
+        let mut os2 = os2.to_vec();
+
+        for axis in face.variation_axes() {
+            let value = variations
+                .iter()
+                .rev()
+                .find(|variation| variation.tag == axis.tag)
+                .map_or(axis.def_value, |variation| variation.value)
+                .clamp(axis.min_value, axis.max_value);
+
+            if axis.tag == tag(b"wght") {
+                let weight_class = value.round().clamp(1., 1000.) as u16;
+                set(&mut os2, 4, &weight_class.to_be_bytes());
+            } else if axis.tag == tag(b"wdth") {
+                set(&mut os2, 6, &width_class(value).to_be_bytes());
+            }
+        }
+
+        tables.push((tag(b"OS/2"), os2));
+    }
+
+    // Shapers estimate the advances of a variable font without `HVAR` from the outlines.
+    if raw_face.table(tag(b"fvar")).is_some() {
+        let hvar = empty_hvar(face.variation_axes().len(), face.number_of_glyphs());
+        tables.push((tag(b"HVAR"), hvar));
+    }
+
+    const REMOVED: [&[u8; 4]; 11] = [
+        b"gvar", b"HVAR", b"VVAR", b"MVAR", b"cvar", b"cvt ", b"fpgm", b"prep", b"hdmx", b"LTSH",
+        b"VDMX",
+    ];
+
+    for record in raw_face.table_records {
+        let replaced = tables.iter().any(|&(t, _)| t == record.tag);
+        let removed = REMOVED.iter().any(|&t| tag(t) == record.tag);
+
+        if !replaced && !removed {
+            tables.push((record.tag, raw_face.table(record.tag)?.to_vec()));
+        }
+    }
+
+    Some(write_font(tables))
+}
+
+/// The axis values of the named instance of a variable font with the subfamily name, such as
+/// "Bold" or "Condensed Light".
+pub fn named_instance(data: &[u8], name: &str) -> Option<Vec<Variation>> {
+    let face = ttf_parser::Face::parse(data, 0).ok()?;
+    let fvar = face.raw_face().table(tag(b"fvar"))?;
+
+    let axes_offset = read_u16(fvar, 4)? as usize;
+    let axis_count = read_u16(fvar, 8)? as usize;
+    let axis_size = read_u16(fvar, 10)? as usize;
+    let instance_count = read_u16(fvar, 12)? as usize;
+    let instance_size = read_u16(fvar, 14)? as usize;
+
+    let axes: Vec<Tag> = face
+        .variation_axes()
+        .into_iter()
+        .map(|axis| axis.tag)
+        .collect();
+
+    (0..instance_count).find_map(|i| {
+        let start = axes_offset + axis_count * axis_size + i * instance_size;
+        let subfamily_name_id = read_u16(fvar, start)?;
+
+        let matches = face.names().into_iter().any(|n| {
+            // Unicode names are UTF-16BE.
+            n.name_id == subfamily_name_id
+                && n.is_unicode()
+                && n.name
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .eq(name.encode_utf16())
+        });
+
+        if !matches {
+            return None;
+        }
+
+        axes.iter()
+            .enumerate()
+            .map(|(axis, &tag)| {
+                let offset = start + 4 + 4 * axis;
+                let value = i32::from_be_bytes(fvar.get(offset..offset + 4)?.try_into().ok()?);
+
+                Some(Variation {
+                    tag,
+                    value: value as f32 / 65536.,
+                })
+            })
+            .collect()
+    })
+}
+
+/// An `HVAR` table with an empty delta set for every glyph, so that the advances in `hmtx` are used
+/// as they are.
+fn empty_hvar(axis_count: u16, glyph_count: u16) -> Vec<u8> {
+    let mut hvar = Vec::new();
+
+    // The version and the offset of the item variation store. There are no delta-set index maps.
+    hvar.extend_from_slice(&0x00010000u32.to_be_bytes());
+    hvar.extend_from_slice(&20u32.to_be_bytes());
+    hvar.extend_from_slice(&[0; 12]);
+
+    // The item variation store with the offsets of the region list and the only variation data.
+    hvar.extend_from_slice(&1u16.to_be_bytes());
+    hvar.extend_from_slice(&12u32.to_be_bytes());
+    hvar.extend_from_slice(&1u16.to_be_bytes());
+    hvar.extend_from_slice(&16u32.to_be_bytes());
+
+    // A region list without regions.
+    hvar.extend_from_slice(&axis_count.to_be_bytes());
+    hvar.extend_from_slice(&0u16.to_be_bytes());
+
+    // Variation data that doesn't refer to any regions, so every delta set is empty.
+    hvar.extend_from_slice(&glyph_count.to_be_bytes());
+    hvar.extend_from_slice(&[0; 4]);
+
+    hvar
+}
+
+/// The width class of the `OS/2` table that is closest to a `wdth` axis value, which is in percent
+/// of the normal width.
+fn width_class(width: f32) -> u16 {
+    const WIDTHS: [f32; 9] = [50., 62.5, 75., 87.5, 100., 112.5, 125., 150., 200.];
+
+    (1..)
+        .zip(WIDTHS)
+        .min_by(|(_, a), (_, b)| (a - width).abs().total_cmp(&(b - width).abs()))
+        .unwrap()
+        .0
+}
+
+fn tag(bytes: &[u8; 4]) -> Tag {
+    Tag::from_bytes(bytes)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn set(data: &mut [u8], offset: usize, value: &[u8]) {
+    data[offset..offset + value.len()].copy_from_slice(value);
+}
+
+/// Builds a font file from the tables. The checksums and the checksum adjustment in the `head`
+/// table get calculated.
+fn write_font(mut tables: Vec<(Tag, Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|&(tag, _)| tag);
+
+    let is_cff = tables
+        .iter()
+        .any(|&(t, _)| t == tag(b"CFF ") || t == tag(b"CFF2"));
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.max(1).leading_zeros() as u16;
+    let search_range: u16 = (1 << entry_selector) * 16;
+
+    let mut font = Vec::new();
+    font.extend_from_slice(if is_cff { b"OTTO" } else { &[0, 1, 0, 0] });
+    font.extend_from_slice(&count.to_be_bytes());
+    font.extend_from_slice(&search_range.to_be_bytes());
+    font.extend_from_slice(&entry_selector.to_be_bytes());
+    font.extend_from_slice(&(count * 16 - search_range).to_be_bytes());
+
+    let mut offset = 12 + tables.len() * 16;
+    let mut head_offset = None;
+
+    for (tag, data) in &mut tables {
+        if tag.to_bytes() == *b"head" {
+            set(data, 8, &[0; 4]);
+            head_offset = Some(offset);
+        }
+
+        font.extend_from_slice(&tag.to_bytes());
+        font.extend_from_slice(&checksum(data).to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+        offset += (data.len() + 3) & !3;
+    }
+
+    for (_, data) in &tables {
+        font.extend_from_slice(data);
+        font.resize((font.len() + 3) & !3, 0);
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0AFBAu32.wrapping_sub(checksum(&font));
+        set(&mut font, head_offset + 8, &adjustment.to_be_bytes());
+    }
+
+    font
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(bytes))
+    })
+}
+
+/// Collects an outline as TrueType contours with explicit on-curve points.
+#[derive(Default)]
+struct Outline {
+    contours: Vec<Vec<(i16, i16, bool)>>,
+}
+
+impl Outline {
+    fn push(&mut self, x: f32, y: f32, on_curve: bool) {
+        if let Some(contour) = self.contours.last_mut() {
+            contour.push((x.round() as i16, y.round() as i16, on_curve));
+        }
+    }
+
+    fn bbox(&self) -> Option<[i16; 4]> {
+        let mut points = self.contours.iter().flatten();
+        let &(x, y, _) = points.next()?;
+
+        Some(
+            points.fold([x, y, x, y], |[x_min, y_min, x_max, y_max], &(x, y, _)| {
+                [x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y)]
+            }),
+        )
+    }
+
+    /// Writes the outline as a simple glyph without instructions, padded to four bytes.
+    fn write(&self, glyf: &mut Vec<u8>, bbox: [i16; 4]) {
+        glyf.extend_from_slice(&(self.contours.len() as i16).to_be_bytes());
+
+        for value in bbox {
+            glyf.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let mut end = 0;
+
+        for contour in &self.contours {
+            end += contour.len();
+            glyf.extend_from_slice(&(end as u16 - 1).to_be_bytes());
+        }
+
+        // No instructions.
+        glyf.extend_from_slice(&0u16.to_be_bytes());
+
+        let points = || self.contours.iter().flatten();
+
+        // Without the short and same flags, all coordinates are written as 16 bit deltas.
+        glyf.extend(points().map(|&(_, _, on_curve)| on_curve as u8));
+
+        for coordinate in [|p: &(i16, i16, bool)| p.0, |p: &(i16, i16, bool)| p.1] {
+            let mut last = 0i16;
+
+            for point in points() {
+                let value = coordinate(point);
+                glyf.extend_from_slice(&value.wrapping_sub(last).to_be_bytes());
+                last = value;
+            }
+        }
+
+        glyf.resize((glyf.len() + 3) & !3, 0);
+    }
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.contours.push(Vec::new());
+        self.push(x, y, true);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push(x, y, true);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.push(x1, y1, false);
+        self.push(x, y, true);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        // Only CFF outlines have cubic curves, this is just a rough approximation.
+        self.quad_to((x1 + x2) / 2., (y1 + y2) / 2., x, y);
+    }
+
+    fn close(&mut self) {
+        // The outline ends at the start point again, which is implicit in TrueType.
+        if let Some(contour) = self.contours.last_mut() {
+            if contour.len() > 1 && contour.first() == contour.last() {
+                contour.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_VARIABLE: &[u8] = include_bytes!("../test_utils/TestCVARGVAROne.ttf");
+    const AMSTELVAR: &[u8] = include_bytes!("../test_utils/AmstelvarAlpha-Subset.ttf");
+
+    fn variation(tag: &[u8; 4], value: f32) -> Variation {
+        Variation {
+            tag: Tag::from_bytes(tag),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_named_instance() {
+        let axes = |name| {
+            named_instance(TEST_VARIABLE, name).map(|variations| {
+                variations
+                    .iter()
+                    .map(|v| (v.tag.to_bytes(), v.value))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            axes("Condensed Black"),
+            Some(vec![(*b"wght", 194.), (*b"wdth", 70.), (*b"opsz", 12.)])
+        );
+        assert_eq!(
+            axes("Thin Display"),
+            Some(vec![(*b"wght", 28.), (*b"wdth", 100.), (*b"opsz", 72.)])
+        );
+        assert_eq!(axes("Bold"), None);
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let variations = [variation(b"wght", 194.), variation(b"wdth", 70.)];
+        let instance = instantiate(TEST_VARIABLE, &variations).unwrap();
+
+        let raw_face = RawFace::parse(&instance, 0).unwrap();
+        for removed in [b"gvar", b"cvar", b"fpgm"] {
+            assert_eq!(raw_face.table(tag(removed)), None);
+        }
+
+        let face = ttf_parser::Face::parse(&instance, 0).unwrap();
+        assert_eq!(face.weight().to_number(), 194);
+        assert_eq!(face.width().to_number(), 3);
+
+        let mut varied = ttf_parser::Face::parse(TEST_VARIABLE, 0).unwrap();
+        for variation in &variations {
+            varied.set_variation(variation.tag, variation.value);
+        }
+
+        let o = face.glyph_index('o').unwrap();
+        let advance = face.glyph_hor_advance(o).unwrap();
+
+        // The advances don't change when the instance is used with the variations.
+        let mut face = face;
+        for variation in &variations {
+            face.set_variation(variation.tag, variation.value);
+        }
+        assert!(face.tables().hvar.is_some());
+        assert_eq!(face.glyph_hor_advance(o), Some(advance));
+
+        assert_eq!(Some(advance), varied.glyph_hor_advance(o));
+        assert_ne!(
+            Some(advance),
+            ttf_parser::Face::parse(TEST_VARIABLE, 0)
+                .unwrap()
+                .glyph_hor_advance(o)
+        );
+
+        let bbox = face.outline_glyph(o, &mut Outline::default()).unwrap();
+        let varied_bbox = varied.outline_glyph(o, &mut Outline::default()).unwrap();
+        assert!((bbox.x_max - varied_bbox.x_max).abs() <= 1);
+        assert!((bbox.y_max - varied_bbox.y_max).abs() <= 1);
+    }
+
+    #[test]
+    fn test_instantiate_mvar() {
+        let instance = instantiate(AMSTELVAR, &[variation(b"wght", 200.)]).unwrap();
+
+        let raw_face = RawFace::parse(&instance, 0).unwrap();
+        assert_eq!(raw_face.table(tag(b"MVAR")), None);
+        assert_eq!(raw_face.table(tag(b"gvar")), None);
+        assert!(raw_face.table(tag(b"fvar")).is_some());
+
+        let face = ttf_parser::Face::parse(&instance, 0).unwrap();
+        assert_eq!(face.weight().to_number(), 200);
+    }
+
+    #[test]
+    fn test_instantiate_default() {
+        // Axes that aren't given stay at their default.
+        let instance = instantiate(TEST_VARIABLE, &[]).unwrap();
+        let face = ttf_parser::Face::parse(&instance, 0).unwrap();
+        assert_eq!(face.weight().to_number(), 94);
+        assert_eq!(face.width().to_number(), 5);
+
+        assert_eq!(instantiate(TEST_VARIABLE, &[variation(b"slnt", 10.)]), None);
+    }
+
+    #[test]
+    fn test_width_class() {
+        assert_eq!(width_class(50.), 1);
+        assert_eq!(width_class(70.), 3);
+        assert_eq!(width_class(100.), 5);
+        assert_eq!(width_class(180.), 9);
+        assert_eq!(width_class(402.), 9);
+    }
+
+    #[test]
+    fn test_write_font() {
+        let font = write_font(vec![
+            (tag(b"name"), vec![1, 2, 3]),
+            (tag(b"cmap"), vec![4, 5, 6, 7, 8]),
+        ]);
+
+        let face = RawFace::parse(&font, 0).unwrap();
+
+        assert_eq!(face.table(tag(b"cmap")), Some(&[4, 5, 6, 7, 8][..]));
+        assert_eq!(face.table(tag(b"name")), Some(&[1, 2, 3][..]));
+
+        // The table records are sorted and the tables are padded to four bytes.
+        assert_eq!(&font[12..16], b"cmap");
+        assert_eq!(font.len(), 12 + 2 * 16 + 8 + 4);
+    }
+
+    #[test]
+    fn test_outline() {
+        let mut outline = Outline::default();
+
+        outline.move_to(0., 0.);
+        outline.line_to(10.4, 0.);
+        outline.quad_to(10., 10., 0., 10.);
+        outline.line_to(0., 0.);
+        outline.close();
+
+        assert_eq!(
+            outline.contours,
+            vec![vec![
+                (0, 0, true),
+                (10, 0, true),
+                (10, 10, false),
+                (0, 10, true)
+            ]]
+        );
+        assert_eq!(outline.bbox(), Some([0, 0, 10, 10]));
+
+        let mut glyf = Vec::new();
+        outline.write(&mut glyf, outline.bbox().unwrap());
+
+        // Header, end point, instruction length, flags, coordinates and padding.
+        assert_eq!(glyf.len(), 10 + 2 + 2 + 4 + 16 + 2);
+        assert_eq!(&glyf[..2], &[0, 1]);
+        assert_eq!(&glyf[10..14], &[0, 3, 0, 0]);
+        assert_eq!(&glyf[14..18], &[1, 1, 0, 1]);
+    }
+}
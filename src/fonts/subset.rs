@@ -0,0 +1,494 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use rustybuzz::ttf_parser;
+
+use super::ShapedGlyph;
+
+/// Records which glyphs of the embedded TrueType fonts were drawn, so that the fonts can be
+/// replaced by subsets when the document gets saved.
+#[derive(Default)]
+pub struct GlyphUsage {
+    fonts: Vec<UsedFont>,
+}
+
+struct UsedFont {
+    /// The [fingerprint] of the complete font file, as it was embedded by printpdf. Fonts loaded
+    /// separately from the same data share it.
+    fingerprint: u64,
+
+    /// The used glyph ids with the text they were produced from. The text is empty if the glyph
+    /// isn't the first one of its cluster.
+    glyphs: BTreeMap<u16, String>,
+}
+
+impl GlyphUsage {
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    /// Records glyphs produced by [super::Font::shape] from `text` for the font file with the
+    /// [fingerprint].
+    pub fn record(&mut self, fingerprint: u64, text: &str, glyphs: &[ShapedGlyph]) {
+        let font = match self.fonts.iter().position(|f| f.fingerprint == fingerprint) {
+            Some(index) => &mut self.fonts[index],
+            None => {
+                self.fonts.push(UsedFont {
+                    fingerprint,
+                    glyphs: BTreeMap::new(),
+                });
+                self.fonts.last_mut().unwrap()
+            }
+        };
+
+        let clusters: BTreeSet<usize> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        let mut mapped = HashSet::new();
+
+        for glyph in glyphs {
+            let mapping = font.glyphs.entry(glyph.glyph_id as u16).or_default();
+
+            // Only the first glyph of a cluster gets the text, otherwise it would be duplicated
+            // when copied.
+            if mapping.is_empty() && mapped.insert(glyph.cluster) {
+                let end = clusters
+                    .range(glyph.cluster + 1..)
+                    .next()
+                    .copied()
+                    .unwrap_or(text.len());
+
+                *mapping = text[glyph.cluster..end].to_string();
+            }
+        }
+    }
+
+    /// Replaces the recorded fonts in a saved document with subsets that only contain the used
+    /// glyphs. The widths and the ToUnicode map of each font are regenerated from the recorded
+    /// glyphs, so that ligatures and other glyphs that aren't in the cmap can still be copied.
+    /// The widths stay the ones printpdf writes, see [glyphs_with_widths]. A font that can't be
+    /// subset is left as it is, which is still correct, just bigger. So are the fonts in `keep`,
+    /// which are needed in full, like the fonts of form fields.
+    ///
+    /// printpdf embeds the font file as is, which is how the fonts are found.
+    pub fn subset_fonts(&self, doc: &mut Document, keep: &[ObjectId]) {
+        let type0_fonts: Vec<ObjectId> = doc
+            .objects
+            .iter()
+            .filter(|(_, object)| {
+                object
+                    .as_dict()
+                    .and_then(|dict| dict.get(b"Subtype"))
+                    .and_then(Object::as_name)
+                    .ok()
+                    == Some(&b"Type0"[..])
+            })
+            .map(|(&id, _)| id)
+            .filter(|id| !keep.contains(id))
+            .collect();
+
+        for type0_id in type0_fonts {
+            self.subset_font(doc, type0_id);
+        }
+    }
+
+    fn subset_font(&self, doc: &mut Document, type0_id: ObjectId) -> Option<()> {
+        let ids = FontObjects::find(doc, type0_id)?;
+
+        let file = doc.get_object(ids.file).and_then(Object::as_stream).ok()?;
+
+        let compressed = file.dict.has(b"Filter");
+        let data = if compressed {
+            file.decompressed_content().ok()?
+        } else {
+            file.content.clone()
+        };
+
+        // Fonts loaded separately from the same data are embedded as separate copies, which all
+        // get the glyphs recorded for any of them.
+        let fingerprint = fingerprint(&data);
+        let glyphs = &self
+            .fonts
+            .iter()
+            .find(|font| font.fingerprint == fingerprint)?
+            .glyphs;
+
+        if glyphs.is_empty() {
+            return None;
+        }
+
+        let face = ttf_parser::Face::parse(&data, 0).ok()?;
+
+        // The .notdef glyph always has to be there.
+        let glyph_ids: Vec<u16> = std::iter::once(0).chain(glyphs.keys().copied()).collect();
+
+        let subset = subsetter::subset(&data, 0, subsetter::Profile::pdf(&glyph_ids)).ok()?;
+
+        let mut file = Stream::new(
+            Dictionary::from_iter(vec![("Length1", (subset.len() as i64).into())]),
+            subset,
+        );
+
+        if compressed {
+            file.compress().ok()?;
+        }
+
+        doc.objects.insert(ids.file, Object::Stream(file));
+
+        let name = Object::Name(subset_name(&ids.base_font, &glyph_ids));
+
+        if let Ok(Object::Dictionary(descriptor)) = doc.get_object_mut(ids.descriptor) {
+            descriptor.set("FontName", name.clone());
+        }
+
+        if let Some(cid_font) = ids.cid_font_mut(doc) {
+            cid_font.set("BaseFont", name.clone());
+            let with_widths = glyphs_with_widths(&face);
+
+            cid_font.set(
+                "W",
+                widths(
+                    &face,
+                    glyphs.keys().copied().filter(|id| with_widths.contains(id)),
+                ),
+            );
+        }
+
+        let mut to_unicode = Stream::new(Dictionary::new(), to_unicode_cmap(glyphs).into_bytes());
+
+        if compressed {
+            to_unicode.compress().ok()?;
+        }
+
+        // The existing map gets replaced, so that it doesn't stay in the file unreferenced.
+        let to_unicode = match ids.to_unicode {
+            Some(id) => {
+                doc.objects.insert(id, Object::Stream(to_unicode));
+                id
+            }
+            None => doc.add_object(to_unicode),
+        };
+
+        if let Ok(Object::Dictionary(type0)) = doc.get_object_mut(ids.type0) {
+            type0.set("BaseFont", name);
+            type0.set("ToUnicode", Object::Reference(to_unicode));
+        }
+
+        Some(())
+    }
+}
+
+/// The objects making up an embedded TrueType font.
+struct FontObjects {
+    type0: ObjectId,
+
+    /// `None` if the CIDFont is a direct object in the `DescendantFonts` array, which is how
+    /// printpdf writes it.
+    cid_font: Option<ObjectId>,
+
+    descriptor: ObjectId,
+    file: ObjectId,
+    to_unicode: Option<ObjectId>,
+    base_font: Vec<u8>,
+}
+
+impl FontObjects {
+    fn find(doc: &Document, type0_id: ObjectId) -> Option<Self> {
+        let type0 = doc.get_dictionary(type0_id).ok()?;
+
+        let base_font = type0.get(b"BaseFont").and_then(Object::as_name).ok()?;
+
+        let (cid_font_id, cid_font) = match descendant(type0)? {
+            Object::Reference(id) => (Some(*id), doc.get_dictionary(*id).ok()?),
+            object => (None, object.as_dict().ok()?),
+        };
+
+        let descriptor = cid_font
+            .get(b"FontDescriptor")
+            .and_then(Object::as_reference)
+            .ok()?;
+
+        let file = doc
+            .get_dictionary(descriptor)
+            .and_then(|descriptor| descriptor.get(b"FontFile2"))
+            .and_then(Object::as_reference)
+            .ok()?;
+
+        Some(FontObjects {
+            type0: type0_id,
+            cid_font: cid_font_id,
+            descriptor,
+            file,
+            to_unicode: type0.get(b"ToUnicode").and_then(Object::as_reference).ok(),
+            base_font: base_font.to_vec(),
+        })
+    }
+
+    fn cid_font_mut<'a>(&self, doc: &'a mut Document) -> Option<&'a mut Dictionary> {
+        match self.cid_font {
+            Some(id) => doc.get_object_mut(id).and_then(Object::as_dict_mut).ok(),
+            None => doc
+                .get_object_mut(self.type0)
+                .and_then(Object::as_dict_mut)
+                .ok()?
+                .get_mut(b"DescendantFonts")
+                .and_then(Object::as_array_mut)
+                .ok()?
+                .first_mut()?
+                .as_dict_mut()
+                .ok(),
+        }
+    }
+}
+
+fn descendant(type0: &Dictionary) -> Option<&Object> {
+    type0
+        .get(b"DescendantFonts")
+        .and_then(Object::as_array)
+        .ok()?
+        .first()
+}
+
+/// Subset fonts need a name that starts with a tag of six uppercase letters. The tag is derived
+/// from the glyphs, so that the output stays deterministic.
+fn subset_name(base_font: &[u8], glyph_ids: &[u16]) -> Vec<u8> {
+    let mut hash = glyph_ids.iter().fold(0u32, |hash, &id| {
+        hash.wrapping_mul(31).wrapping_add(id as u32)
+    });
+
+    let mut name = Vec::with_capacity(base_font.len() + 7);
+
+    for _ in 0..6 {
+        name.push(b'A' + (hash % 26) as u8);
+        hash /= 26;
+    }
+
+    name.push(b'+');
+    name.extend_from_slice(base_font);
+    name
+}
+
+/// Identifies the data of a font file, so that the fonts loaded from it can be found in the
+/// saved document.
+pub(super) fn fingerprint(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The glyphs printpdf writes widths for, which are the ones reachable through the cmap. Readers
+/// use the default width of 1000 for all other glyphs, such as ligatures, so that's what the
+/// positioning of the glyphs has to be based on, whether the font gets subset or not.
+pub(super) fn glyphs_with_widths(face: &ttf_parser::Face) -> HashSet<u16> {
+    let mut glyphs = HashSet::new();
+
+    for subtable in face.tables().cmap.iter().flat_map(|cmap| cmap.subtables) {
+        if subtable.is_unicode() {
+            subtable.codepoints(|c| {
+                if let Some(id) = char::from_u32(c).and_then(|c| face.glyph_index(c)) {
+                    if id.0 != 0 {
+                        glyphs.insert(id.0);
+                    }
+                }
+            });
+        }
+    }
+
+    glyphs
+}
+
+/// The `W` array of a CIDFont with exact widths for the glyphs. Consecutive glyphs share an
+/// entry.
+fn widths(face: &ttf_parser::Face, glyph_ids: impl Iterator<Item = u16>) -> Object {
+    let scale = 1000. / face.units_per_em() as f64;
+
+    let mut entries = Vec::new();
+    let mut run: Vec<Object> = Vec::new();
+    let mut next = None;
+
+    for id in glyph_ids {
+        if next != Some(id) {
+            if !run.is_empty() {
+                entries.push(Object::Array(std::mem::take(&mut run)));
+            }
+
+            entries.push((id as i64).into());
+        }
+
+        let advance = face.glyph_hor_advance(ttf_parser::GlyphId(id)).unwrap_or(0);
+
+        run.push(Object::Real(advance as f64 * scale));
+        next = id.checked_add(1);
+    }
+
+    if !run.is_empty() {
+        entries.push(Object::Array(run));
+    }
+
+    Object::Array(entries)
+}
+
+/// A ToUnicode CMap mapping the two byte glyph ids to the text they were produced from.
+fn to_unicode_cmap(glyphs: &BTreeMap<u16, String>) -> String {
+    let mappings: Vec<String> = glyphs
+        .iter()
+        .filter(|(_, text)| !text.is_empty())
+        .map(|(id, text)| {
+            let utf16: String = text.encode_utf16().map(|u| format!("{:04X}", u)).collect();
+            format!("<{:04X}> <{}>", id, utf16)
+        })
+        .collect();
+
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n\
+         12 dict begin\n\
+         begincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n\
+         /CMapType 2 def\n\
+         1 begincodespacerange\n\
+         <0000> <FFFF>\n\
+         endcodespacerange\n",
+    );
+
+    // A bfchar block can have at most 100 entries.
+    for chunk in mappings.chunks(100) {
+        cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+
+        for mapping in chunk {
+            cmap.push_str(mapping);
+            cmap.push('\n');
+        }
+
+        cmap.push_str("endbfchar\n");
+    }
+
+    cmap.push_str(
+        "endcmap\n\
+         CMapName currentdict /CMap defineresource pop\n\
+         end\n\
+         end\n",
+    );
+
+    cmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        elements::{column::Column, text::Text},
+        fonts::truetype::TruetypeFont,
+        *,
+    };
+
+    const DEJAVU_SANS: &[u8] = include_bytes!("../test_utils/DejaVuSans.ttf");
+
+    fn glyph(glyph_id: u32, cluster: usize) -> ShapedGlyph {
+        ShapedGlyph {
+            glyph_id,
+            cluster,
+            x_advance: 0.,
+            x_offset: 0.,
+            y_offset: 0.,
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let data = fingerprint(b"font");
+
+        let mut usage = GlyphUsage::default();
+        assert!(usage.is_empty());
+
+        // An "fi" ligature, a glyph for a space and an accent that was decomposed into two
+        // glyphs of the same cluster.
+        usage.record(
+            data,
+            "fi a\u{301}",
+            &[glyph(10, 0), glyph(3, 2), glyph(20, 3), glyph(21, 3)],
+        );
+        usage.record(data, "i", &[glyph(30, 0)]);
+
+        assert_eq!(usage.fonts.len(), 1);
+
+        let glyphs: Vec<(u16, &str)> = usage.fonts[0]
+            .glyphs
+            .iter()
+            .map(|(&id, text)| (id, text.as_str()))
+            .collect();
+
+        assert_eq!(
+            glyphs,
+            vec![(3, " "), (10, "fi"), (20, "a\u{301}"), (21, ""), (30, "i")]
+        );
+
+        let cmap = to_unicode_cmap(&usage.fonts[0].glyphs);
+        assert!(cmap.contains(
+            "4 beginbfchar\n<0003> <0020>\n<000A> <00660069>\n<0014> <00610301>\n<001E> <0069>\n"
+        ));
+    }
+
+    #[test]
+    fn test_subset_name() {
+        assert_eq!(subset_name(b"F1", &[0]), b"AAAAAA+F1");
+        assert_eq!(subset_name(b"F1", &[0, 27]), b"BBAAAA+F1");
+    }
+
+    #[test]
+    fn test_same_data() {
+        type Fonts = (TruetypeFont<&'static [u8]>, TruetypeFont<&'static [u8]>);
+
+        let pdf = build_pdf(
+            "test",
+            (100., 100.),
+            |doc| {
+                Ok((
+                    TruetypeFont::new(doc, DEJAVU_SANS)?,
+                    TruetypeFont::new(doc, DEJAVU_SANS)?,
+                ))
+            },
+            |fonts: &Fonts| Column {
+                content: move |content| {
+                    content
+                        .add(&Text::basic("a", &fonts.0, 12.))?
+                        .add(&Text::basic("b", &fonts.1, 12.))?;
+                    None
+                },
+                gap: 0.,
+                collapse: true,
+            },
+        )
+        .unwrap();
+
+        let document = Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let to_unicode: Vec<String> = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .filter(|dict| dict.has(b"ToUnicode"))
+            .map(|dict| {
+                let id = dict
+                    .get(b"ToUnicode")
+                    .and_then(Object::as_reference)
+                    .unwrap();
+                let stream = document.get_object(id).and_then(Object::as_stream).unwrap();
+                let content = stream
+                    .decompressed_content()
+                    .unwrap_or_else(|_| stream.content.clone());
+
+                String::from_utf8(content).unwrap()
+            })
+            .collect();
+
+        // Each copy of the font has the glyphs drawn with either of them.
+        assert_eq!(to_unicode.len(), 2);
+
+        for cmap in to_unicode {
+            assert!(cmap.contains("> <0061>"));
+            assert!(cmap.contains("> <0062>"));
+        }
+    }
+}
@@ -1,29 +1,148 @@
-use std::ops::Deref;
+use std::{collections::HashSet, ops::Deref, sync::Arc};
 
-use printpdf::{IndirectFontRef, PdfDocumentReference};
+use lopdf::{content::Operation, Object, StringFormat};
+use printpdf::{IndirectFontRef, PdfDocumentReference, PdfLayerReference};
+use rustybuzz::Variation;
 use stb_truetype::FontInfo;
 
-use super::Font;
+use crate::{Error, Pdf, Result};
+
+use super::{
+    sfnt,
+    subset::{fingerprint, glyphs_with_widths},
+    Font, ShapedGlyph,
+};
 
 #[derive(Debug)]
 pub struct TruetypeFont<D: Deref<Target = [u8]>> {
     pub font_ref: IndirectFontRef,
-    pub font: FontInfo<D>,
+    pub font: FontInfo<FontData<D>>,
+
+    // The same data as in `font`, which doesn't give access to it. The shaping face is parsed
+    // from it for every call, which is cheap compared to the shaping itself.
+    data: FontData<D>,
+
+    // Variable fonts are embedded as a static instance, but the layout tables can still depend on
+    // the variations, so they are set on the face for shaping.
+    variations: Vec<Variation>,
+
+    // Identifies the font file in [Pdf::glyph_usage] without keeping a copy of it there.
+    fingerprint: u64,
+
+    // printpdf only writes widths for the glyphs that are reachable through the cmap. Readers use
+    // the default width of 1000 for all other glyphs (such as ligatures), so we have to correct
+    // for that when writing.
+    cmap_glyphs: HashSet<u16>,
+}
+
+/// The data of a [TruetypeFont], shared between its [FontInfo] and the shaping without copying
+/// it.
+#[derive(Debug)]
+pub struct FontData<D>(Arc<D>);
+
+impl<D> Clone for FontData<D> {
+    fn clone(&self) -> Self {
+        FontData(self.0.clone())
+    }
+}
+
+impl<D: Deref<Target = [u8]>> Deref for FontData<D> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0[..]
+    }
 }
 
+const SOFT_HYPHEN: char = '\u{00ad}';
+
 impl<D: AsRef<[u8]> + Deref<Target = [u8]>> TruetypeFont<D> {
-    pub fn new(doc: &PdfDocumentReference, bytes: D) -> Self {
-        let font_reader = std::io::Cursor::new(&bytes);
-        let pdf_font = doc.add_external_font(font_reader).unwrap();
-        let font_info = FontInfo::new(bytes, 0).unwrap();
+    /// Loads a single font file. Variable fonts are used at their default instance.
+    pub fn new(doc: &PdfDocumentReference, bytes: D) -> Result<Self> {
+        Self::with_instance_variations(doc, bytes, &[])
+    }
+
+    fn with_instance_variations(
+        doc: &PdfDocumentReference,
+        bytes: D,
+        variations: &[Variation],
+    ) -> Result<Self> {
+        let data = FontData(Arc::new(bytes));
+
+        // The font is checked before it's added to the document, so that nothing is embedded for
+        // an invalid font.
+        let face = rustybuzz::Face::from_slice(&data, 0)
+            .ok_or_else(|| Error::Font("the font can't be parsed".to_string()))?;
+
+        let cmap_glyphs = glyphs_with_widths(&face);
+
+        let font_info = FontInfo::new(data.clone(), 0)
+            .ok_or_else(|| Error::Font("the font can't be parsed".to_string()))?;
+
+        let font_reader = std::io::Cursor::new(&data[..]);
+        let pdf_font = doc
+            .add_external_font(font_reader)
+            .map_err(|error| Error::Font(error.to_string()))?;
 
-        TruetypeFont {
+        Ok(TruetypeFont {
             font_ref: pdf_font,
             font: font_info,
+            fingerprint: fingerprint(&data),
+            data,
+            variations: variations.to_vec(),
+            cmap_glyphs,
+        })
+    }
+}
+
+impl TruetypeFont<Vec<u8>> {
+    /// Loads the face with the index from a font collection (.ttc/.otc). The face is copied into
+    /// a font file of its own for embedding.
+    pub fn from_collection(doc: &PdfDocumentReference, bytes: &[u8], index: u32) -> Result<Self> {
+        Self::new(doc, extract_face(bytes, index)?)
+    }
+
+    /// Loads an instance of a variable font with the axis values, for example `wght=600` or
+    /// `wdth=75`. Axes that aren't given stay at their default. `index` is the face in a font
+    /// collection and is 0 for a single font file.
+    ///
+    /// The metrics and the embedded outlines are those of the instance. Only fonts with TrueType
+    /// outlines are supported.
+    pub fn with_variations(
+        doc: &PdfDocumentReference,
+        bytes: &[u8],
+        index: u32,
+        variations: &[Variation],
+    ) -> Result<Self> {
+        let face = extract_face(bytes, index)?;
+        let instance = sfnt::instantiate(&face, variations)
+            .ok_or_else(|| Error::Font("the variable font can't be instantiated".to_string()))?;
+
+        Self::with_instance_variations(doc, instance, variations)
+    }
+
+    /// Loads a named instance of a variable font, such as "SemiBold" or "Condensed Light".
+    /// Returns `None` if the font has no instance with the name.
+    pub fn named_instance(
+        doc: &PdfDocumentReference,
+        bytes: &[u8],
+        index: u32,
+        name: &str,
+    ) -> Result<Option<Self>> {
+        let face = extract_face(bytes, index)?;
+
+        match sfnt::named_instance(&face, name) {
+            Some(variations) => Self::with_variations(doc, &face, 0, &variations).map(Some),
+            None => Ok(None),
         }
     }
 }
 
+fn extract_face(bytes: &[u8], index: u32) -> Result<Vec<u8>> {
+    sfnt::extract_face(bytes, index)
+        .ok_or_else(|| Error::Font(format!("the font has no face with the index {}", index)))
+}
+
 impl<D: Deref<Target = [u8]>> Font for TruetypeFont<D> {
     fn indirect_font_ref(&self) -> &printpdf::IndirectFontRef {
         &self.font_ref
@@ -51,4 +170,284 @@ impl<D: Deref<Target = [u8]>> Font for TruetypeFont<D> {
             line_height: (v_metrics.ascent + v_metrics.descent.abs() + v_metrics.line_gap) as f64,
         }
     }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.font.find_glyph_index(c as u32) != 0
+    }
+
+    fn shape(&self, text: &str, rtl: bool) -> Vec<ShapedGlyph> {
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+
+        // Soft hyphens are default ignorables, so the shaper would make them invisible. At the end
+        // of a line they need to be displayed as a hyphen though.
+        if let Some(rest) = text.strip_suffix(SOFT_HYPHEN) {
+            buffer.push_str(&format!("{}-", rest));
+        } else {
+            buffer.push_str(text);
+        }
+
+        buffer.guess_segment_properties();
+        buffer.set_direction(if rtl {
+            rustybuzz::Direction::RightToLeft
+        } else {
+            rustybuzz::Direction::LeftToRight
+        });
+
+        let mut face = rustybuzz::Face::from_slice(&self.data, 0)
+            .expect("the face was parsed when the font was loaded");
+        face.set_variations(&self.variations);
+
+        let output = rustybuzz::shape(&face, &[], buffer);
+
+        output
+            .glyph_infos()
+            .iter()
+            .zip(output.glyph_positions())
+            .map(|(info, position)| ShapedGlyph {
+                glyph_id: info.glyph_id,
+                cluster: info.cluster as usize,
+                x_advance: position.x_advance as f64,
+                x_offset: position.x_offset as f64,
+                y_offset: position.y_offset as f64,
+            })
+            .collect()
+    }
+
+    /// Writes the shaped glyph ids as a `TJ` array, with the differences between the shaped
+    /// advances and the widths the reader will use as adjustments. Vertical offsets are not
+    /// supported by `TJ` and are ignored.
+    fn write_glyphs(
+        &self,
+        pdf: &mut Pdf,
+        layer: &PdfLayerReference,
+        _size: f64,
+        text: &str,
+        glyphs: &[ShapedGlyph],
+        word_spacing: f64,
+    ) {
+        pdf.glyph_usage.record(self.fingerprint, text, glyphs);
+
+        layer.add_op(Operation::new(
+            "TJ",
+            vec![Object::Array(self.tj_elements(text, glyphs, word_spacing))],
+        ));
+    }
+}
+
+impl<D: Deref<Target = [u8]>> TruetypeFont<D> {
+    /// The advance width a PDF reader will use for the glyph.
+    fn pdf_advance(&self, glyph_id: u32) -> f64 {
+        if self.cmap_glyphs.contains(&(glyph_id as u16)) {
+            self.font.get_glyph_h_metrics(glyph_id).advance_width as f64
+        } else {
+            self.font.units_per_em() as f64
+        }
+    }
+
+    /// The elements of the `TJ` array for [Font::write_glyphs].
+    fn tj_elements(&self, text: &str, glyphs: &[ShapedGlyph], word_spacing: f64) -> Vec<Object> {
+        let scale = 1000. / self.units_per_em() as f64;
+
+        let mut elements = Vec::new();
+        let mut run = Vec::new();
+
+        // How far the cursor is away from where the reader thinks it is.
+        let mut shift = 0.;
+
+        for glyph in glyphs {
+            let shift_before = shift + glyph.x_offset;
+
+            if shift_before != 0. {
+                if !run.is_empty() {
+                    elements.push(Object::String(
+                        std::mem::take(&mut run),
+                        StringFormat::Hexadecimal,
+                    ));
+                }
+
+                // In a `TJ` array positive numbers move the cursor to the left.
+                elements.push((-shift_before * scale).into());
+            }
+
+            run.extend_from_slice(&(glyph.glyph_id as u16).to_be_bytes());
+
+            shift = glyph.x_advance - self.pdf_advance(glyph.glyph_id) - glyph.x_offset;
+
+            if text[glyph.cluster..].starts_with(' ') {
+                shift += word_spacing;
+            }
+        }
+
+        if !run.is_empty() {
+            elements.push(Object::String(run, StringFormat::Hexadecimal));
+        }
+
+        elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::PdfDocument;
+    use rustybuzz::ttf_parser::Tag;
+
+    use super::*;
+    use crate::text::{shape_line, text_width};
+
+    const DEJAVU_SANS: &[u8] = include_bytes!("../test_utils/DejaVuSans.ttf");
+    const TEST_COLLECTION: &[u8] = include_bytes!("../test_utils/TestCollection.ttc");
+    const TEST_VARIABLE: &[u8] = include_bytes!("../test_utils/TestCVARGVAROne.ttf");
+
+    /// Shapes the line and checks that a reader following the `TJ` array puts every glyph where
+    /// the shaper placed it and ends up at the measured width.
+    fn check_drawn_advance(font: &TruetypeFont<&[u8]>, text: &str) -> Vec<ShapedGlyph> {
+        let glyphs = shape_line(font, text, false);
+        let elements = font.tj_elements(text, &glyphs, 0.);
+
+        let mut reader_positions = Vec::new();
+        let mut reader_x = 0.;
+
+        for element in &elements {
+            match element {
+                Object::String(bytes, _) => {
+                    for id in bytes.chunks(2) {
+                        let glyph_id = u16::from_be_bytes([id[0], id[1]]) as u32;
+                        reader_positions.push(reader_x);
+                        reader_x += font.pdf_advance(glyph_id);
+                    }
+                }
+                // In a `TJ` array positive numbers move the cursor to the left.
+                Object::Real(adjustment) => reader_x -= adjustment * 2048. / 1000.,
+                _ => panic!("unexpected TJ element {:?}", element),
+            }
+        }
+
+        let mut shaped_x = 0.;
+        for (glyph, reader_x) in glyphs.iter().zip(&reader_positions) {
+            assert!((shaped_x + glyph.x_offset - reader_x).abs() < 0.01);
+            shaped_x += glyph.x_advance;
+        }
+        assert_eq!(reader_positions.len(), glyphs.len());
+
+        let last = glyphs.last().unwrap();
+        let drawn_width = reader_positions.last().unwrap() - last.x_offset + last.x_advance;
+        let measured_width = text_width(text, 2048., font, 0., 0.);
+        assert!((drawn_width - measured_width).abs() < 0.01);
+
+        glyphs
+    }
+
+    #[test]
+    fn test_kerning() {
+        let doc = PdfDocument::empty("");
+        let font = TruetypeFont::new(&doc, DEJAVU_SANS).unwrap();
+
+        let glyphs = check_drawn_advance(&font, "AV");
+
+        assert_eq!(glyphs.len(), 2);
+        assert!(glyphs[0].x_advance < font.codepoint_h_metrics('A' as u32).advance_width);
+    }
+
+    #[test]
+    fn test_ligature() {
+        let doc = PdfDocument::empty("");
+        let font = TruetypeFont::new(&doc, DEJAVU_SANS).unwrap();
+
+        let glyphs = check_drawn_advance(&font, "fit");
+
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(glyphs[0].cluster, 0);
+        assert_eq!(glyphs[1].cluster, 2);
+        assert_ne!(glyphs[0].glyph_id, font.font.find_glyph_index('f' as u32));
+    }
+
+    #[test]
+    fn test_complex_script() {
+        let doc = PdfDocument::empty("");
+        let font = TruetypeFont::new(&doc, DEJAVU_SANS).unwrap();
+
+        // Arabic letters take contextual forms and lam alef is a mandatory ligature.
+        let glyphs = check_drawn_advance(&font, "\u{633}\u{644}\u{627}\u{645}");
+
+        assert_eq!(glyphs.len(), 3);
+        assert_eq!(
+            glyphs.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>(),
+            [6, 2, 0],
+        );
+        assert_ne!(glyphs[2].glyph_id, font.font.find_glyph_index(0x633));
+    }
+
+    #[test]
+    fn test_collection() {
+        let doc = PdfDocument::empty("");
+
+        for index in 0..2 {
+            let font = TruetypeFont::from_collection(&doc, TEST_COLLECTION, index).unwrap();
+
+            assert_eq!(font.units_per_em(), 1000);
+            assert!(font.has_glyph('.'));
+        }
+
+        assert!(matches!(
+            TruetypeFont::from_collection(&doc, TEST_COLLECTION, 2),
+            Err(Error::Font(_))
+        ));
+    }
+
+    #[test]
+    fn test_variable_font() {
+        let doc = PdfDocument::empty("");
+
+        let advance = |font: &TruetypeFont<Vec<u8>>| {
+            let advance = font.codepoint_h_metrics('o' as u32).advance_width;
+
+            // The shaper has to agree with the widths of the embedded instance.
+            assert_eq!(font.shape("o", false)[0].x_advance, advance);
+
+            advance
+        };
+
+        let default = TruetypeFont::new(&doc, TEST_VARIABLE.to_vec()).unwrap();
+
+        let black = TruetypeFont::named_instance(&doc, TEST_VARIABLE, 0, "Black")
+            .unwrap()
+            .unwrap();
+
+        let wght = |value| Variation {
+            tag: Tag::from_bytes(b"wght"),
+            value,
+        };
+        let explicit =
+            TruetypeFont::with_variations(&doc, TEST_VARIABLE, 0, &[wght(194.)]).unwrap();
+
+        assert_ne!(advance(&black), advance(&default));
+        assert_eq!(advance(&black), advance(&explicit));
+
+        assert!(TruetypeFont::named_instance(&doc, TEST_VARIABLE, 0, "Bold")
+            .unwrap()
+            .is_none());
+
+        let slant = Variation {
+            tag: Tag::from_bytes(b"slnt"),
+            value: -10.,
+        };
+        assert!(matches!(
+            TruetypeFont::with_variations(&doc, TEST_VARIABLE, 0, &[slant]),
+            Err(Error::Font(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_font() {
+        let doc = PdfDocument::empty("");
+
+        assert!(matches!(
+            TruetypeFont::new(&doc, vec![0; 100]),
+            Err(Error::Font(_))
+        ));
+        assert!(matches!(
+            TruetypeFont::from_collection(&doc, b"not a font", 1),
+            Err(Error::Font(_))
+        ));
+    }
 }
@@ -0,0 +1,553 @@
+use std::collections::BTreeMap;
+
+use lopdf::{
+    content::{Content, Operation},
+    Dictionary, Document, Object, ObjectId, Stream,
+};
+use printpdf::PdfLayerReference;
+
+use crate::{
+    links::add_annotation,
+    outline::text_string,
+    output::{catalog_mut, page_id, plain_content},
+    utils::mm_to_pt,
+    Location, Result,
+};
+
+/// The tag of the marked-content sequences the appearances of widgets are drawn in. They are moved
+/// from the page into the appearance streams when the document is saved.
+const APPEARANCE_TAG: &[u8] = b"LaserPdfAppearance";
+
+/// The appearance state of buttons that aren't selected.
+pub const OFF_STATE: &str = "Off";
+
+/// The appearance state of checked checkboxes.
+pub const CHECKED_STATE: &str = "Yes";
+
+/// The kinds of form fields with their default values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Text {
+        value: String,
+        multiline: bool,
+        max_length: Option<u32>,
+    },
+
+    Checkbox {
+        checked: bool,
+    },
+
+    /// A button of a radio group, which are the buttons with the same name. The value is what the
+    /// group is set to when the button is selected.
+    RadioButton {
+        value: String,
+        selected: bool,
+    },
+
+    Dropdown {
+        options: Vec<String>,
+        selected: Option<usize>,
+    },
+
+    /// A field that a signature can be added to later.
+    Signature,
+}
+
+impl Field {
+    fn field_type(&self) -> &'static [u8] {
+        match self {
+            Field::Text { .. } => b"Tx",
+            Field::Checkbox { .. } | Field::RadioButton { .. } => b"Btn",
+            Field::Dropdown { .. } => b"Ch",
+            Field::Signature => b"Sig",
+        }
+    }
+
+    fn flags(&self) -> i64 {
+        match *self {
+            Field::Text {
+                multiline: true, ..
+            } => 1 << 12,
+
+            // Radio, and no toggling off by clicking the selected button again.
+            Field::RadioButton { .. } => 1 << 15 | 1 << 14,
+
+            // Combo.
+            Field::Dropdown { .. } => 1 << 17,
+            _ => 0,
+        }
+    }
+
+    /// The appearance states of buttons, with the selected one first.
+    pub fn states(&self) -> Vec<&str> {
+        match self {
+            Field::Checkbox { .. } => vec![CHECKED_STATE, OFF_STATE],
+            Field::RadioButton { value, .. } => vec![value, OFF_STATE],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The value of the field with this widget as the first one.
+    fn value(&self) -> Option<Object> {
+        match self {
+            Field::Text { value, .. } => Some(text_string(value)),
+            Field::Checkbox { checked: true } => Some(Object::Name(CHECKED_STATE.into())),
+            Field::Checkbox { checked: false } => Some(Object::Name(OFF_STATE.into())),
+            Field::RadioButton { .. } => None,
+            Field::Dropdown { options, selected } => selected
+                .and_then(|selected| options.get(selected))
+                .map(|option| text_string(option)),
+            Field::Signature => None,
+        }
+    }
+}
+
+struct Widget {
+    name: String,
+    field: Field,
+    page: usize,
+
+    /// The area of the widget on the page in mm.
+    rect: [f64; 4],
+
+    /// The area the appearance was drawn in, which differs from the rectangle when the location is
+    /// scaled.
+    bbox: [f64; 4],
+    scale: f64,
+}
+
+/// The interactive form of the document. Each widget is an area of a page that shows a field,
+/// where widgets with the same name show the same field.
+#[derive(Default)]
+pub struct Forms {
+    widgets: Vec<Widget>,
+}
+
+impl Forms {
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+
+    /// Adds a widget for a rectangle with the size in mm, starting at the location and going down.
+    /// The appearance of the widget has to be drawn in the rectangle on the location between
+    /// [begin_appearance] and [end_appearance] with the returned index, once for each of the
+    /// [Field::states] or once for fields without states.
+    pub fn add(
+        &mut self,
+        location: &Location,
+        size: (f64, f64),
+        name: &str,
+        field: Field,
+    ) -> usize {
+        let scale = location.scale_factor;
+        let (x, y) = location.pos;
+
+        self.widgets.push(Widget {
+            name: name.to_string(),
+            field,
+            page: location.layer.page.0,
+            rect: [
+                x * scale,
+                (y - size.1) * scale,
+                (x + size.0) * scale,
+                y * scale,
+            ],
+            bbox: [x, y - size.1, x + size.0, y],
+            scale,
+        });
+
+        self.widgets.len() - 1
+    }
+
+    /// Adds the fields to a saved document, with the drawn appearances moved into appearance
+    /// streams. Returns the fonts of the default resources, which viewers use for the text typed
+    /// into the fields, so they must not be subset.
+    pub fn write(&self, doc: &mut Document) -> Result<Vec<ObjectId>> {
+        if self.widgets.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+        let mut appearances = BTreeMap::new();
+        let mut resources = BTreeMap::new();
+
+        for (index, &page) in pages.iter().enumerate() {
+            if !self.widgets.iter().any(|widget| widget.page == index) {
+                continue;
+            }
+
+            for stream in doc.get_page_contents(page) {
+                extract_appearances(doc, stream, &mut appearances)?;
+            }
+
+            resources.insert(index, page_resources(doc, page));
+        }
+
+        // The widgets grouped into fields by name, in the order of their first widget.
+        let mut fields: Vec<(&str, Vec<usize>)> = Vec::new();
+
+        for (index, widget) in self.widgets.iter().enumerate() {
+            match fields.iter_mut().find(|(name, _)| *name == widget.name) {
+                Some((_, widgets)) => widgets.push(index),
+                None => fields.push((&widget.name, vec![index])),
+            }
+        }
+
+        let mut field_ids = Vec::new();
+        let mut fonts = Dictionary::new();
+        let mut default_appearance = None;
+
+        for (name, widgets) in fields {
+            let first = &self.widgets[widgets[0]].field;
+            let field_id = doc.new_object_id();
+
+            let mut field = Dictionary::from_iter(vec![
+                ("FT", Object::Name(first.field_type().to_vec())),
+                ("T", text_string(name)),
+            ]);
+
+            if first.flags() != 0 {
+                field.set("Ff", Object::Integer(first.flags()));
+            }
+
+            let value = match first {
+                Field::RadioButton { .. } => Some(Object::Name(
+                    widgets
+                        .iter()
+                        .find_map(|&widget| match self.widgets[widget].field {
+                            Field::RadioButton {
+                                ref value,
+                                selected: true,
+                            } => Some(value.as_str()),
+                            _ => None,
+                        })
+                        .unwrap_or(OFF_STATE)
+                        .into(),
+                )),
+                _ => first.value(),
+            };
+
+            if let Some(ref value) = value {
+                field.set("V", value.clone());
+                field.set("DV", value.clone());
+            }
+
+            match *first {
+                Field::Text {
+                    max_length: Some(max_length),
+                    ..
+                } => field.set("MaxLen", Object::Integer(max_length as i64)),
+                Field::Dropdown { ref options, .. } => field.set(
+                    "Opt",
+                    Object::Array(options.iter().map(|option| text_string(option)).collect()),
+                ),
+                _ => (),
+            }
+
+            let mut kids = Vec::new();
+
+            for widget_index in widgets {
+                let widget = &self.widgets[widget_index];
+
+                let page = page_id(&pages, widget.page)?;
+
+                let resources = resources.get(&widget.page).cloned().unwrap_or(Object::Null);
+
+                let mut annotation = Dictionary::from_iter(vec![
+                    ("Type", Object::Name(b"Annot".to_vec())),
+                    ("Subtype", Object::Name(b"Widget".to_vec())),
+                    ("Rect", rectangle(widget.rect)),
+                    ("P", Object::Reference(page)),
+                    ("F", Object::Integer(4)),
+                    ("Parent", Object::Reference(field_id)),
+                ]);
+
+                let states = widget.field.states();
+
+                let normal = if states.is_empty() {
+                    let mut operations = appearances
+                        .remove(&(widget_index, String::new()))
+                        .unwrap_or_default();
+
+                    // The text of a signature field isn't edited, so it's left as it was drawn.
+                    let variable_text = match widget.field {
+                        Field::Signature => None,
+                        _ => text_appearance(&operations),
+                    };
+
+                    if let Some(appearance) = variable_text {
+                        let font = appearance.operations[0].operands[0].clone();
+
+                        if let Some(font) = font_resource(doc, &resources, &font) {
+                            fonts.extend(&font);
+                        }
+
+                        let appearance = Object::string_literal(appearance.encode()?);
+
+                        field.set("DA", appearance.clone());
+                        default_appearance.get_or_insert(appearance);
+
+                        // Marks the variable text, which viewers replace when the field is edited.
+                        let start = operations
+                            .iter()
+                            .position(|operation| operation.operator == "Tf")
+                            .unwrap_or(0);
+
+                        operations.insert(
+                            start,
+                            Operation::new("BMC", vec![Object::Name(b"Tx".to_vec())]),
+                        );
+                        operations.push(Operation::new("EMC", Vec::new()));
+                    }
+
+                    Object::Reference(add_appearance(doc, widget, operations, &resources))
+                } else {
+                    let state = match value {
+                        Some(Object::Name(ref value)) if value == states[0].as_bytes() => states[0],
+                        _ => OFF_STATE,
+                    };
+
+                    annotation.set("AS", Object::Name(state.into()));
+
+                    Object::Dictionary(Dictionary::from_iter(
+                        states
+                            .iter()
+                            .map(|&state| {
+                                let operations = appearances
+                                    .remove(&(widget_index, state.to_string()))
+                                    .unwrap_or_default();
+
+                                let appearance =
+                                    add_appearance(doc, widget, operations, &resources);
+
+                                (state, Object::Reference(appearance))
+                            })
+                            .collect::<Vec<_>>(),
+                    ))
+                };
+
+                annotation.set(
+                    "AP",
+                    Object::Dictionary(Dictionary::from_iter(vec![("N", normal)])),
+                );
+
+                let annotation = doc.add_object(annotation);
+                add_annotation(doc, page, annotation)?;
+                kids.push(Object::Reference(annotation));
+            }
+
+            field.set("Kids", Object::Array(kids));
+            doc.objects.insert(field_id, Object::Dictionary(field));
+            field_ids.push(Object::Reference(field_id));
+        }
+
+        let mut form = Dictionary::from_iter(vec![("Fields", Object::Array(field_ids))]);
+
+        let font_ids = fonts
+            .iter()
+            .filter_map(|(_, font)| font.as_reference().ok())
+            .collect();
+
+        if !fonts.is_empty() {
+            form.set(
+                "DR",
+                Dictionary::from_iter(vec![("Font", Object::Dictionary(fonts))]),
+            );
+        }
+
+        if let Some(default_appearance) = default_appearance {
+            form.set("DA", default_appearance);
+        }
+
+        let form = doc.add_object(form);
+        catalog_mut(doc)?.set("AcroForm", Object::Reference(form));
+
+        Ok(font_ids)
+    }
+}
+
+/// Starts the appearance of a widget from [Forms::add] for one of its states.
+pub fn begin_appearance(layer: &PdfLayerReference, widget: usize, state: Option<&str>) {
+    let mut properties = Dictionary::from_iter(vec![("Widget", Object::Integer(widget as i64))]);
+
+    if let Some(state) = state {
+        properties.set("State", Object::Name(state.into()));
+    }
+
+    layer.add_op(Operation::new(
+        "BDC",
+        vec![
+            Object::Name(APPEARANCE_TAG.to_vec()),
+            Object::Dictionary(properties),
+        ],
+    ));
+}
+
+pub fn end_appearance(layer: &PdfLayerReference) {
+    layer.add_op(Operation::new("EMC", Vec::new()));
+}
+
+/// Removes the appearances from a content stream, keyed by widget and state.
+fn extract_appearances(
+    doc: &mut Document,
+    stream: ObjectId,
+    appearances: &mut BTreeMap<(usize, String), Vec<Operation>>,
+) -> Result<()> {
+    let stream = doc.get_object_mut(stream).and_then(Object::as_stream_mut)?;
+    let content = Content::decode(&plain_content(stream)?)?;
+
+    let mut operations = Vec::with_capacity(content.operations.len());
+    let mut current: Option<((usize, String), Vec<Operation>, usize)> = None;
+    let mut found = false;
+
+    for operation in content.operations {
+        if let Some((_, ref mut captured, ref mut depth)) = current {
+            match operation.operator.as_str() {
+                "BDC" | "BMC" => *depth += 1,
+                "EMC" if *depth == 0 => {
+                    let (key, captured, _) = current.take().unwrap();
+                    appearances.insert(key, captured);
+                    continue;
+                }
+                "EMC" => *depth -= 1,
+                _ => (),
+            }
+
+            captured.push(operation);
+        } else if let Some(key) = appearance_key(&operation) {
+            current = Some((key, Vec::new(), 0));
+            found = true;
+        } else {
+            operations.push(operation);
+        }
+    }
+
+    if found {
+        stream.set_plain_content((Content { operations }).encode()?);
+    }
+
+    Ok(())
+}
+
+fn appearance_key(operation: &Operation) -> Option<(usize, String)> {
+    match (operation.operator.as_str(), operation.operands.as_slice()) {
+        ("BDC", [Object::Name(tag), Object::Dictionary(properties)]) if tag == APPEARANCE_TAG => {
+            let widget = properties.get(b"Widget").and_then(Object::as_i64).ok()?;
+
+            let state = properties
+                .get(b"State")
+                .and_then(Object::as_name_str)
+                .unwrap_or("");
+
+            Some((widget as usize, state.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// The default appearance of a text field, which is the font and the color of the first text in
+/// its appearance.
+fn text_appearance(operations: &[Operation]) -> Option<Content<Vec<Operation>>> {
+    let font = operations
+        .iter()
+        .find(|operation| operation.operator == "Tf")?;
+
+    let text = operations
+        .iter()
+        .position(|operation| operation.operator == "Tj" || operation.operator == "TJ")
+        .unwrap_or(operations.len());
+
+    let color = operations[..text]
+        .iter()
+        .rev()
+        .find(|operation| ["g", "rg", "k"].contains(&operation.operator.as_str()))
+        .cloned()
+        .unwrap_or_else(|| Operation::new("g", vec![Object::Integer(0)]));
+
+    Some(Content {
+        operations: vec![font.clone(), color],
+    })
+}
+
+fn add_appearance(
+    doc: &mut Document,
+    widget: &Widget,
+    operations: Vec<Operation>,
+    resources: &Object,
+) -> ObjectId {
+    let scale = widget.scale;
+    let zero = Object::Integer(0);
+
+    let mut dictionary = Dictionary::from_iter(vec![
+        ("Type", Object::Name(b"XObject".to_vec())),
+        ("Subtype", Object::Name(b"Form".to_vec())),
+        ("BBox", rectangle(widget.bbox)),
+        (
+            "Matrix",
+            Object::Array(vec![
+                Object::Real(scale),
+                zero.clone(),
+                zero.clone(),
+                Object::Real(scale),
+                zero.clone(),
+                zero,
+            ]),
+        ),
+    ]);
+
+    if !matches!(resources, Object::Null) {
+        dictionary.set("Resources", resources.clone());
+    }
+
+    let content = Content { operations }.encode().unwrap_or_default();
+
+    doc.add_object(Stream::new(dictionary, content))
+}
+
+/// The resources of a page as an object that can be shared with the appearance streams.
+fn page_resources(doc: &mut Document, page: ObjectId) -> Object {
+    let resources = doc
+        .get_dictionary(page)
+        .and_then(|page| page.get(b"Resources"))
+        .ok()
+        .cloned();
+
+    match resources {
+        Some(Object::Dictionary(resources)) => {
+            let id = doc.add_object(resources);
+
+            if let Ok(Object::Dictionary(page)) = doc.get_object_mut(page) {
+                page.set("Resources", Object::Reference(id));
+            }
+
+            Object::Reference(id)
+        }
+        Some(resources) => resources,
+        None => Object::Null,
+    }
+}
+
+/// The entry of the font with the name in the resources.
+fn font_resource(doc: &Document, resources: &Object, name: &Object) -> Option<Dictionary> {
+    let name = name.as_name().ok()?;
+
+    let resolve = |object: &Object| -> Option<Dictionary> {
+        match object {
+            Object::Reference(id) => doc.get_dictionary(*id).ok().cloned(),
+            Object::Dictionary(dictionary) => Some(dictionary.clone()),
+            _ => None,
+        }
+    };
+
+    let fonts = resolve(resolve(resources)?.get(b"Font").ok()?)?;
+
+    let mut font = Dictionary::new();
+    font.set(name.to_vec(), fonts.get(name).ok()?.clone());
+
+    Some(font)
+}
+
+fn rectangle(rect: [f64; 4]) -> Object {
+    Object::Array(rect.iter().map(|&c| Object::Real(mm_to_pt(c))).collect())
+}
@@ -0,0 +1,261 @@
+use std::{borrow::Cow, collections::HashMap, path::Path};
+
+const SOFT_HYPHEN: char = '\u{00ad}';
+
+/// Hyphenation using Liang's algorithm with TeX hyphenation patterns. Since the patterns are
+/// language specific there should be one [Hyphenator] per language.
+///
+/// Hyphenation works by inserting soft hyphens into the text, so the line breaking treats the
+/// hyphenation points exactly like manually inserted soft hyphens.
+#[derive(Clone, Debug)]
+pub struct Hyphenator {
+    patterns: HashMap<String, Vec<u8>>,
+    exceptions: HashMap<String, Vec<usize>>,
+    max_pattern_len: usize,
+
+    /// The minimum number of characters before the first hyphen in a word.
+    pub left_min: usize,
+
+    /// The minimum number of characters after the last hyphen in a word.
+    pub right_min: usize,
+}
+
+impl Hyphenator {
+    /// Parses hyphenation patterns. This accepts both TeX pattern files (`hyph-*.tex`), where the
+    /// patterns are in `\patterns{...}` and the exceptions in `\hyphenation{...}`, and plain
+    /// pattern lists (`hyph-*.pat.txt`) with one pattern per line.
+    pub fn from_patterns(source: &str) -> Self {
+        let source: String = source
+            .lines()
+            .map(|line| line.split('%').next().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut hyphenator = Hyphenator {
+            patterns: HashMap::new(),
+            exceptions: HashMap::new(),
+            max_pattern_len: 0,
+            left_min: 2,
+            right_min: 2,
+        };
+
+        if let Some(patterns) = tex_group(&source, "\\patterns") {
+            for pattern in patterns.split_whitespace() {
+                hyphenator.add_pattern(pattern);
+            }
+
+            if let Some(exceptions) = tex_group(&source, "\\hyphenation") {
+                for exception in exceptions.split_whitespace() {
+                    hyphenator.add_exception(exception);
+                }
+            }
+        } else {
+            for pattern in source.split_whitespace() {
+                hyphenator.add_pattern(pattern);
+            }
+        }
+
+        hyphenator
+    }
+
+    /// Loads hyphenation patterns from a file. See [Hyphenator::from_patterns] for the supported
+    /// formats.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::from_patterns(&std::fs::read_to_string(path)?))
+    }
+
+    /// Adds a pattern such as `hen5at` or `.ach4`.
+    pub fn add_pattern(&mut self, pattern: &str) {
+        let mut letters = String::new();
+        let mut values = vec![0];
+
+        for c in pattern.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                *values.last_mut().unwrap() = digit as u8;
+            } else {
+                letters.push(c);
+                values.push(0);
+            }
+        }
+
+        self.max_pattern_len = self.max_pattern_len.max(letters.chars().count());
+        self.patterns.insert(letters, values);
+    }
+
+    /// Adds an exception such as `ta-ble`, where the hyphens mark the only allowed hyphenation
+    /// points of the word.
+    pub fn add_exception(&mut self, exception: &str) {
+        let mut word = String::new();
+        let mut positions = Vec::new();
+        let mut len = 0;
+
+        for c in exception.chars() {
+            if c == '-' {
+                positions.push(len);
+            } else {
+                word.push(c);
+                len += 1;
+            }
+        }
+
+        self.exceptions.insert(word.to_lowercase(), positions);
+    }
+
+    /// Returns the character indices in the word before which a hyphen can be inserted.
+    pub fn hyphenation_points(&self, word: &str) -> Vec<usize> {
+        let chars: Vec<char> = word
+            .chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect();
+
+        let len = chars.len();
+
+        if len < self.left_min + self.right_min {
+            return Vec::new();
+        }
+
+        let allowed = |&i: &usize| i >= self.left_min && i <= len - self.right_min;
+
+        if let Some(positions) = self.exceptions.get(&chars.iter().collect::<String>()) {
+            return positions.iter().copied().filter(allowed).collect();
+        }
+
+        let dotted: Vec<char> = std::iter::once('.')
+            .chain(chars.iter().copied())
+            .chain(std::iter::once('.'))
+            .collect();
+
+        // `values[i]` is the value for the position before `dotted[i]`.
+        let mut values = vec![0u8; dotted.len() + 1];
+
+        for start in 0..dotted.len() {
+            for end in start + 1..=dotted.len().min(start + self.max_pattern_len) {
+                let key: String = dotted[start..end].iter().collect();
+
+                if let Some(pattern) = self.patterns.get(&key) {
+                    for (offset, &value) in pattern.iter().enumerate() {
+                        let value_ref = &mut values[start + offset];
+                        *value_ref = (*value_ref).max(value);
+                    }
+                }
+            }
+        }
+
+        // Position `i` in the word is position `i + 1` in the dotted word.
+        (1..len)
+            .filter(allowed)
+            .filter(|&i| values[i + 1] % 2 == 1)
+            .collect()
+    }
+
+    /// Inserts soft hyphens at all hyphenation points. Words that already contain soft hyphens are
+    /// left alone.
+    pub fn hyphenate<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let is_word_char = |c: char| c.is_alphabetic() || c == SOFT_HYPHEN;
+
+        let mut result = String::new();
+        let mut copied_to = 0;
+        let mut rest = text;
+
+        while let Some(start) = rest.find(is_word_char) {
+            let word_start = text.len() - rest.len() + start;
+            let word_len = rest[start..]
+                .find(|c: char| !is_word_char(c))
+                .unwrap_or(rest.len() - start);
+            let word = &text[word_start..word_start + word_len];
+
+            rest = &text[word_start + word_len..];
+
+            if word.contains(SOFT_HYPHEN) {
+                continue;
+            }
+
+            let points = self.hyphenation_points(word);
+
+            if points.is_empty() {
+                continue;
+            }
+
+            result.push_str(&text[copied_to..word_start]);
+
+            let mut points = points.into_iter().peekable();
+
+            for (i, c) in word.chars().enumerate() {
+                if points.next_if_eq(&i).is_some() {
+                    result.push(SOFT_HYPHEN);
+                }
+
+                result.push(c);
+            }
+
+            copied_to = word_start + word_len;
+        }
+
+        if copied_to == 0 {
+            Cow::Borrowed(text)
+        } else {
+            result.push_str(&text[copied_to..]);
+            Cow::Owned(result)
+        }
+    }
+}
+
+/// Returns the contents of the braces following `command`.
+fn tex_group<'a>(source: &'a str, command: &str) -> Option<&'a str> {
+    let start = source.find(command)? + command.len();
+    let start = start + source[start..].find('{')? + 1;
+    let end = start + source[start..].find('}')?;
+
+    Some(&source[start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERNS: &str = "
+        % Some of the english patterns relevant to the examples from Liang's thesis.
+        \\patterns{
+            .hy3p he2n hena4 hen5at 1na n2at 1tio 2io o2n
+        }
+        \\hyphenation{ta-ble}
+    ";
+
+    #[test]
+    fn test_hyphenation_points() {
+        let hyphenator = Hyphenator::from_patterns(PATTERNS);
+
+        assert_eq!(hyphenator.hyphenation_points("hyphenation"), vec![2, 6]);
+        assert_eq!(hyphenator.hyphenation_points("Hyphenation"), vec![2, 6]);
+        assert_eq!(hyphenator.hyphenation_points("table"), vec![2]);
+        assert_eq!(hyphenator.hyphenation_points("on"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_hyphenate() {
+        let hyphenator = Hyphenator::from_patterns(PATTERNS);
+
+        assert_eq!(
+            hyphenator.hyphenate("Hyphenation of a table."),
+            "Hy\u{00ad}phen\u{00ad}ation of a ta\u{00ad}ble."
+        );
+
+        // Manually hyphenated words are left as they are.
+        assert_eq!(
+            hyphenator.hyphenate("hyphe\u{00ad}nation"),
+            Cow::Borrowed("hyphe\u{00ad}nation")
+        );
+
+        assert!(matches!(
+            hyphenator.hyphenate("no points"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_plain_patterns() {
+        let hyphenator = Hyphenator::from_patterns(".hy3p\nhe2n\nhena4\nhen5at\n1na\nn2at\n");
+
+        assert_eq!(hyphenator.hyphenation_points("hyphenat"), vec![2, 6]);
+    }
+}
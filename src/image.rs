@@ -1,5 +1,29 @@
+use std::{cell::RefCell, path::Path};
+
 use serde::{de::Visitor, Deserializer};
 
+use crate::{Error, Result};
+
+thread_local! {
+    // The error of the last file that failed to load while deserializing. Serde can only pass it
+    // on as a message, so [crate::serde_elements::ElementValue::from_json] returns it from here to
+    // keep its variant.
+    static LOAD_ERROR: RefCell<Option<Error>> = RefCell::new(None);
+}
+
+/// Keeps the error for [take_load_error] and turns it into a deserialization error.
+fn load_error<E: serde::de::Error>(error: Error) -> E {
+    let deserialize_error = E::custom(&error);
+    LOAD_ERROR.with(|load_error| *load_error.borrow_mut() = Some(error));
+
+    deserialize_error
+}
+
+/// The error of the last file that failed to load while deserializing, if any.
+pub(crate) fn take_load_error() -> Option<Error> {
+    LOAD_ERROR.with(|load_error| load_error.borrow_mut().take())
+}
+
 pub fn deserialize_buffer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
     struct FileVisitor;
 
@@ -11,15 +35,15 @@ pub fn deserialize_buffer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<
         }
 
         fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            std::fs::read(v).map_err(|e| E::custom(e))
+            std::fs::read(v).map_err(|e| load_error(Error::Io(e)))
         }
 
         fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            std::fs::read(v).map_err(|e| E::custom(e))
+            std::fs::read(v).map_err(|e| load_error(Error::Io(e)))
         }
 
         fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            std::fs::read(v).map_err(|e| E::custom(e))
+            std::fs::read(v).map_err(|e| load_error(Error::Io(e)))
         }
     }
 
@@ -37,15 +61,15 @@ pub fn deserialize_svg<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usv
         }
 
         fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            usvg::Tree::from_str(v, &Default::default()).map_err(|e| E::custom(e))
+            parse_svg(v).map_err(load_error)
         }
 
         fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            usvg::Tree::from_str(v, &Default::default()).map_err(|e| E::custom(e))
+            parse_svg(v).map_err(load_error)
         }
 
         fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            usvg::Tree::from_str(&v, &Default::default()).map_err(|e| E::custom(e))
+            parse_svg(&v).map_err(load_error)
         }
     }
 
@@ -65,41 +89,64 @@ pub fn deserialize_svg_from_path<'de, D: Deserializer<'de>>(
         }
 
         fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            usvg::Tree::from_file(v, &Default::default()).map_err(|e| E::custom(e))
+            load_svg(v).map_err(load_error)
         }
 
         fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            usvg::Tree::from_file(v, &Default::default()).map_err(|e| E::custom(e))
+            load_svg(v).map_err(load_error)
         }
 
         fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            usvg::Tree::from_file(&v, &Default::default()).map_err(|e| E::custom(e))
+            load_svg(v).map_err(load_error)
         }
     }
 
     Ok(deserializer.deserialize_str(SvgVisitor)?)
 }
 
+pub fn parse_svg(text: &str) -> Result<usvg::Tree> {
+    Ok(usvg::Tree::from_str(text, &Default::default())?)
+}
+
+pub fn load_svg(path: impl AsRef<Path>) -> Result<usvg::Tree> {
+    let data = std::fs::read(path)?;
+
+    Ok(usvg::Tree::from_data(&data, &Default::default())?)
+}
+
 #[derive(Clone)]
 pub enum Image {
     Svg(usvg::Tree),
     Pixel(printpdf::image::DynamicImage),
 }
 
-pub fn deserialize_image<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Image, D::Error> {
-    struct ImageVisitor;
+impl Image {
+    /// Decodes an image from memory. Data that isn't in a known pixel format is parsed as SVG.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if printpdf::image::guess_format(bytes).is_ok() {
+            Ok(Image::Pixel(printpdf::image::load_from_memory(bytes)?))
+        } else {
+            Ok(Image::Svg(usvg::Tree::from_data(
+                bytes,
+                &Default::default(),
+            )?))
+        }
+    }
+
+    /// Loads an image file. Files with the extension `svg` are parsed as SVG.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
 
-    fn visit<E: serde::de::Error>(path: impl AsRef<std::path::Path>) -> Result<Image, E> {
-        if path.as_ref().extension().map_or(false, |e| e == "svg") {
-            Ok(Image::Svg(
-                usvg::Tree::from_file(path, &Default::default()).map_err(E::custom)?,
-            ))
+        if path.extension().map_or(false, |e| e == "svg") {
+            Ok(Image::Svg(load_svg(path)?))
         } else {
-            Ok(Image::Pixel(
-                printpdf::image::open(path).map_err(E::custom)?,
-            ))
+            Ok(Image::Pixel(printpdf::image::open(path)?))
         }
     }
+}
+
+pub fn deserialize_image<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Image, D::Error> {
+    struct ImageVisitor;
 
     impl<'de> Visitor<'de> for ImageVisitor {
         type Value = Image;
@@ -109,15 +156,15 @@ pub fn deserialize_image<'de, D: Deserializer<'de>>(deserializer: D) -> Result<I
         }
 
         fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            visit(v)
+            Image::from_path(v).map_err(load_error)
         }
 
         fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            visit(v)
+            Image::from_path(v).map_err(load_error)
         }
 
         fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            visit(v)
+            Image::from_path(v).map_err(load_error)
         }
     }
 
@@ -0,0 +1,366 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+};
+
+use lopdf::{content::Operation, Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::{
+    output::page_id,
+    utils::{mm_to_pt, pt_to_mm},
+    Error, Location, Result,
+};
+
+/// A page of an existing document, drawn with [crate::elements::imported_page::ImportedPage].
+/// Cloning it is cheap and the page is only added to the document once, no matter how often it's
+/// drawn.
+#[derive(Clone, Debug)]
+pub struct PdfPage {
+    form: Arc<Form>,
+}
+
+/// The content of a page as a Form XObject. The references of the stream point to the objects
+/// of the source document in `objects`, which get new ids when they are added to a document.
+#[derive(Debug)]
+struct Form {
+    stream: Stream,
+    objects: BTreeMap<ObjectId, Object>,
+
+    /// The visible area of the page in points, with the rotation of the page applied.
+    bbox: [f64; 4],
+}
+
+impl PdfPage {
+    /// Loads all pages of a document.
+    pub fn load(bytes: &[u8]) -> Result<Vec<Self>> {
+        let doc = Document::load_mem(bytes).map_err(|error| Error::Import(error.to_string()))?;
+
+        if doc.trailer.get(b"Encrypt").is_ok() {
+            return Err(Error::Import(
+                "encrypted documents can't be imported".to_string(),
+            ));
+        }
+
+        doc.get_pages()
+            .values()
+            .map(|&page| {
+                Ok(PdfPage {
+                    form: Arc::new(Form::new(&doc, page)?),
+                })
+            })
+            .collect()
+    }
+
+    pub fn load_file(path: impl AsRef<Path>) -> Result<Vec<Self>> {
+        Self::load(&std::fs::read(path)?)
+    }
+
+    /// The size of the visible area of the page in mm.
+    pub fn size(&self) -> (f64, f64) {
+        let [x0, y0, x1, y1] = self.form.bbox;
+
+        (pt_to_mm(x1 - x0), pt_to_mm(y1 - y0))
+    }
+
+    /// Draws the page with its top left corner at the location, scaled by the factor.
+    pub(crate) fn draw(&self, pdf: &mut crate::Pdf, location: &Location, scale: f64) {
+        let name = pdf.imported_pages.add(&self.form, location.layer.page.0);
+
+        let [x0, _, _, y1] = self.form.bbox;
+        let x = mm_to_pt(location.pos.0) - x0 * scale;
+        let y = mm_to_pt(location.pos.1) - y1 * scale;
+
+        let layer = &location.layer;
+        layer.add_op(Operation::new("q", vec![]));
+        layer.add_op(Operation::new(
+            "cm",
+            vec![
+                scale.into(),
+                0.into(),
+                0.into(),
+                scale.into(),
+                x.into(),
+                y.into(),
+            ],
+        ));
+        layer.add_op(Operation::new("Do", vec![Object::Name(name.into_bytes())]));
+        layer.add_op(Operation::new("Q", vec![]));
+    }
+}
+
+impl Form {
+    fn new(doc: &Document, page: ObjectId) -> Result<Self> {
+        let inherited = |key: &[u8]| inherited(doc, page, key);
+
+        let page_box = inherited(b"CropBox")
+            .or_else(|| inherited(b"MediaBox"))
+            .and_then(|page_box| rectangle(doc, page_box))
+            .ok_or_else(|| Error::Import("the page has no size".to_string()))?;
+
+        let rotation = inherited(b"Rotate")
+            .and_then(|rotation| rotation.as_i64().ok())
+            .unwrap_or(0)
+            .rem_euclid(360);
+
+        // Rotates clockwise, like viewers show rotated pages.
+        let matrix = match rotation {
+            90 => [0., -1., 1., 0.],
+            180 => [-1., 0., 0., -1.],
+            270 => [0., 1., -1., 0.],
+            _ => [1., 0., 0., 1.],
+        };
+
+        let mut content = Vec::new();
+
+        for id in doc.get_page_contents(page) {
+            if let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) {
+                content.extend(
+                    stream
+                        .decompressed_content()
+                        .unwrap_or_else(|_| stream.content.clone()),
+                );
+
+                // The streams of a page can split the content anywhere between tokens.
+                content.push(b'\n');
+            }
+        }
+
+        let mut dictionary = Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"XObject".to_vec())),
+            ("Subtype", Object::Name(b"Form".to_vec())),
+            (
+                "BBox",
+                page_box
+                    .iter()
+                    .map(|&x| x.into())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            (
+                "Matrix",
+                matrix
+                    .iter()
+                    .chain(&[0., 0.])
+                    .map(|&x| x.into())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+        ]);
+
+        let mut objects = BTreeMap::new();
+
+        for key in [&b"Resources"[..], b"Group"] {
+            if let Some(object) = inherited(key) {
+                collect_objects(doc, object, &mut objects);
+                dictionary.set(key.to_vec(), object.clone());
+            }
+        }
+
+        let [x0, y0, x1, y1] = page_box;
+        let corners = [(x0, y0), (x0, y1), (x1, y0), (x1, y1)]
+            .map(|(x, y)| (matrix[0] * x + matrix[2] * y, matrix[1] * x + matrix[3] * y));
+
+        let min = |f: fn(&(f64, f64)) -> f64| corners.iter().map(f).fold(f64::INFINITY, f64::min);
+        let max =
+            |f: fn(&(f64, f64)) -> f64| corners.iter().map(f).fold(f64::NEG_INFINITY, f64::max);
+
+        Ok(Form {
+            stream: Stream::new(dictionary, content),
+            objects,
+            bbox: [min(|c| c.0), min(|c| c.1), max(|c| c.0), max(|c| c.1)],
+        })
+    }
+}
+
+/// An entry of the page, which it can also inherit from the page tree.
+fn inherited<'a>(doc: &'a Document, page: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = doc.get_dictionary(page).ok()?;
+
+    loop {
+        if let Ok(object) = node.get(key) {
+            return Some(object);
+        }
+
+        node = node
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|parent| doc.get_dictionary(parent))
+            .ok()?;
+    }
+}
+
+fn rectangle(doc: &Document, object: &Object) -> Option<[f64; 4]> {
+    let number = |object: &Object| match doc.dereference(object).ok()?.1 {
+        &Object::Integer(number) => Some(number as f64),
+        &Object::Real(number) => Some(number),
+        _ => None,
+    };
+
+    match doc.dereference(object).ok()?.1 {
+        Object::Array(array) if array.len() == 4 => {
+            let [x0, y0, x1, y1] = [
+                number(&array[0])?,
+                number(&array[1])?,
+                number(&array[2])?,
+                number(&array[3])?,
+            ];
+
+            Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+        }
+        _ => None,
+    }
+}
+
+/// Collects the objects the object refers to, directly and indirectly. Pages aren't followed, so
+/// that the rest of the source document isn't copied along.
+fn collect_objects(doc: &Document, object: &Object, objects: &mut BTreeMap<ObjectId, Object>) {
+    match object {
+        Object::Reference(id) => {
+            if objects.contains_key(id) {
+                return;
+            }
+
+            let object = doc.get_object(*id).cloned().unwrap_or(Object::Null);
+
+            let is_page = match object {
+                Object::Dictionary(ref dictionary) => {
+                    dictionary.type_is(b"Page") || dictionary.type_is(b"Pages")
+                }
+                _ => false,
+            };
+
+            if is_page {
+                objects.insert(*id, Object::Null);
+            } else {
+                objects.insert(*id, object.clone());
+                collect_objects(doc, &object, objects);
+            }
+        }
+        Object::Array(array) => {
+            for object in array {
+                collect_objects(doc, object, objects);
+            }
+        }
+        Object::Dictionary(dictionary) => {
+            for (_, object) in dictionary.iter() {
+                collect_objects(doc, object, objects);
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, object) in stream.dict.iter() {
+                collect_objects(doc, object, objects);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn renumber(object: &mut Object, ids: &BTreeMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => *id = ids.get(id).copied().unwrap_or(*id),
+        Object::Array(array) => {
+            for object in array {
+                renumber(object, ids);
+            }
+        }
+        Object::Dictionary(dictionary) => renumber_dictionary(dictionary, ids),
+        Object::Stream(stream) => renumber_dictionary(&mut stream.dict, ids),
+        _ => (),
+    }
+}
+
+fn renumber_dictionary(dictionary: &mut Dictionary, ids: &BTreeMap<ObjectId, ObjectId>) {
+    for (_, object) in dictionary.iter_mut() {
+        renumber(object, ids);
+    }
+}
+
+/// The pages imported from other documents and the pages they are drawn on.
+#[derive(Default)]
+pub struct ImportedPages {
+    forms: Vec<(Arc<Form>, BTreeSet<usize>)>,
+}
+
+impl ImportedPages {
+    pub fn is_empty(&self) -> bool {
+        self.forms.is_empty()
+    }
+
+    /// Returns the name of the Form XObject in the resources of the page.
+    fn add(&mut self, form: &Arc<Form>, page: usize) -> String {
+        let index = match self.forms.iter().position(|(f, _)| Arc::ptr_eq(f, form)) {
+            Some(index) => index,
+            None => {
+                self.forms.push((form.clone(), BTreeSet::new()));
+                self.forms.len() - 1
+            }
+        };
+
+        self.forms[index].1.insert(page);
+
+        format!("LaserPdfImport{}", index)
+    }
+
+    /// Adds the Form XObjects of the imported pages to the document and to the resources of the
+    /// pages they are drawn on.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+        for (index, (form, used_on)) in self.forms.iter().enumerate() {
+            let ids: BTreeMap<ObjectId, ObjectId> = form
+                .objects
+                .keys()
+                .map(|&id| (id, doc.new_object_id()))
+                .collect();
+
+            for (id, object) in &form.objects {
+                let mut object = object.clone();
+                renumber(&mut object, &ids);
+                doc.objects.insert(ids[id], object);
+            }
+
+            let mut stream = form.stream.clone();
+            renumber_dictionary(&mut stream.dict, &ids);
+            let xobject = doc.add_object(stream);
+
+            for &page in used_on {
+                let page = page_id(&pages, page)?;
+                let resources = indirect_dictionary(doc, page, b"Resources")?;
+                let xobjects = indirect_dictionary(doc, resources, b"XObject")?;
+
+                doc.get_object_mut(xobjects)
+                    .and_then(Object::as_dict_mut)?
+                    .set(
+                        format!("LaserPdfImport{}", index),
+                        Object::Reference(xobject),
+                    );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The dictionary in the entry of the object as an indirect object, so that it can be changed.
+fn indirect_dictionary(doc: &mut Document, id: ObjectId, key: &[u8]) -> Result<ObjectId> {
+    let entry = doc.get_dictionary(id)?.get(key).ok().cloned();
+
+    match entry {
+        Some(Object::Reference(entry)) => Ok(entry),
+        entry => {
+            let dictionary = match entry {
+                Some(Object::Dictionary(dictionary)) => dictionary,
+                _ => Dictionary::new(),
+            };
+
+            let entry = doc.add_object(dictionary);
+
+            doc.get_object_mut(id)
+                .and_then(Object::as_dict_mut)?
+                .set(key.to_vec(), Object::Reference(entry));
+
+            Ok(entry)
+        }
+    }
+}
@@ -1,16 +1,48 @@
+pub mod attachments;
+pub mod conformance;
 pub mod elements;
+pub mod encryption;
+pub mod error;
 pub mod flex;
 pub mod fonts;
+pub mod forms;
+pub mod hyphenation;
 pub mod image;
+pub mod import;
+pub mod links;
+pub mod marks;
+pub mod metadata;
+pub mod outline;
+pub mod output;
+pub mod page_numbers;
+pub mod sections;
 pub mod serde_elements;
+pub mod signature;
+pub mod structure;
 pub mod test_utils;
 pub mod text;
 pub mod utils;
 
+use std::io::{BufWriter, Write};
+
+pub use error::{Error, Result};
+
+use attachments::Attachments;
+use conformance::Conformance;
 use elements::padding::Padding;
-use fonts::Font;
+use fonts::{subset::GlyphUsage, Font};
+use forms::Forms;
+use import::ImportedPages;
+use links::Links;
+use marks::Marks;
+use metadata::Metadata;
+use outline::Outline;
+use output::SaveOptions;
+use page_numbers::PageNumbers;
 use printpdf::{CurTransMat, Mm, PdfDocumentReference, PdfLayerReference};
+use sections::{Orientation, Section, Sections};
 use serde::{Deserialize, Serialize};
+use structure::Structure;
 
 pub const EMPTY_FIELD: &str = "—";
 
@@ -107,9 +139,142 @@ pub struct LineStyle {
     pub cap_style: LineCapStyle,
 }
 
+/// A document that is being built. It has to be saved with [Pdf::save] or the other saving
+/// methods, which add everything that is only recorded while drawing, like the links, the outline,
+/// the metadata and the font subsets.
 pub struct Pdf {
-    pub document: PdfDocumentReference,
+    // Private, so that it can't be saved directly, which would skip all of that.
+    pub(crate) document: PdfDocumentReference,
+
+    /// The page size of the section that is being drawn.
     pub page_size: (f64, f64),
+    pub glyph_usage: GlyphUsage,
+    pub links: Links,
+    pub outline: Outline,
+    pub page_numbers: PageNumbers,
+    pub marks: Marks,
+
+    /// Written when the document is saved. It can also be set while building the document with
+    /// [sections::Sections::metadata].
+    pub metadata: Metadata,
+
+    /// The PDF/A level the document is written in. It's checked when the document is saved.
+    pub conformance: Option<Conformance>,
+
+    pub attachments: Attachments,
+    pub structure: Structure,
+    pub forms: Forms,
+    pub imported_pages: ImportedPages,
+}
+
+impl Pdf {
+    pub fn new(document: PdfDocumentReference, page_size: (f64, f64)) -> Self {
+        Pdf {
+            document,
+            page_size,
+            glyph_usage: GlyphUsage::default(),
+            links: Links::default(),
+            outline: Outline::default(),
+            page_numbers: PageNumbers::default(),
+            marks: Marks::default(),
+            metadata: Metadata::default(),
+            conformance: None,
+            attachments: Attachments::default(),
+            structure: Structure::default(),
+            forms: Forms::default(),
+            imported_pages: ImportedPages::default(),
+        }
+    }
+
+    /// The printpdf document, for elements that need to add to it directly.
+    pub fn document(&self) -> &PdfDocumentReference {
+        &self.document
+    }
+
+    pub fn save<W: Write>(self, target: &mut W) -> Result<()> {
+        self.save_with_options(target, &SaveOptions::default())
+    }
+
+    pub fn to_bytes(self) -> Result<Vec<u8>> {
+        self.to_bytes_with_options(&SaveOptions::default())
+    }
+
+    pub fn to_bytes_with_options(self, options: &SaveOptions) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.save_with_options(&mut bytes, options)?;
+
+        Ok(bytes)
+    }
+
+    /// Saves the document. Imported pages are added as Form XObjects, the recorded links are added
+    /// as annotations, the bookmarks become the document outline, restarted page numbers become
+    /// page labels, the metadata is added to the info dictionary and the XMP metadata, the
+    /// attachments are embedded, the structure tree of a tagged document is added and the form
+    /// fields get their appearance streams. Then the embedded TrueType fonts, except the ones of
+    /// form fields, are replaced by subsets that only contain the glyphs that were drawn.
+    /// Encryption and the signature, if the options ask for them, are applied last.
+    pub fn save_with_options<W: Write>(self, target: &mut W, options: &SaveOptions) -> Result<()> {
+        let mut bytes = Vec::new();
+
+        options
+            .apply_to_document(self.document)
+            .save(&mut BufWriter::new(&mut bytes))
+            .map_err(|error| Error::Pdf(error.to_string()))?;
+
+        if self.glyph_usage.is_empty()
+            && self.links.is_empty()
+            && self.outline.is_empty()
+            && !self.page_numbers.has_restarts()
+            && self.metadata.is_empty()
+            && self.conformance.is_none()
+            && self.attachments.is_empty()
+            && !self.structure.is_enabled()
+            && self.forms.is_empty()
+            && self.imported_pages.is_empty()
+            && !options.modifies_saved_document()
+        {
+            return Ok(target.write_all(&bytes)?);
+        }
+
+        // printpdf doesn't give us access to the objects before they are written, so the saved
+        // document gets loaded again to be modified.
+        let mut document = lopdf::Document::load_mem(&bytes)?;
+
+        self.imported_pages.write(&mut document)?;
+        self.links.write(&mut document)?;
+        self.outline.write(&mut document)?;
+        self.page_numbers.write(&mut document)?;
+        self.metadata.write(&mut document)?;
+        self.attachments.write(&mut document)?;
+        self.structure.write(&mut document)?;
+        let form_fonts = self.forms.write(&mut document)?;
+        self.glyph_usage.subset_fonts(&mut document, &form_fonts);
+
+        if let Some(conformance) = self.conformance {
+            if options.encryption.is_some() {
+                return Err(Error::Conformance(
+                    "PDF/A doesn't allow encryption".to_string(),
+                ));
+            }
+
+            conformance.write(&mut document)?;
+        }
+
+        options.apply(&mut document)?;
+
+        match options.signature {
+            Some(ref signature) => {
+                // The signature covers the bytes of the whole document, except for itself.
+                let mut bytes = Vec::new();
+                document.save_to(&mut bytes)?;
+                signature.sign(&mut bytes)?;
+                target.write_all(&bytes)?;
+            }
+            None => document.save_to(target)?,
+        }
+
+        Ok(())
+    }
 }
 
 /// A position for an element to render at.
@@ -430,70 +595,81 @@ impl<'a, F: 'static, R: Element + 'a, O: FnOnce(&'a F) -> R> BuildElement<'a, F>
 pub fn build_pdf<F: 'static>(
     name: &str,
     page_size: (f64, f64),
-    build_fonts: impl FnOnce(&PdfDocumentReference) -> F,
+    build_fonts: impl FnOnce(&PdfDocumentReference) -> Result<F>,
     build_element: impl for<'a> BuildElement<'a, F>,
-) -> printpdf::PdfDocumentReference {
-    use printpdf::{
-        indices::{PdfLayerIndex, PdfPageIndex},
-        PdfDocument,
-    };
-
-    let (doc, page, layer) = PdfDocument::new(name, Mm(page_size.0), Mm(page_size.1), "Layer 0");
-    let mut page_idx = 0;
-
-    let mut pdf = Pdf {
-        document: doc,
-        page_size,
-    };
-
-    let do_break = &mut |pdf: &mut Pdf, location_idx, size| {
-        while page_idx <= location_idx {
-            pdf.document
-                .add_page(Mm(page_size.0), Mm(page_size.1), "Layer 0");
-            page_idx += 1;
-        }
-
-        let layer = pdf
-            .document
-            .get_page(PdfPageIndex((location_idx + 1) as usize))
-            .get_layer(PdfLayerIndex(0));
+) -> Result<Pdf> {
+    build_pdf_sections(name, build_fonts, |sections, fonts| {
+        sections.add(&Section {
+            page_size,
+            orientation: Orientation::Portrait,
+            restart_page_numbers: None,
+            element: &build_element.call(fonts),
+        });
+    })
+}
 
-        Location {
-            layer,
-            pos: (0., page_size.1),
-            scale_factor: 1.,
-        }
-    };
+/// Like [build_pdf], but the document is laid out twice. The anchors and the page count of the
+/// first pass are available in the second one, so that elements like
+/// [elements::table_of_contents::TableOfContents] can show the pages of anchors that come after
+/// them and page decorations can show the total number of pages.
+pub fn build_pdf_two_pass<F: 'static>(
+    name: &str,
+    page_size: (f64, f64),
+    build_fonts: impl Fn(&PdfDocumentReference) -> Result<F>,
+    build_element: impl for<'a> BuildElement<'a, F> + Clone,
+) -> Result<Pdf> {
+    build_pdf_sections_two_pass(name, build_fonts, |sections, fonts| {
+        sections.add(&Section {
+            page_size,
+            orientation: Orientation::Portrait,
+            restart_page_numbers: None,
+            element: &build_element.clone().call(fonts),
+        });
+    })
+}
 
-    let layer = pdf.document.get_page(page).get_layer(layer);
+/// Builds a document out of sections that can have different page sizes. The sections get added
+/// in the callback and each one starts on a new page.
+pub fn build_pdf_sections<F: 'static>(
+    name: &str,
+    build_fonts: impl FnOnce(&PdfDocumentReference) -> Result<F>,
+    build_sections: impl FnOnce(&mut Sections, &F),
+) -> Result<Pdf> {
+    build_pdf_pass(name, build_fonts, build_sections, None)
+}
 
-    let fonts = build_fonts(&pdf.document);
+/// Like [build_pdf_sections], but laid out twice like [build_pdf_two_pass].
+pub fn build_pdf_sections_two_pass<F: 'static>(
+    name: &str,
+    build_fonts: impl Fn(&PdfDocumentReference) -> Result<F>,
+    build_sections: impl Fn(&mut Sections, &F),
+) -> Result<Pdf> {
+    let first_pass = build_pdf_pass(name, &build_fonts, &build_sections, None)?;
 
-    let element = build_element.call(&fonts);
+    build_pdf_pass(name, build_fonts, build_sections, Some(first_pass))
+}
 
-    let ctx = DrawCtx {
-        pdf: &mut pdf,
-        width: WidthConstraint {
-            max: page_size.0,
-            expand: true,
-        },
-        location: Location {
-            layer,
-            pos: (0., page_size.1),
-            scale_factor: 1.,
-        },
+fn build_pdf_pass<F: 'static>(
+    name: &str,
+    build_fonts: impl FnOnce(&PdfDocumentReference) -> Result<F>,
+    build_sections: impl FnOnce(&mut Sections, &F),
+    previous_pass: Option<Pdf>,
+) -> Result<Pdf> {
+    let mut pdf = Pdf::new(printpdf::PdfDocument::empty(name), (0., 0.));
+
+    if let Some(previous_pass) = previous_pass {
+        pdf.page_numbers =
+            PageNumbers::with_previous_page_count(previous_pass.page_numbers.page_count());
+        pdf.links = Links::with_previous_anchors(previous_pass.links.into_anchors());
+    }
 
-        first_height: page_size.1,
-        preferred_height: None,
+    let fonts = build_fonts(&pdf.document)?;
 
-        breakable: Some(BreakableDraw {
-            full_height: page_size.1,
-            preferred_height_break_count: 0,
-            do_break,
-        }),
-    };
+    let mut sections = Sections::new(&mut pdf);
+    build_sections(&mut sections, &fonts);
+    let page_count = sections.page_count();
 
-    element.draw(ctx);
+    pdf.page_numbers.set_page_count(page_count);
 
-    pdf.document
+    Ok(pdf)
 }
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::{output::page_id, utils::mm_to_pt, Location, Result};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LinkTarget {
+    Uri(String),
+
+    /// The name of an [crate::elements::link::Anchor] somewhere in the document.
+    Anchor(String),
+}
+
+/// A position in the document. The y coordinate is in mm from the bottom of the page.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Destination {
+    pub page: usize,
+    pub y: f64,
+}
+
+impl Destination {
+    pub fn from_location(location: &Location) -> Self {
+        Destination {
+            page: location.layer.page.0,
+            y: location.pos.1 * location.scale_factor,
+        }
+    }
+}
+
+struct LinkAnnotation {
+    page: usize,
+
+    /// Left, bottom, right and top in mm.
+    rect: [f64; 4],
+
+    target: LinkTarget,
+}
+
+/// The clickable areas and named anchors recorded while drawing. They get written as link
+/// annotations when the document is saved, which is also when the anchors of internal links are
+/// resolved, so a link can point to an anchor that is drawn after it.
+#[derive(Default)]
+pub struct Links {
+    links: Vec<LinkAnnotation>,
+    anchors: HashMap<String, Destination>,
+
+    /// The anchors of a previous layout pass of the same document.
+    previous_anchors: HashMap<String, Destination>,
+}
+
+impl Links {
+    pub fn with_previous_anchors(previous_anchors: HashMap<String, Destination>) -> Self {
+        Links {
+            previous_anchors,
+            ..Default::default()
+        }
+    }
+
+    pub fn into_anchors(self) -> HashMap<String, Destination> {
+        self.anchors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Adds a link for a rectangle with the size in mm, starting at the location and going down.
+    pub fn add(&mut self, location: &Location, size: (f64, f64), target: LinkTarget) {
+        let scale = location.scale_factor;
+        let (x, y) = (location.pos.0 * scale, location.pos.1 * scale);
+
+        self.links.push(LinkAnnotation {
+            page: location.layer.page.0,
+            rect: [x, y - size.1 * scale, x + size.0 * scale, y],
+            target,
+        });
+    }
+
+    /// Records a named position for internal links. If the same name is drawn more than once the
+    /// first position is kept.
+    pub fn add_anchor(&mut self, name: &str, destination: Destination) {
+        self.anchors.entry(name.to_string()).or_insert(destination);
+    }
+
+    pub fn anchor(&self, name: &str) -> Option<Destination> {
+        self.anchors.get(name).copied()
+    }
+
+    /// Looks the anchor up in the anchors drawn so far and then in the previous layout pass, so
+    /// that anchors that come later in the document can be found during the second pass.
+    pub fn resolve_anchor(&self, name: &str) -> Option<Destination> {
+        self.anchor(name)
+            .or_else(|| self.previous_anchors.get(name).copied())
+    }
+
+    /// Adds the link annotations to the pages of a saved document. Links to anchors that were
+    /// never drawn are left out.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+        for link in &self.links {
+            let page = page_id(&pages, link.page)?;
+
+            let mut annotation = Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"Annot".to_vec())),
+                ("Subtype", Object::Name(b"Link".to_vec())),
+                (
+                    "Rect",
+                    Object::Array(
+                        link.rect
+                            .iter()
+                            .map(|&c| Object::Real(mm_to_pt(c)))
+                            .collect(),
+                    ),
+                ),
+                ("Border", Object::Array(vec![Object::Integer(0); 3])),
+            ]);
+
+            match link.target {
+                LinkTarget::Uri(ref uri) => {
+                    annotation.set(
+                        "A",
+                        Dictionary::from_iter(vec![
+                            ("S", Object::Name(b"URI".to_vec())),
+                            ("URI", Object::string_literal(uri.as_str())),
+                        ]),
+                    );
+                }
+                LinkTarget::Anchor(ref name) => {
+                    let destination = match self.anchor(name) {
+                        Some(destination) => destination,
+                        None => continue,
+                    };
+
+                    annotation.set("Dest", destination_array(&pages, destination)?);
+                }
+            }
+
+            let annotation = doc.add_object(annotation);
+            add_annotation(doc, page, annotation)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An explicit destination that scrolls to the y position and keeps the zoom.
+pub fn destination_array(pages: &[ObjectId], destination: Destination) -> Result<Object> {
+    Ok(Object::Array(vec![
+        Object::Reference(page_id(pages, destination.page)?),
+        Object::Name(b"XYZ".to_vec()),
+        Object::Null,
+        Object::Real(mm_to_pt(destination.y)),
+        Object::Null,
+    ]))
+}
+
+pub fn add_annotation(doc: &mut Document, page: ObjectId, annotation: ObjectId) -> Result<()> {
+    let annots = doc.get_dictionary(page)?.get(b"Annots").ok().cloned();
+
+    // The array can also be an indirect object, in which case that object gets extended.
+    if let Some(Object::Reference(id)) = annots {
+        doc.get_object_mut(id)
+            .and_then(Object::as_array_mut)?
+            .push(Object::Reference(annotation));
+
+        return Ok(());
+    }
+
+    let page = doc.get_object_mut(page).and_then(Object::as_dict_mut)?;
+
+    match page.get_mut(b"Annots") {
+        Ok(Object::Array(annots)) => annots.push(Object::Reference(annotation)),
+        _ => page.set("Annots", vec![Object::Reference(annotation)]),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+
+use crate::links::Destination;
+
+struct MarkEntry {
+    name: String,
+    value: String,
+    destination: Destination,
+}
+
+/// The values set by [crate::elements::mark::Mark] while drawing, for running headers like the
+/// current chapter or the first and last word on a dictionary page.
+#[derive(Default)]
+pub struct Marks {
+    entries: Vec<MarkEntry>,
+}
+
+impl Marks {
+    pub fn add(&mut self, name: &str, value: &str, destination: Destination) {
+        self.entries.push(MarkEntry {
+            name: name.to_string(),
+            value: value.to_string(),
+            destination,
+        });
+    }
+
+    /// The topmost value of the mark on the page. If the mark isn't set on the page, this is the
+    /// last value set on a page before it.
+    pub fn first(&self, name: &str, page: usize) -> Option<&str> {
+        let entries = self.sorted(name);
+
+        entries
+            .iter()
+            .find(|entry| entry.destination.page == page)
+            .copied()
+            .or_else(|| Self::last_before(&entries, page))
+            .map(|entry| &entry.value[..])
+    }
+
+    /// The bottommost value of the mark on the page. If the mark isn't set on the page, this is
+    /// the last value set on a page before it.
+    pub fn last(&self, name: &str, page: usize) -> Option<&str> {
+        let entries = self.sorted(name);
+
+        entries
+            .iter()
+            .rev()
+            .find(|entry| entry.destination.page == page)
+            .copied()
+            .or_else(|| Self::last_before(&entries, page))
+            .map(|entry| &entry.value[..])
+    }
+
+    /// The entries with the name in the order they appear in the document.
+    fn sorted(&self, name: &str) -> Vec<&MarkEntry> {
+        let mut entries: Vec<&MarkEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .collect();
+
+        // The sort is stable, so marks at the same position stay in the order they were drawn.
+        entries.sort_by(|a, b| {
+            a.destination.page.cmp(&b.destination.page).then(
+                b.destination
+                    .y
+                    .partial_cmp(&a.destination.y)
+                    .unwrap_or(Ordering::Equal),
+            )
+        });
+
+        entries
+    }
+
+    fn last_before<'a>(entries: &[&'a MarkEntry], page: usize) -> Option<&'a MarkEntry> {
+        entries
+            .iter()
+            .rev()
+            .find(|entry| entry.destination.page < page)
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks() {
+        let mut marks = Marks::default();
+
+        let destination = |page, y| Destination { page, y };
+
+        marks.add("chapter", "2", destination(1, 50.));
+        marks.add("chapter", "1", destination(1, 100.));
+        marks.add("word", "apple", destination(1, 80.));
+        marks.add("chapter", "3", destination(3, 20.));
+
+        assert_eq!(marks.first("chapter", 0), None);
+        assert_eq!(marks.first("chapter", 1), Some("1"));
+        assert_eq!(marks.last("chapter", 1), Some("2"));
+        assert_eq!(marks.first("chapter", 2), Some("2"));
+        assert_eq!(marks.last("chapter", 2), Some("2"));
+        assert_eq!(marks.first("chapter", 3), Some("3"));
+        assert_eq!(marks.last("word", 4), Some("apple"));
+        assert_eq!(marks.first("section", 1), None);
+    }
+}
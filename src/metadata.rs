@@ -0,0 +1,326 @@
+use lopdf::{Dictionary, Document, Object, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    outline::text_string,
+    output::{catalog_mut, info_mut, plain_content},
+    Error, Result,
+};
+
+/// The document information that viewers show in the document properties and that archives
+/// index. It is written to the info dictionary and to the XMP metadata when the document is saved.
+///
+/// Set it with [crate::sections::Sections::metadata] while building the document, or on
+/// [crate::Pdf::metadata] before saving it. It can be deserialized on its own, for example from the
+/// same configuration that the elements of a document are read from.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    /// Replaces the name passed to [crate::build_pdf].
+    pub title: Option<String>,
+
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+
+    /// The natural language of the text in the document as a language tag, like `de-CH`.
+    pub language: Option<String>,
+
+    /// The application that created the original content of the document.
+    pub creator: Option<String>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Metadata::default()
+    }
+
+    /// Adds the metadata to the info dictionary, the catalog and the XMP metadata of a saved
+    /// document.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let info = info_mut(doc)?;
+
+        if let Some(ref title) = self.title {
+            info.set("Title", text_string(title));
+        }
+
+        if let Some(ref author) = self.author {
+            info.set("Author", text_string(author));
+        }
+
+        if let Some(ref subject) = self.subject {
+            info.set("Subject", text_string(subject));
+        }
+
+        if !self.keywords.is_empty() {
+            info.set("Keywords", text_string(&self.keywords.join(", ")));
+        }
+
+        if let Some(ref creator) = self.creator {
+            info.set("Creator", text_string(creator));
+        }
+
+        if !update_xmp(doc, |xmp| self.xmp(Some(xmp)))? {
+            let mut stream = Stream::new(
+                Dictionary::from_iter(vec![
+                    ("Type", Object::Name(b"Metadata".to_vec())),
+                    ("Subtype", Object::Name(b"XML".to_vec())),
+                ]),
+                self.xmp(None).into_bytes(),
+            );
+            stream.allows_compression = false;
+
+            let id = doc.add_object(stream);
+            catalog_mut(doc)?.set("Metadata", Object::Reference(id));
+        }
+
+        if let Some(ref language) = self.language {
+            catalog_mut(doc)?.set("Lang", text_string(language));
+        }
+
+        Ok(())
+    }
+
+    /// The XMP properties of the metadata, added to an existing packet or to a new one.
+    fn xmp(&self, existing: Option<&str>) -> String {
+        let mut properties = String::new();
+
+        if let Some(ref author) = self.author {
+            properties += &format!(
+                "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+                escape_xml(author)
+            );
+        }
+
+        if let Some(ref subject) = self.subject {
+            properties += &format!(
+                "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>",
+                escape_xml(subject)
+            );
+        }
+
+        if !self.keywords.is_empty() {
+            properties += "<dc:subject><rdf:Bag>";
+
+            for keyword in &self.keywords {
+                properties += &format!("<rdf:li>{}</rdf:li>", escape_xml(keyword));
+            }
+
+            properties += "</rdf:Bag></dc:subject>";
+            properties += &format!(
+                "<pdf:Keywords>{}</pdf:Keywords>",
+                escape_xml(&self.keywords.join(", "))
+            );
+        }
+
+        if let Some(ref language) = self.language {
+            properties += &format!(
+                "<dc:language><rdf:Bag><rdf:li>{}</rdf:li></rdf:Bag></dc:language>",
+                escape_xml(language)
+            );
+        }
+
+        if let Some(ref creator) = self.creator {
+            properties += &format!("<xmp:CreatorTool>{}</xmp:CreatorTool>", escape_xml(creator));
+        }
+
+        let title = self.title.as_ref().map(|title| {
+            format!(
+                "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>",
+                escape_xml(title)
+            )
+        });
+
+        match existing {
+            // printpdf always writes a title, so it is replaced instead of added.
+            Some(existing) => {
+                let xmp = match title {
+                    Some(title) => replace_element(existing, "dc:title", &title),
+                    None => existing.to_string(),
+                };
+
+                add_properties(&xmp, &properties)
+            }
+            None => format!(
+                concat!(
+                    "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+                    "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">",
+                    "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+                    "<rdf:Description rdf:about=\"\"",
+                    " xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"",
+                    " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+                    " xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\">",
+                    "{}{}",
+                    "</rdf:Description>",
+                    "</rdf:RDF>",
+                    "</x:xmpmeta>",
+                    "<?xpacket end=\"w\"?>",
+                ),
+                title.unwrap_or_default(),
+                properties,
+            ),
+        }
+    }
+}
+
+/// Replaces the XMP packet of a saved document by the result of `update`. Returns false if the
+/// document has no XMP metadata.
+pub fn update_xmp(doc: &mut Document, update: impl FnOnce(&str) -> String) -> Result<bool> {
+    let metadata_id = doc
+        .catalog()
+        .and_then(|catalog| catalog.get(b"Metadata"))
+        .and_then(Object::as_reference)
+        .ok();
+
+    let stream = match metadata_id {
+        Some(id) => doc.get_object_mut(id).and_then(Object::as_stream_mut)?,
+        None => return Ok(false),
+    };
+
+    let xmp = String::from_utf8(plain_content(stream)?)
+        .map_err(|_| Error::Pdf("the XMP metadata isn't UTF-8".to_string()))?;
+    stream.set_plain_content(update(&xmp).into_bytes());
+
+    Ok(true)
+}
+
+/// Inserts properties at the end of the first description of an XMP packet.
+pub fn add_properties(xmp: &str, properties: &str) -> String {
+    match xmp.find("</rdf:Description>") {
+        Some(end) => format!("{}{}{}", &xmp[..end], properties, &xmp[end..]),
+        None => xmp.to_string(),
+    }
+}
+
+/// Adds a description, for properties in another namespace, at the end of an XMP packet.
+pub fn add_description(xmp: &str, description: &str) -> String {
+    match xmp.find("</rdf:RDF>") {
+        Some(end) => format!("{}{}{}", &xmp[..end], description, &xmp[end..]),
+        None => xmp.to_string(),
+    }
+}
+
+/// The content of the first element with the tag.
+pub fn element_content<'a>(xmp: &'a str, tag: &str) -> Option<&'a str> {
+    let start_tag = format!("<{}>", tag);
+    let start = xmp.find(&start_tag)? + start_tag.len();
+    let end = start + xmp[start..].find(&format!("</{}>", tag))?;
+
+    Some(&xmp[start..end])
+}
+
+/// Replaces the first element with the tag, including its content, by `replacement`. The
+/// replacement is added as a new property if there is no such element.
+pub fn replace_element(xmp: &str, tag: &str, replacement: &str) -> String {
+    let start = xmp.find(&format!("<{}>", tag));
+    let end_tag = format!("</{}>", tag);
+
+    match start.and_then(|start| Some((start, start + xmp[start..].find(&end_tag)?))) {
+        Some((start, end)) => format!(
+            "{}{}{}",
+            &xmp[..start],
+            replacement,
+            &xmp[end + end_tag.len()..]
+        ),
+        None => add_properties(xmp, replacement),
+    }
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_pdf, test_utils::FakeText};
+
+    #[test]
+    fn test_replace_element() {
+        let xmp = "<a><dc:title>old</dc:title><b/></rdf:Description>";
+
+        assert_eq!(
+            replace_element(xmp, "dc:title", "<dc:title>new</dc:title>"),
+            "<a><dc:title>new</dc:title><b/></rdf:Description>"
+        );
+        assert_eq!(
+            replace_element(xmp, "dc:creator", "<x/>"),
+            "<a><dc:title>old</dc:title><b/><x/></rdf:Description>"
+        );
+        assert_eq!(escape_xml("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+    }
+
+    #[test]
+    fn test_metadata() {
+        let metadata: Metadata = serde_json::from_str(
+            r#"{ "title": "Rechnung", "author": "Müller & Co", "keywords": ["a", "b"], "language": "de-CH" }"#,
+        )
+        .unwrap();
+
+        assert_eq!(metadata.subject, None);
+
+        let mut pdf = build_pdf(
+            "test",
+            (10., 10.),
+            |_| Ok(()),
+            |_: &()| FakeText {
+                lines: 1,
+                line_height: 5.,
+                width: 3.,
+            },
+        )
+        .unwrap();
+
+        pdf.metadata = metadata;
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let info = document
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .unwrap();
+
+        assert!(matches!(info.get(b"Title"), Ok(Object::String(title, _)) if title == b"Rechnung"));
+        assert!(
+            matches!(info.get(b"Keywords"), Ok(Object::String(keywords, _)) if keywords == b"a, b")
+        );
+        assert!(
+            matches!(info.get(b"Author"), Ok(Object::String(author, _)) if author[..2] == [0xfe, 0xff])
+        );
+
+        let catalog = document.catalog().unwrap();
+        assert!(matches!(catalog.get(b"Lang"), Ok(Object::String(lang, _)) if lang == b"de-CH"));
+
+        let xmp = catalog
+            .get(b"Metadata")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let xmp = String::from_utf8(xmp.content.clone()).unwrap();
+
+        assert_eq!(xmp.matches("<dc:title>").count(), 1);
+        assert!(xmp.contains("<rdf:li xml:lang=\"x-default\">Rechnung</rdf:li>"));
+        assert!(xmp.contains("<rdf:li>Müller &amp; Co</rdf:li>"));
+        assert!(xmp.contains("<pdf:Keywords>a, b</pdf:Keywords>"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::cmp::Ordering;
+
+use lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+
+use crate::{
+    links::{destination_array, Destination},
+    output::catalog_mut,
+    Result,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutlineEntry {
+    pub title: String,
+
+    /// Zero for the top level. An entry becomes a child of the closest entry before it with a
+    /// lower level.
+    pub level: u8,
+
+    pub destination: Destination,
+}
+
+/// The bookmarks recorded while drawing. They get written as the document outline when the
+/// document is saved.
+#[derive(Default)]
+pub struct Outline {
+    entries: Vec<OutlineEntry>,
+}
+
+impl Outline {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn add(&mut self, title: &str, level: u8, destination: Destination) {
+        self.entries.push(OutlineEntry {
+            title: title.to_string(),
+            level,
+            destination,
+        });
+    }
+
+    /// The entries in the order they appear in the document, which isn't necessarily the order
+    /// they were drawn in, for example in rows.
+    pub fn entries(&self) -> Vec<&OutlineEntry> {
+        let mut entries: Vec<&OutlineEntry> = self.entries.iter().collect();
+
+        entries.sort_by(|a, b| {
+            a.destination.page.cmp(&b.destination.page).then(
+                b.destination
+                    .y
+                    .partial_cmp(&a.destination.y)
+                    .unwrap_or(Ordering::Equal),
+            )
+        });
+
+        entries
+    }
+
+    /// Adds the outline to the catalog of a saved document. All entries start out expanded.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        let entries = self.entries();
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+        let parents = parents(&entries);
+
+        let root_id = doc.new_object_id();
+        let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+
+        let children = |parent: Option<usize>| -> Vec<usize> {
+            (0..entries.len())
+                .filter(|&i| parents[i] == parent)
+                .collect()
+        };
+
+        let descendant_count = |i: usize| -> i64 {
+            (i + 1..entries.len())
+                .take_while(|&j| is_descendant(&parents, j, i))
+                .count() as i64
+        };
+
+        for (i, entry) in entries.iter().enumerate() {
+            let siblings = children(parents[i]);
+            let position = siblings.iter().position(|&j| j == i).unwrap();
+
+            let mut item = Dictionary::from_iter(vec![
+                ("Title", text_string(&entry.title)),
+                (
+                    "Parent",
+                    Object::Reference(parents[i].map_or(root_id, |parent| ids[parent])),
+                ),
+            ]);
+
+            if position > 0 {
+                item.set("Prev", Object::Reference(ids[siblings[position - 1]]));
+            }
+
+            if let Some(&next) = siblings.get(position + 1) {
+                item.set("Next", Object::Reference(ids[next]));
+            }
+
+            let own_children = children(Some(i));
+
+            if let (Some(&first), Some(&last)) = (own_children.first(), own_children.last()) {
+                item.set("First", Object::Reference(ids[first]));
+                item.set("Last", Object::Reference(ids[last]));
+                item.set("Count", descendant_count(i));
+            }
+
+            item.set("Dest", destination_array(&pages, entry.destination)?);
+
+            doc.objects.insert(ids[i], Object::Dictionary(item));
+        }
+
+        let top_level = children(None);
+
+        doc.objects.insert(
+            root_id,
+            Object::Dictionary(Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"Outlines".to_vec())),
+                ("First", Object::Reference(ids[top_level[0]])),
+                ("Last", Object::Reference(ids[*top_level.last().unwrap()])),
+                ("Count", (entries.len() as i64).into()),
+            ])),
+        );
+
+        let catalog = catalog_mut(doc)?;
+        catalog.set("Outlines", Object::Reference(root_id));
+        catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+
+        Ok(())
+    }
+}
+
+/// The index of the parent of each entry.
+fn parents(entries: &[&OutlineEntry]) -> Vec<Option<usize>> {
+    let mut stack: Vec<usize> = Vec::new();
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            while let Some(&last) = stack.last() {
+                if entries[last].level < entry.level {
+                    break;
+                }
+
+                stack.pop();
+            }
+
+            let parent = stack.last().copied();
+            stack.push(i);
+            parent
+        })
+        .collect()
+}
+
+fn is_descendant(parents: &[Option<usize>], entry: usize, ancestor: usize) -> bool {
+    let mut current = parents[entry];
+
+    while let Some(parent) = current {
+        if parent == ancestor {
+            return true;
+        }
+
+        current = parents[parent];
+    }
+
+    false
+}
+
+/// A PDF text string. Text that isn't ASCII is encoded as UTF-16 with a byte order mark.
+pub fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        Object::string_literal(text)
+    } else {
+        let bytes = [0xfe, 0xff]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+
+        Object::String(bytes, StringFormat::Hexadecimal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parents() {
+        let mut outline = Outline::default();
+
+        let destination = |page, y| Destination { page, y };
+
+        outline.add("b", 1, destination(0, 50.));
+        outline.add("a", 0, destination(0, 100.));
+        outline.add("c", 2, destination(1, 100.));
+        outline.add("d", 1, destination(1, 20.));
+        outline.add("e", 0, destination(2, 100.));
+        outline.add("f", 2, destination(2, 50.));
+
+        let entries = outline.entries();
+
+        assert_eq!(
+            entries.iter().map(|e| &e.title[..]).collect::<Vec<_>>(),
+            ["a", "b", "c", "d", "e", "f"]
+        );
+
+        let parents = parents(&entries);
+        assert_eq!(parents, [None, Some(0), Some(1), Some(0), None, Some(4)]);
+
+        assert!(is_descendant(&parents, 2, 0));
+        assert!(!is_descendant(&parents, 3, 1));
+    }
+
+    #[test]
+    fn test_text_string() {
+        assert!(matches!(
+            text_string("Ab"),
+            Object::String(bytes, StringFormat::Literal) if bytes == b"Ab"
+        ));
+        assert!(matches!(
+            text_string("Ä"),
+            Object::String(bytes, StringFormat::Hexadecimal) if bytes == [0xfe, 0xff, 0x00, 0xc4]
+        ));
+    }
+}
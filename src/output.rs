@@ -0,0 +1,290 @@
+use std::time::SystemTime;
+
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use printpdf::{OffsetDateTime, PdfDocumentReference};
+
+use crate::{
+    encryption::Encryption,
+    metadata::{escape_xml, replace_element, update_xmp},
+    outline::text_string,
+    signature::Signature,
+    Error, Result,
+};
+
+/// How [crate::Pdf::save_with_options] writes the document.
+#[derive(Clone, Debug, Default)]
+pub struct SaveOptions {
+    /// Compresses all streams that aren't compressed yet with Flate, except for the XMP metadata.
+    pub compress: bool,
+
+    /// Used as the creation, modification and metadata date instead of the current time.
+    pub date: Option<SystemTime>,
+
+    /// Used for the document and instance IDs in the trailer and the XMP metadata instead of
+    /// random ones.
+    pub document_id: Option<String>,
+
+    pub producer: Option<String>,
+
+    /// Encrypts the document after everything else was written.
+    pub encryption: Option<Encryption>,
+
+    /// Signs the document. The signature is dated with [SaveOptions::date] if it's set.
+    pub signature: Option<Signature>,
+}
+
+impl SaveOptions {
+    /// Options that make the output only depend on the input, so that the same document is
+    /// always saved to the same bytes.
+    pub fn reproducible(date: SystemTime, document_id: &str) -> Self {
+        SaveOptions {
+            compress: true,
+            date: Some(date),
+            document_id: Some(document_id.to_string()),
+            producer: None,
+            encryption: None,
+            signature: None,
+        }
+    }
+
+    /// Whether the document needs to be changed after printpdf wrote it.
+    pub(crate) fn modifies_saved_document(&self) -> bool {
+        self.compress
+            || self.producer.is_some()
+            || self.encryption.is_some()
+            || self.signature.is_some()
+    }
+
+    /// Sets the dates and IDs printpdf writes.
+    pub(crate) fn apply_to_document(
+        &self,
+        mut document: PdfDocumentReference,
+    ) -> PdfDocumentReference {
+        if let Some(date) = self.date {
+            let date = OffsetDateTime::from(date);
+
+            document = document
+                .with_creation_date(date)
+                .with_mod_date(date)
+                .with_metadata_date(date);
+        }
+
+        if let Some(ref id) = self.document_id {
+            document = document
+                .with_document_id(id.clone())
+                .with_instance_id(id.clone())
+                .with_xmp_document_id(id.clone())
+                .with_xmp_instance_id(id.clone());
+        }
+
+        document
+    }
+
+    pub(crate) fn apply(&self, doc: &mut Document) -> Result<()> {
+        if let Some(ref producer) = self.producer {
+            info_mut(doc)?.set("Producer", text_string(producer));
+
+            // Without XMP metadata there is no producer to replace there.
+            update_xmp(doc, |xmp| {
+                let producer = format!("<pdf:Producer>{}</pdf:Producer>", escape_xml(producer));
+                replace_element(xmp, "pdf:Producer", &producer)
+            })?;
+        }
+
+        if let Some(ref signature) = self.signature {
+            signature.write(doc, self.date.unwrap_or_else(SystemTime::now))?;
+        }
+
+        if self.compress {
+            let metadata = doc
+                .catalog()
+                .and_then(|catalog| catalog.get(b"Metadata"))
+                .and_then(Object::as_reference)
+                .ok();
+
+            // Keeping the XMP packet readable lets tools that don't parse PDF find it.
+            if let Some(Ok(Object::Stream(stream))) = metadata.map(|id| doc.get_object_mut(id)) {
+                stream.allows_compression = false;
+            }
+
+            doc.compress();
+        }
+
+        if let Some(ref encryption) = self.encryption {
+            encryption.write(doc)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The catalog of a saved document, for the parts that are added to it when saving.
+pub(crate) fn catalog_mut(doc: &mut Document) -> Result<&mut Dictionary> {
+    let id = doc.trailer.get(b"Root").and_then(Object::as_reference)?;
+
+    Ok(doc.get_object_mut(id).and_then(Object::as_dict_mut)?)
+}
+
+/// The info dictionary of a saved document, which is added if it's missing.
+pub(crate) fn info_mut(doc: &mut Document) -> Result<&mut Dictionary> {
+    let id = match doc.trailer.get(b"Info").and_then(Object::as_reference) {
+        Ok(id) => id,
+        Err(_) => {
+            let id = doc.add_object(Dictionary::new());
+            doc.trailer.set("Info", Object::Reference(id));
+            id
+        }
+    };
+
+    Ok(doc.get_object_mut(id).and_then(Object::as_dict_mut)?)
+}
+
+/// The decoded content of a stream of a saved document.
+pub(crate) fn plain_content(stream: &Stream) -> Result<Vec<u8>> {
+    if stream.dict.has(b"Filter") {
+        Ok(stream.decompressed_content()?)
+    } else {
+        Ok(stream.content.clone())
+    }
+}
+
+/// The id of the page with the index it was drawn on in a saved document.
+pub(crate) fn page_id(pages: &[ObjectId], index: usize) -> Result<ObjectId> {
+    pages
+        .get(index)
+        .copied()
+        .ok_or_else(|| Error::Pdf(format!("the document has no page {}", index + 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::{build_pdf, test_utils::FakeText};
+
+    #[test]
+    fn test_reproducible() {
+        let save = || {
+            let text = FakeText {
+                lines: 3,
+                line_height: 5.,
+                width: 3.,
+            };
+
+            build_pdf("test", (10., 10.), |_| Ok(()), |_: &()| text)
+                .unwrap()
+                .to_bytes_with_options(&SaveOptions {
+                    producer: Some("test".to_string()),
+                    ..SaveOptions::reproducible(UNIX_EPOCH, "0123456789abcdef")
+                })
+                .unwrap()
+        };
+
+        assert_eq!(save(), save());
+    }
+
+    fn save(options: &SaveOptions) -> Document {
+        let text = FakeText {
+            lines: 3,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let bytes = build_pdf("test", (10., 10.), |_| Ok(()), |_: &()| text)
+            .unwrap()
+            .to_bytes_with_options(options)
+            .unwrap();
+
+        Document::load_mem(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_date_id_and_producer() {
+        let document = save(&SaveOptions {
+            producer: Some("test producer".to_string()),
+            ..SaveOptions::reproducible(UNIX_EPOCH, "0123456789abcdef")
+        });
+
+        let info = document
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .unwrap();
+
+        assert!(matches!(
+            info.get(b"CreationDate"),
+            Ok(Object::String(date, _)) if date.starts_with(b"D:19700101000000")
+        ));
+        assert!(matches!(
+            info.get(b"ModDate"),
+            Ok(Object::String(date, _)) if date.starts_with(b"D:19700101000000")
+        ));
+        assert!(matches!(
+            info.get(b"Producer"),
+            Ok(Object::String(producer, _)) if producer == b"test producer"
+        ));
+
+        let ids = document
+            .trailer
+            .get(b"ID")
+            .and_then(Object::as_array)
+            .unwrap();
+        assert!(matches!(&ids[0], Object::String(id, _) if id == b"0123456789abcdef"));
+        assert!(matches!(&ids[1], Object::String(id, _) if id == b"0123456789abcdef"));
+
+        let xmp = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"Metadata"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_object(id))
+            .and_then(Object::as_stream)
+            .unwrap();
+        let xmp = String::from_utf8(xmp.content.clone()).unwrap();
+
+        assert!(xmp.contains("<pdf:Producer>test producer</pdf:Producer>"));
+        assert!(xmp.contains("0123456789abcdef"));
+        assert!(xmp.contains("1970-01-01T00:00:00"));
+    }
+
+    #[test]
+    fn test_compress() {
+        let is_compressed = |document: &Document, id| {
+            let stream = document.get_object(id).and_then(Object::as_stream).unwrap();
+            let filter = stream.dict.get(b"Filter").and_then(Object::as_name);
+            matches!(filter, Ok(filter) if filter == b"FlateDecode")
+        };
+
+        let xmp_id = |document: &Document| {
+            document
+                .catalog()
+                .and_then(|catalog| catalog.get(b"Metadata"))
+                .and_then(Object::as_reference)
+                .unwrap()
+        };
+
+        let compressed = save(&SaveOptions {
+            compress: true,
+            ..SaveOptions::default()
+        });
+
+        let pages = compressed.get_pages();
+        assert_eq!(pages.len(), 1);
+
+        for &page in pages.values() {
+            let contents = compressed.get_page_contents(page);
+            assert!(!contents.is_empty());
+            assert!(contents.iter().all(|&id| is_compressed(&compressed, id)));
+        }
+
+        assert!(!is_compressed(&compressed, xmp_id(&compressed)));
+
+        let uncompressed = save(&SaveOptions::default());
+
+        for &page in uncompressed.get_pages().values() {
+            let contents = uncompressed.get_page_contents(page);
+            assert!(contents.iter().all(|&id| !is_compressed(&uncompressed, id)));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use lopdf::{Dictionary, Document, Object};
+
+use crate::{output::catalog_mut, Result};
+
+/// The page numbers shown in the document. They start at one and can be restarted by sections,
+/// in which case they are also written as page labels, so that viewers show the same numbers.
+#[derive(Default)]
+pub struct PageNumbers {
+    /// The index of the first page and its number for each restart, in page order.
+    restarts: Vec<(usize, usize)>,
+
+    page_count: Option<usize>,
+
+    /// The page count of a previous layout pass of the same document.
+    previous_page_count: Option<usize>,
+}
+
+impl PageNumbers {
+    pub fn with_previous_page_count(previous_page_count: Option<usize>) -> Self {
+        PageNumbers {
+            previous_page_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn has_restarts(&self) -> bool {
+        !self.restarts.is_empty()
+    }
+
+    /// Numbers the pages from the index on starting at `start`.
+    pub fn restart(&mut self, page: usize, start: usize) {
+        self.restarts.retain(|&(restart, _)| restart < page);
+        self.restarts.push((page, start));
+    }
+
+    /// The number of the page with the index.
+    pub fn number(&self, page: usize) -> usize {
+        match self
+            .restarts
+            .iter()
+            .rev()
+            .find(|&&(first, _)| first <= page)
+        {
+            Some(&(first, start)) => start + page - first,
+            None => page + 1,
+        }
+    }
+
+    /// The number of pages, which is only known once the document is drawn.
+    pub fn page_count(&self) -> Option<usize> {
+        self.page_count
+    }
+
+    pub fn set_page_count(&mut self, page_count: usize) {
+        self.page_count = Some(page_count);
+    }
+
+    /// The page count of the document, if it is already known, or otherwise the one of the
+    /// previous layout pass. Decorations are drawn before the document is complete, so the total
+    /// needs [crate::build_pdf_two_pass].
+    pub fn resolve_page_count(&self) -> Option<usize> {
+        self.page_count.or(self.previous_page_count)
+    }
+
+    /// Adds the page labels to the catalog of a saved document if the numbers were restarted.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        if self.restarts.is_empty() {
+            return Ok(());
+        }
+
+        let mut nums = Vec::new();
+
+        if self.restarts[0].0 > 0 {
+            nums.push(Object::Integer(0));
+            nums.push(Object::Dictionary(label(1)));
+        }
+
+        for &(page, start) in &self.restarts {
+            nums.push(Object::Integer(page as i64));
+            nums.push(Object::Dictionary(label(start)));
+        }
+
+        let page_labels =
+            doc.add_object(Dictionary::from_iter(vec![("Nums", Object::Array(nums))]));
+
+        catalog_mut(doc)?.set("PageLabels", Object::Reference(page_labels));
+
+        Ok(())
+    }
+}
+
+/// A label range with decimal numbers.
+fn label(start: usize) -> Dictionary {
+    Dictionary::from_iter(vec![
+        ("S", Object::Name(b"D".to_vec())),
+        ("St", Object::Integer(start as i64)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number() {
+        let mut page_numbers = PageNumbers::default();
+
+        assert_eq!(page_numbers.number(0), 1);
+        assert_eq!(page_numbers.number(4), 5);
+
+        page_numbers.restart(2, 1);
+        page_numbers.restart(5, 10);
+
+        assert_eq!(
+            (0..7)
+                .map(|page| page_numbers.number(page))
+                .collect::<Vec<_>>(),
+            [1, 2, 1, 2, 3, 10, 11]
+        );
+
+        // A restart on the same page replaces the previous one.
+        page_numbers.restart(5, 20);
+        assert_eq!(page_numbers.number(6), 21);
+    }
+}
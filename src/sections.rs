@@ -0,0 +1,246 @@
+use printpdf::{
+    indices::{PdfLayerIndex, PdfPageIndex},
+    Mm,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{metadata::Metadata, *};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    /// Swaps the sides of the page size if needed, so that the page is taller than wide for
+    /// portrait and wider than tall for landscape.
+    pub fn apply(self, page_size: (f64, f64)) -> (f64, f64) {
+        let (short, long) = if page_size.0 <= page_size.1 {
+            page_size
+        } else {
+            (page_size.1, page_size.0)
+        };
+
+        match self {
+            Orientation::Portrait => (short, long),
+            Orientation::Landscape => (long, short),
+        }
+    }
+}
+
+/// A part of the document that always starts on a new page. All of its pages have the same size.
+/// For margins and decorations the element can be a [elements::page::Page].
+pub struct Section<'a, E: Element> {
+    /// The width and height in mm.
+    pub page_size: (f64, f64),
+
+    pub orientation: Orientation,
+
+    /// The number of the first page of the section. Without it the numbers continue from the
+    /// previous section.
+    pub restart_page_numbers: Option<usize>,
+
+    pub element: &'a E,
+}
+
+/// The sections of a document. Each section is drawn when it's added.
+pub struct Sections<'a> {
+    pdf: &'a mut Pdf,
+    page_count: usize,
+}
+
+impl<'a> Sections<'a> {
+    pub(crate) fn new(pdf: &'a mut Pdf) -> Self {
+        Sections { pdf, page_count: 0 }
+    }
+
+    pub(crate) fn page_count(&self) -> usize {
+        self.page_count
+    }
+
+    /// Makes the document a tagged PDF, in which the elements that support it add their content
+    /// to the structure tree. This has to be called before the sections are added. The language of
+    /// the document should be set in the [Sections::metadata].
+    pub fn tagged(&mut self) -> &mut Self {
+        self.pdf.structure.enable();
+        self
+    }
+
+    /// Sets the metadata that is written when the document is saved, replacing
+    /// [crate::Pdf::metadata].
+    pub fn metadata(&mut self, metadata: Metadata) -> &mut Self {
+        self.pdf.metadata = metadata;
+        self
+    }
+
+    pub fn add<E: Element>(&mut self, section: &Section<E>) -> &mut Self {
+        let page_size = section.orientation.apply(section.page_size);
+        let first_page = self.page_count;
+
+        if let Some(start) = section.restart_page_numbers {
+            self.pdf.page_numbers.restart(first_page, start);
+        }
+
+        self.pdf.page_size = page_size;
+
+        let (page, layer) = self
+            .pdf
+            .document
+            .add_page(Mm(page_size.0), Mm(page_size.1), "Layer 0");
+        let layer = self.pdf.document.get_page(page).get_layer(layer);
+
+        let mut page_count = first_page + 1;
+
+        let do_break = &mut |pdf: &mut Pdf, location_idx: u32, _| {
+            let page = first_page + location_idx as usize + 1;
+
+            while page_count <= page {
+                pdf.document
+                    .add_page(Mm(page_size.0), Mm(page_size.1), "Layer 0");
+                page_count += 1;
+            }
+
+            let layer = pdf
+                .document
+                .get_page(PdfPageIndex(page))
+                .get_layer(PdfLayerIndex(0));
+
+            Location {
+                layer,
+                pos: (0., page_size.1),
+                scale_factor: 1.,
+            }
+        };
+
+        section.element.draw(DrawCtx {
+            pdf: self.pdf,
+            width: WidthConstraint {
+                max: page_size.0,
+                expand: true,
+            },
+            location: Location {
+                layer,
+                pos: (0., page_size.1),
+                scale_factor: 1.,
+            },
+
+            first_height: page_size.1,
+            preferred_height: None,
+
+            breakable: Some(BreakableDraw {
+                full_height: page_size.1,
+                preferred_height_break_count: 0,
+                do_break,
+            }),
+        });
+
+        self.page_count = page_count;
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Object;
+
+    use super::*;
+    use crate::test_utils::FakeText;
+
+    #[test]
+    fn test_sections() {
+        let body = FakeText {
+            lines: 6,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let annex = FakeText {
+            lines: 1,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let pdf = build_pdf_sections(
+            "test",
+            |_| Ok(()),
+            |sections, _| {
+                sections
+                    .metadata(Metadata {
+                        title: Some("Annual report".to_string()),
+                        ..Default::default()
+                    })
+                    .add(&Section {
+                        page_size: (10., 20.),
+                        orientation: Orientation::Portrait,
+                        restart_page_numbers: None,
+                        element: &body,
+                    })
+                    .add(&Section {
+                        page_size: (10., 20.),
+                        orientation: Orientation::Landscape,
+                        restart_page_numbers: Some(1),
+                        element: &annex,
+                    });
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pdf.page_size, (20., 10.));
+        assert_eq!(pdf.page_numbers.page_count(), Some(3));
+        assert_eq!(
+            (0..3)
+                .map(|page| pdf.page_numbers.number(page))
+                .collect::<Vec<_>>(),
+            [1, 2, 1]
+        );
+
+        let mut bytes = Vec::new();
+        pdf.save(&mut bytes).unwrap();
+
+        let document = lopdf::Document::load_mem(&bytes).unwrap();
+
+        let widths: Vec<f64> = document
+            .get_pages()
+            .into_values()
+            .map(|page| {
+                let media_box = document
+                    .get_dictionary(page)
+                    .and_then(|page| page.get(b"MediaBox"))
+                    .and_then(Object::as_array)
+                    .unwrap();
+
+                match media_box[2] {
+                    Object::Real(width) => width,
+                    Object::Integer(width) => width as f64,
+                    _ => panic!("unexpected media box"),
+                }
+            })
+            .collect();
+
+        assert_eq!(widths.len(), 3);
+        assert!(widths[0] < widths[2]);
+
+        let catalog = document.catalog().unwrap();
+        assert!(catalog.get(b"PageLabels").is_ok());
+
+        let info = document
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .unwrap();
+        assert!(
+            matches!(info.get(b"Title"), Ok(Object::String(title, _)) if title == b"Annual report")
+        );
+    }
+
+    #[test]
+    fn test_orientation() {
+        assert_eq!(Orientation::Portrait.apply((210., 297.)), (210., 297.));
+        assert_eq!(Orientation::Portrait.apply((297., 210.)), (210., 297.));
+        assert_eq!(Orientation::Landscape.apply((297., 420.)), (420., 297.));
+    }
+}
@@ -2,7 +2,10 @@ pub mod elements;
 
 use std::{ops::Index, rc::Rc};
 
-use crate::{fonts::truetype::TruetypeFont, CompositeElement, CompositeElementCallback};
+use crate::{
+    fonts::truetype::TruetypeFont, image::take_load_error, CompositeElement,
+    CompositeElementCallback,
+};
 use elements::*;
 
 pub type Font = Rc<TruetypeFont<Vec<u8>>>;
@@ -56,9 +59,13 @@ define_serde_element_value!(ElementValue {
     Debug<ElementValue>,
     Text,
     RichText,
+    TableOfContents,
     VGap,
     HAlign<ElementValue>,
     Padding<ElementValue>,
+    Link<ElementValue>,
+    Anchor<ElementValue>,
+    Bookmark<ElementValue>,
     StyledBox<ElementValue>,
     Line,
     Image,
@@ -83,3 +90,64 @@ define_serde_element_value!(ElementValue {
     ShrinkToFit<ElementValue>,
     Rotate<ElementValue>,
 });
+
+impl ElementValue {
+    /// Parses an element from JSON. Files referenced by the element, like images, are loaded while
+    /// parsing, so their errors are returned here too, with their own variant like
+    /// [crate::Error::Io]. Deserializing an element with serde directly only keeps their message.
+    pub fn from_json(json: &str) -> crate::Result<Self> {
+        take_load_error();
+
+        serde_json::from_str(json)
+            .map_err(|error| take_load_error().unwrap_or_else(|| error.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_empty_font_family() {
+        let rich_text = |fonts: &str| {
+            format!(
+                r#"{{ "RichText": {{
+                    "spans": [],
+                    "size": 12.0,
+                    "small_size": 8.0,
+                    "extra_line_height": 0.0,
+                    "fonts": {}
+                }} }}"#,
+                fonts,
+            )
+        };
+
+        assert!(matches!(
+            ElementValue::from_json(&rich_text("[]")),
+            Err(Error::Deserialize(_))
+        ));
+        assert!(matches!(
+            ElementValue::from_json(&rich_text(r#"[{ "font": "regular" }]"#)),
+            Ok(ElementValue::RichText(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_error() {
+        let image = r#"{ "Image": { "path": "does/not/exist.png" } }"#;
+        assert!(matches!(
+            ElementValue::from_json(image),
+            Err(Error::Image(_))
+        ));
+
+        let svg = r#"{ "Image": { "path": "does/not/exist.svg" } }"#;
+        assert!(matches!(ElementValue::from_json(svg), Err(Error::Io(_))));
+
+        // Errors that come from the JSON itself aren't mixed up with an earlier load error.
+        assert!(matches!(
+            ElementValue::from_json(r#"{ "Image": {} }"#),
+            Err(Error::Deserialize(_))
+        ));
+    }
+}
@@ -3,10 +3,20 @@ use std::ops::Index;
 use elements::rotate::Rotation;
 
 use crate::{
-    elements::{h_align::HorizontalAlignment, rich_text::Span, row::Flex, text::TextAlign},
+    elements::{
+        h_align::HorizontalAlignment,
+        rich_text::Span,
+        row::Flex,
+        table_of_contents::TocEntry,
+        text::{TextAlign, TextDirection},
+    },
+    fonts::family::{self, FontFamily, FontStretch, FontStyle, NORMAL_WEIGHT},
+    links::LinkTarget,
     *,
 };
 
+use serde::Deserializer;
+
 use super::{Font, SerdeElement, SerdeElementElement};
 
 const fn default_false() -> bool {
@@ -17,6 +27,18 @@ const fn default_0u8() -> u8 {
     0
 }
 
+const fn default_text_align_start() -> TextAlign {
+    TextAlign::Start
+}
+
+const fn default_text_direction_auto() -> TextDirection {
+    TextDirection::Auto
+}
+
+const fn default_normal_weight() -> u16 {
+    NORMAL_WEIGHT
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct None;
 
@@ -72,6 +94,9 @@ pub struct Text {
     pub extra_word_spacing: f64,
     pub extra_line_height: f64,
     pub align: TextAlign,
+
+    #[serde(default = "default_text_direction_auto")]
+    pub direction: TextDirection,
 }
 
 impl SerdeElement for Text {
@@ -90,6 +115,10 @@ impl SerdeElement for Text {
             extra_word_spacing: self.extra_word_spacing,
             extra_line_height: self.extra_line_height,
             align: self.align,
+            direction: self.direction,
+            // Hyphenation needs patterns loaded from a file, which a serialized element can't
+            // refer to.
+            hyphenation: Option::None,
         });
     }
 }
@@ -100,10 +129,44 @@ pub struct RichText {
     pub size: f64,
     pub small_size: f64,
     pub extra_line_height: f64,
-    pub regular: String,
-    pub bold: String,
-    pub italic: String,
-    pub bold_italic: String,
+
+    /// The faces of the font family the spans are resolved in. There has to be at least one.
+    #[serde(deserialize_with = "deserialize_font_faces")]
+    pub fonts: Vec<FontFace>,
+
+    #[serde(default = "default_text_align_start")]
+    pub align: TextAlign,
+
+    #[serde(default = "default_text_direction_auto")]
+    pub direction: TextDirection,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FontFace {
+    pub font: String,
+
+    #[serde(default = "default_normal_weight")]
+    pub weight: u16,
+
+    #[serde(default)]
+    pub style: FontStyle,
+
+    #[serde(default)]
+    pub stretch: FontStretch,
+}
+
+fn deserialize_font_faces<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<FontFace>, D::Error> {
+    let faces = Vec::<FontFace>::deserialize(deserializer)?;
+
+    if faces.is_empty() {
+        return Err(serde::de::Error::custom(
+            "a font family needs at least one face",
+        ));
+    }
+
+    Ok(faces)
 }
 
 impl SerdeElement for RichText {
@@ -117,12 +180,49 @@ impl SerdeElement for RichText {
             size: self.size,
             small_size: self.small_size,
             extra_line_height: self.extra_line_height,
-            fonts: FontSet {
-                regular: &*fonts[&self.regular],
-                bold: &*fonts[&self.bold],
-                italic: &*fonts[&self.italic],
-                bold_italic: &*fonts[&self.bold_italic],
-            },
+            fonts: FontFamily::new(
+                self.fonts
+                    .iter()
+                    .map(|face| family::FontFace {
+                        font: &*fonts[&face.font],
+                        weight: face.weight,
+                        style: face.style,
+                        stretch: face.stretch,
+                    })
+                    .collect(),
+            ),
+            align: self.align,
+            direction: self.direction,
+            // Hyphenation needs patterns loaded from a file, which a serialized element can't
+            // refer to.
+            hyphenation: Option::None,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TableOfContents {
+    pub entries: Vec<TocEntry>,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub indent: f64,
+    pub gap: f64,
+}
+
+impl SerdeElement for TableOfContents {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::table_of_contents::TableOfContents {
+            entries: &self.entries,
+            font: &*fonts[&self.font],
+            size: self.size,
+            color: self.color,
+            indent: self.indent,
+            gap: self.gap,
         });
     }
 }
@@ -194,6 +294,81 @@ impl<E: SerdeElement> SerdeElement for Padding<E> {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Link<E> {
+    pub target: LinkTarget,
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for Link<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::link::Link {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            target: self.target.clone(),
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Anchor<E> {
+    pub name: String,
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for Anchor<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::link::Anchor {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            name: &self.name,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark<E> {
+    pub title: String,
+
+    #[serde(default = "default_0u8")]
+    pub level: u8,
+
+    #[serde(default)]
+    pub name: Option<String>,
+
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for Bookmark<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::bookmark::Bookmark {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            title: &self.title,
+            level: self.level,
+            name: self.name.as_deref(),
+        });
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct StyledBox<E> {
     pub element: Box<E>,
@@ -247,6 +422,9 @@ impl SerdeElement for Line {
 pub struct Image {
     #[serde(rename = "path", deserialize_with = "crate::image::deserialize_image")]
     pub image: crate::image::Image,
+
+    #[serde(default)]
+    pub alt: Option<String>,
 }
 
 impl SerdeElement for Image {
@@ -255,7 +433,10 @@ impl SerdeElement for Image {
         _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::image::ImageElement { image: &self.image });
+        callback.call(&elements::image::ImageElement {
+            image: &self.image,
+            alt: self.alt.as_deref(),
+        });
     }
 }
 
@@ -0,0 +1,505 @@
+use std::{fmt, path::Path, time::SystemTime};
+
+use cms::{
+    builder::{SignedDataBuilder, SignerInfoBuilder},
+    cert::{CertificateChoices, IssuerAndSerialNumber},
+    signed_data::{EncapsulatedContentInfo, SignerIdentifier},
+};
+use lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+use p12_keystore::KeyStore;
+use rsa::{
+    pkcs1v15::{self, SigningKey},
+    pkcs8::DecodePrivateKey,
+    RsaPrivateKey,
+};
+use sha2::{Digest, Sha256};
+use x509_cert::{
+    der::{
+        oid::db::{rfc5911, rfc5912},
+        DateTime, Decode, Encode,
+    },
+    spki::AlgorithmIdentifierOwned,
+    Certificate,
+};
+
+use crate::{links::add_annotation, outline::text_string, Error, Result};
+
+/// The bytes reserved for the DER encoded signature. They are written as hex digits, so the
+/// document gets twice as many bytes.
+const SIGNATURE_CAPACITY: usize = 8192;
+
+/// Written instead of the byte range until the offsets are known. The offsets are written
+/// padded to the same length, so nothing else in the document moves.
+const BYTE_RANGE_PLACEHOLDER: [i64; 4] = [0, 9_999_999_999, 9_999_999_999, 9_999_999_999];
+
+/// Used for the invisible field when the signature isn't shown in a field of the layout.
+const INVISIBLE_FIELD_NAME: &str = "Signature";
+
+/// A private key and its certificate chain to sign documents with.
+#[derive(Clone)]
+pub struct Signer {
+    key: RsaPrivateKey,
+
+    /// The certificate of the key first, followed by the certificates of its issuers.
+    chain: Vec<Certificate>,
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Signer")
+            .field(
+                "subject",
+                &self.chain[0].tbs_certificate.subject.to_string(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer {
+    /// Loads the first private key of a PKCS#12 file (.p12 or .pfx) and its certificate chain.
+    /// Only RSA keys are supported.
+    pub fn from_pkcs12(data: &[u8], password: &str) -> Result<Self> {
+        let keystore = KeyStore::from_pkcs12(data, password)
+            .map_err(|error| Error::Signature(error.to_string()))?;
+
+        let (_, key_chain) = keystore
+            .private_key_chain()
+            .ok_or_else(|| Error::Signature("no private key with a certificate".to_string()))?;
+
+        let key = RsaPrivateKey::from_pkcs8_der(key_chain.key())
+            .map_err(|_| Error::Signature("only RSA keys are supported".to_string()))?;
+
+        let chain = key_chain
+            .chain()
+            .iter()
+            .map(|certificate| Certificate::from_der(certificate.as_der()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| Error::Signature(error.to_string()))?;
+
+        Ok(Signer { key, chain })
+    }
+
+    pub fn from_pkcs12_file(path: impl AsRef<Path>, password: &str) -> Result<Self> {
+        Self::from_pkcs12(&std::fs::read(path)?, password)
+    }
+
+    /// A detached CMS signature of the digest, with the certificate chain embedded. The signing
+    /// time is only part of the signature dictionary.
+    fn sign(&self, digest: &[u8]) -> Result<Vec<u8>> {
+        let error = |error: &dyn fmt::Display| Error::Signature(error.to_string());
+
+        let certificate = &self.chain[0];
+        let signing_key = SigningKey::<Sha256>::new(self.key.clone());
+
+        let content = EncapsulatedContentInfo {
+            econtent_type: rfc5911::ID_DATA,
+            econtent: None,
+        };
+
+        let digest_algorithm = AlgorithmIdentifierOwned {
+            oid: rfc5912::ID_SHA_256,
+            parameters: None,
+        };
+
+        let signer_info = SignerInfoBuilder::new(
+            &signing_key,
+            SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+                issuer: certificate.tbs_certificate.issuer.clone(),
+                serial_number: certificate.tbs_certificate.serial_number.clone(),
+            }),
+            digest_algorithm.clone(),
+            &content,
+            Some(digest),
+        )
+        .map_err(|e| error(&e))?;
+
+        let mut signed_data = SignedDataBuilder::new(&content);
+
+        signed_data
+            .add_digest_algorithm(digest_algorithm)
+            .map_err(|e| error(&e))?;
+
+        for certificate in &self.chain {
+            signed_data
+                .add_certificate(CertificateChoices::Certificate(certificate.clone()))
+                .map_err(|e| error(&e))?;
+        }
+
+        signed_data
+            .add_signer_info::<_, pkcs1v15::Signature>(signer_info)
+            .map_err(|e| error(&e))?
+            .build()
+            .map_err(|e| error(&e))?
+            .to_der()
+            .map_err(|e| error(&e))
+    }
+}
+
+/// Signs the document with a detached CMS signature, which readers check against the certificate
+/// chain of the signer.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    pub signer: Signer,
+
+    /// The name of a [crate::elements::form_fields::SignatureField] in the layout that shows the
+    /// signature. Without it the signature is added to an invisible field.
+    pub field: Option<String>,
+
+    /// The name of the person or authority signing, if it should differ from the certificate.
+    pub name: Option<String>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+    pub contact_info: Option<String>,
+}
+
+impl Signature {
+    pub fn new(signer: Signer) -> Self {
+        Signature {
+            signer,
+            field: None,
+            name: None,
+            reason: None,
+            location: None,
+            contact_info: None,
+        }
+    }
+
+    /// Adds the signature dictionary to the field, with room for the byte range and the signature
+    /// that [Signature::sign] fills in after the document was saved.
+    pub fn write(&self, doc: &mut Document, date: SystemTime) -> Result<()> {
+        let mut signature = Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"Sig".to_vec())),
+            ("Filter", Object::Name(b"Adobe.PPKLite".to_vec())),
+            ("SubFilter", Object::Name(b"adbe.pkcs7.detached".to_vec())),
+            (
+                "ByteRange",
+                Object::Array(
+                    BYTE_RANGE_PLACEHOLDER
+                        .iter()
+                        .map(|&offset| Object::Integer(offset))
+                        .collect(),
+                ),
+            ),
+            (
+                "Contents",
+                Object::String(vec![0; SIGNATURE_CAPACITY], StringFormat::Hexadecimal),
+            ),
+            ("M", Object::string_literal(pdf_date(date)?)),
+        ]);
+
+        let entries = [
+            ("Name", &self.name),
+            ("Reason", &self.reason),
+            ("Location", &self.location),
+            ("ContactInfo", &self.contact_info),
+        ];
+
+        for (key, value) in entries {
+            if let Some(value) = value {
+                signature.set(key, text_string(value));
+            }
+        }
+
+        let signature = doc.add_object(signature);
+        let form = acro_form(doc)?;
+
+        let field = match self.field {
+            Some(ref name) => find_field(doc, form, name)
+                .ok_or_else(|| Error::Signature(format!("no signature field named {}", name)))?,
+            None => add_invisible_field(doc, form)?,
+        };
+
+        if let Ok(Object::Dictionary(field)) = doc.get_object_mut(field) {
+            field.set("V", Object::Reference(signature));
+        }
+
+        if let Ok(Object::Dictionary(form)) = doc.get_object_mut(form) {
+            // The document contains signatures and must only be changed by appending to it.
+            form.set("SigFlags", Object::Integer(3));
+        }
+
+        Ok(())
+    }
+
+    /// Fills in the byte range and the signature of a document saved after [Signature::write].
+    /// The signature covers everything except for itself.
+    pub fn sign(&self, bytes: &mut [u8]) -> Result<()> {
+        let placeholder = format!(
+            "[{}]",
+            BYTE_RANGE_PLACEHOLDER
+                .map(|offset| offset.to_string())
+                .join(" "),
+        );
+
+        let contents = format!("<{}>", "0".repeat(SIGNATURE_CAPACITY * 2));
+
+        let (byte_range, start) = match (find(bytes, &placeholder), find(bytes, &contents)) {
+            (Some(byte_range), Some(start)) => (byte_range, start),
+            _ => {
+                return Err(Error::Signature(
+                    "the signature placeholder is missing".to_string(),
+                ))
+            }
+        };
+
+        let end = start + contents.len();
+
+        let offsets = format!("[0 {} {} {}", start, end, bytes.len() - end);
+        let offsets = format!("{:width$}]", offsets, width = placeholder.len() - 1);
+        bytes[byte_range..byte_range + placeholder.len()].copy_from_slice(offsets.as_bytes());
+
+        let digest = Sha256::new()
+            .chain_update(&bytes[..start])
+            .chain_update(&bytes[end..])
+            .finalize();
+
+        let signature = self.signer.sign(&digest)?;
+
+        if signature.len() > SIGNATURE_CAPACITY {
+            return Err(Error::Signature(format!(
+                "the signature needs {} bytes, but only {} are reserved",
+                signature.len(),
+                SIGNATURE_CAPACITY,
+            )));
+        }
+
+        let hex: String = signature
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        bytes[start + 1..start + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+
+        Ok(())
+    }
+}
+
+/// The interactive form of the document, which is added if there is none yet.
+fn acro_form(doc: &mut Document) -> Result<ObjectId> {
+    let catalog = doc.trailer.get(b"Root").and_then(Object::as_reference)?;
+
+    let form = match doc.get_dictionary(catalog)?.get(b"AcroForm") {
+        Ok(&Object::Reference(form)) => return Ok(form),
+        Ok(Object::Dictionary(form)) => form.clone(),
+        _ => Dictionary::from_iter(vec![("Fields", Object::Array(Vec::new()))]),
+    };
+
+    let form = doc.add_object(form);
+
+    if let Ok(Object::Dictionary(catalog)) = doc.get_object_mut(catalog) {
+        catalog.set("AcroForm", Object::Reference(form));
+    }
+
+    Ok(form)
+}
+
+fn find_field(doc: &Document, form: ObjectId, name: &str) -> Option<ObjectId> {
+    let name = text_string(name);
+    let name = name.as_str().ok()?;
+
+    doc.get_dictionary(form)
+        .and_then(|form| form.get(b"Fields"))
+        .and_then(Object::as_array)
+        .ok()?
+        .iter()
+        .filter_map(|field| field.as_reference().ok())
+        .find(|&id| {
+            doc.get_dictionary(id).map_or(false, |field| {
+                matches!(field.get(b"FT"), Ok(Object::Name(t)) if t == b"Sig")
+                    && field.get(b"T").and_then(Object::as_str).ok() == Some(name)
+            })
+        })
+}
+
+/// A signature field that isn't shown on the first page.
+fn add_invisible_field(doc: &mut Document, form: ObjectId) -> Result<ObjectId> {
+    let page = *doc
+        .get_pages()
+        .values()
+        .next()
+        .ok_or_else(|| Error::Signature("the document has no pages".to_string()))?;
+
+    let rect = Object::Array(vec![0.into(), 0.into(), 0.into(), 0.into()]);
+
+    let field = doc.add_object(Dictionary::from_iter(vec![
+        ("FT", Object::Name(b"Sig".to_vec())),
+        ("T", text_string(INVISIBLE_FIELD_NAME)),
+        ("Type", Object::Name(b"Annot".to_vec())),
+        ("Subtype", Object::Name(b"Widget".to_vec())),
+        ("Rect", rect),
+        ("P", Object::Reference(page)),
+        // Printed and locked.
+        ("F", Object::Integer(132)),
+    ]));
+
+    add_annotation(doc, page, field)?;
+
+    if let Ok(Object::Dictionary(form)) = doc.get_object_mut(form) {
+        match form.get_mut(b"Fields") {
+            Ok(Object::Array(fields)) => fields.push(Object::Reference(field)),
+            _ => form.set("Fields", vec![Object::Reference(field)]),
+        }
+    }
+
+    Ok(field)
+}
+
+fn find(bytes: &[u8], pattern: &str) -> Option<usize> {
+    bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern.as_bytes())
+}
+
+fn pdf_date(date: SystemTime) -> Result<String> {
+    let date =
+        DateTime::from_system_time(date).map_err(|error| Error::Signature(error.to_string()))?;
+
+    Ok(format!(
+        "D:{:04}{:02}{:02}{:02}{:02}{:02}Z",
+        date.year(),
+        date.month(),
+        date.day(),
+        date.hour(),
+        date.minutes(),
+        date.seconds(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use cms::{content_info::ContentInfo, signed_data::SignedData};
+    use rsa::{pkcs1v15::VerifyingKey, signature::Verifier, RsaPublicKey};
+    use x509_cert::der::{asn1::OctetString, SliceReader};
+
+    use super::*;
+    use crate::{
+        build_pdf,
+        elements::form_fields::{FieldStyle, SignatureField},
+        encryption::{Encryption, EncryptionMethod, Permissions},
+        fonts::builtin::BuiltinFont,
+        output::SaveOptions,
+    };
+
+    /// A self-signed certificate for "CN=laser-pdf test" made with openssl, with the password
+    /// "test".
+    const SIGNER: &[u8] = include_bytes!("test_utils/signer.p12");
+
+    #[test]
+    fn test_signer() {
+        assert!(Signer::from_pkcs12(SIGNER, "wrong").is_err());
+
+        let signer = Signer::from_pkcs12(SIGNER, "test").unwrap();
+        assert_eq!(signer.chain.len(), 1);
+    }
+
+    #[test]
+    fn test_sign() {
+        let signer = Signer::from_pkcs12(SIGNER, "test").unwrap();
+
+        let encryptions = [
+            None,
+            Some(Encryption {
+                method: EncryptionMethod::Aes256,
+                user_password: String::new(),
+                owner_password: None,
+                permissions: Permissions::default(),
+            }),
+        ];
+
+        for encryption in encryptions {
+            let pdf = build_pdf(
+                "test",
+                (100., 100.),
+                BuiltinFont::helvetica,
+                |font: &BuiltinFont| SignatureField {
+                    name: "signature",
+                    text: "laser-pdf test\n1970-01-01",
+                    font,
+                    size: 10.,
+                    color: 0x00_00_00_FF,
+                    height: 20.,
+                    style: FieldStyle::default(),
+                },
+            )
+            .unwrap();
+
+            let encrypted = encryption.is_some();
+
+            let bytes = pdf
+                .to_bytes_with_options(&SaveOptions {
+                    encryption,
+                    signature: Some(Signature {
+                        field: Some("signature".to_string()),
+                        reason: Some("Approval".to_string()),
+                        ..Signature::new(signer.clone())
+                    }),
+                    ..SaveOptions::reproducible(UNIX_EPOCH, "0123456789abcdef")
+                })
+                .unwrap();
+
+            let byte_range = find(&bytes, "/ByteRange[").unwrap() + "/ByteRange[".len();
+            let byte_range: Vec<usize> = String::from_utf8_lossy(&bytes[byte_range..])
+                .split(']')
+                .next()
+                .unwrap()
+                .split_whitespace()
+                .map(|offset| offset.parse().unwrap())
+                .collect();
+
+            let [start, end] = [byte_range[1], byte_range[2]];
+            assert_eq!(byte_range[0], 0);
+            assert_eq!(end + byte_range[3], bytes.len());
+            assert_eq!((bytes[start], bytes[end - 1]), (b'<', b'>'));
+
+            let contents: Vec<u8> = std::str::from_utf8(&bytes[start + 1..end - 1])
+                .unwrap()
+                .as_bytes()
+                .chunks(2)
+                .map(|hex| u8::from_str_radix(std::str::from_utf8(hex).unwrap(), 16).unwrap())
+                .collect();
+
+            // The signature is followed by the unused zeros of the placeholder.
+            let content_info =
+                ContentInfo::decode(&mut SliceReader::new(&contents).unwrap()).unwrap();
+            let signed_data: SignedData = content_info.content.decode_as().unwrap();
+            let signer_info = signed_data.signer_infos.0.get(0).unwrap();
+            let attributes = signer_info.signed_attrs.as_ref().unwrap();
+
+            let digest = attributes
+                .iter()
+                .find(|attribute| attribute.oid == rfc5911::ID_MESSAGE_DIGEST)
+                .and_then(|attribute| attribute.values.get(0))
+                .unwrap()
+                .decode_as::<OctetString>()
+                .unwrap();
+
+            let expected = Sha256::new()
+                .chain_update(&bytes[..start])
+                .chain_update(&bytes[end..])
+                .finalize();
+
+            assert_eq!(digest.as_bytes(), expected.as_slice());
+
+            VerifyingKey::<Sha256>::new(RsaPublicKey::from(&signer.key))
+                .verify(
+                    &attributes.to_der().unwrap(),
+                    &pkcs1v15::Signature::try_from(signer_info.signature.as_bytes()).unwrap(),
+                )
+                .unwrap();
+
+            if !encrypted {
+                let document = Document::load_mem(&bytes).unwrap();
+                let form = document
+                    .catalog()
+                    .and_then(|catalog| catalog.get(b"AcroForm"))
+                    .and_then(Object::as_reference)
+                    .unwrap();
+
+                let field = find_field(&document, form, "signature").unwrap();
+                let field = document.get_dictionary(field).unwrap();
+                assert!(field.get(b"V").and_then(Object::as_reference).is_ok());
+            }
+        }
+    }
+}
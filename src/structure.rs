@@ -0,0 +1,796 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use lopdf::{
+    content::{Content, Operation},
+    Dictionary, Document, Object, ObjectId,
+};
+use printpdf::PdfLayerReference;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    outline::text_string,
+    output::{catalog_mut, page_id, plain_content},
+    BreakableDraw, DrawCtx, ElementSize, Pdf, Result,
+};
+
+/// The kinds of structure elements, which tell assistive technology what the content is.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StructureType {
+    Document,
+
+    /// A [crate::elements::titled::Titled] with its title and content.
+    Section,
+
+    /// The title of a section. The level is the number of sections it's in.
+    Heading,
+
+    Paragraph,
+
+    /// Groups the table rows drawn in it into one table, for example with a
+    /// [crate::elements::tagged::Tagged] around a column of rows. Rows that aren't drawn in a
+    /// table get one of their own, which the rows directly after them share.
+    Table,
+    TableRow,
+    TableDataCell,
+    Figure,
+
+    /// A type that isn't part of the standard ones. It needs an entry in the role map of the
+    /// [Structure].
+    Custom(String),
+}
+
+impl StructureType {
+    fn tag(&self) -> &str {
+        match self {
+            StructureType::Document => "Document",
+            StructureType::Section => "Sect",
+            StructureType::Heading => "H",
+            StructureType::Paragraph => "P",
+            StructureType::Table => "Table",
+            StructureType::TableRow => "TR",
+            StructureType::TableDataCell => "TD",
+            StructureType::Figure => "Figure",
+            StructureType::Custom(name) => name,
+        }
+    }
+}
+
+enum Kid {
+    Element(usize),
+
+    /// A marked-content sequence on the page with the index.
+    Content {
+        page: usize,
+        mcid: i64,
+    },
+}
+
+struct StructureElement {
+    tag: String,
+    alt: Option<String>,
+    parent: usize,
+    kids: Vec<Kid>,
+}
+
+/// The structure tree of a tagged document, recorded while drawing. Elements that are tagged
+/// surround their content with marked-content sequences, which are connected to the structure
+/// elements when the document is saved. Tagging is off unless it's enabled before drawing, for
+/// example through [crate::sections::Sections::tagged].
+#[derive(Default)]
+pub struct Structure {
+    enabled: bool,
+
+    /// The first element is the document, which is the root of the tree.
+    elements: Vec<StructureElement>,
+
+    /// The open elements. The flag is set for tables that were opened for a row and have to be
+    /// closed with it.
+    stack: Vec<(usize, bool)>,
+
+    /// The last table that was added for rows outside of a table.
+    row_table: Option<usize>,
+
+    /// Set while drawing marked content or artifacts, which can't contain other structure.
+    marked: bool,
+
+    /// The structure element of each marked-content sequence of each page.
+    page_contents: Vec<Vec<usize>>,
+
+    /// Maps custom structure types to standard ones.
+    pub role_map: BTreeMap<String, String>,
+}
+
+impl Structure {
+    pub fn enable(&mut self) {
+        if self.enabled {
+            return;
+        }
+
+        self.enabled = true;
+        self.elements.push(StructureElement {
+            tag: StructureType::Document.tag().to_string(),
+            alt: None,
+            parent: 0,
+            kids: Vec::new(),
+        });
+        self.stack.push((0, false));
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn can_tag(&self) -> bool {
+        self.enabled && !self.marked
+    }
+
+    fn current(&self) -> usize {
+        self.stack.last().map_or(0, |&(element, _)| element)
+    }
+
+    fn add_element(&mut self, parent: usize, tag: String, alt: Option<&str>) -> usize {
+        let element = self.elements.len();
+
+        self.elements.push(StructureElement {
+            tag,
+            alt: alt.map(str::to_string),
+            parent,
+            kids: Vec::new(),
+        });
+        self.elements[parent].kids.push(Kid::Element(element));
+
+        element
+    }
+
+    /// Adds an element to the current one without opening it, so that content can be drawn into
+    /// it later while keeping its position in the reading order.
+    pub fn reserve(&mut self, structure_type: StructureType, alt: Option<&str>) -> Option<usize> {
+        if !self.can_tag() {
+            return None;
+        }
+
+        let tag = if structure_type == StructureType::Heading {
+            let level = self
+                .stack
+                .iter()
+                .filter(|&&(element, _)| self.elements[element].tag == "Sect")
+                .count();
+
+            format!("H{}", level.clamp(1, 6))
+        } else {
+            structure_type.tag().to_string()
+        };
+
+        Some(self.add_element(self.current(), tag, alt))
+    }
+
+    /// Removes a reserved element that didn't get any content.
+    pub fn remove(&mut self, element: usize) {
+        let parent = self.elements[element].parent;
+
+        self.elements[parent]
+            .kids
+            .retain(|kid| !matches!(*kid, Kid::Element(e) if e == element));
+    }
+
+    /// Opens an element that following elements and content are added to. Table rows outside of
+    /// a table are added to the table of the rows directly before them, if there are any.
+    fn begin(&mut self, structure_type: StructureType) {
+        let parent = self.current();
+
+        if structure_type == StructureType::TableRow && self.elements[parent].tag != "Table" {
+            let table = match (self.elements[parent].kids.last(), self.row_table) {
+                (Some(&Kid::Element(last)), Some(table)) if last == table => table,
+                _ => {
+                    let table =
+                        self.add_element(parent, StructureType::Table.tag().to_string(), None);
+                    self.row_table = Some(table);
+                    table
+                }
+            };
+
+            self.stack.push((table, true));
+        }
+
+        let element = self.add_element(self.current(), structure_type.tag().to_string(), None);
+        self.stack.push((element, false));
+    }
+
+    fn end(&mut self) {
+        self.stack.pop();
+
+        if let Some(&(_, true)) = self.stack.last() {
+            self.stack.pop();
+        }
+    }
+
+    fn begin_content(&mut self, layer: &PdfLayerReference) {
+        let element = self.current();
+        let page = layer.page.0;
+
+        if self.page_contents.len() <= page {
+            self.page_contents.resize(page + 1, Vec::new());
+        }
+
+        let mcid = self.page_contents[page].len() as i64;
+        self.page_contents[page].push(element);
+        self.elements[element]
+            .kids
+            .push(Kid::Content { page, mcid });
+
+        layer.add_op(Operation::new(
+            "BDC",
+            vec![
+                Object::Name(self.elements[element].tag.as_bytes().to_vec()),
+                Object::Dictionary(Dictionary::from_iter(vec![("MCID", Object::Integer(mcid))])),
+            ],
+        ));
+    }
+
+    /// Adds the structure tree to a saved document and marks it as tagged.
+    pub fn write(&self, doc: &mut Document) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+        let root_id = doc.new_object_id();
+
+        let empty = remove_empty_sequences(doc, &pages)?;
+
+        // Only the elements that are still part of the tree get written.
+        let mut ids: Vec<Option<ObjectId>> = vec![None; self.elements.len()];
+        let mut pending = vec![0];
+
+        while let Some(element) = pending.pop() {
+            ids[element] = Some(doc.new_object_id());
+
+            for kid in &self.elements[element].kids {
+                if let Kid::Element(kid) = *kid {
+                    pending.push(kid);
+                }
+            }
+        }
+
+        for (index, element) in self.elements.iter().enumerate() {
+            let id = match ids[index] {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let mut kids = Vec::new();
+
+            for kid in &element.kids {
+                match *kid {
+                    Kid::Element(kid) => kids.extend(ids[kid].map(Object::Reference)),
+                    Kid::Content { page, mcid } if empty.contains(&(page, mcid)) => (),
+                    Kid::Content { page, mcid } => {
+                        kids.push(Object::Dictionary(Dictionary::from_iter(vec![
+                            ("Type", Object::Name(b"MCR".to_vec())),
+                            ("Pg", Object::Reference(page_id(&pages, page)?)),
+                            ("MCID", Object::Integer(mcid)),
+                        ])))
+                    }
+                }
+            }
+
+            let parent = if index == 0 {
+                root_id
+            } else {
+                ids[element.parent].unwrap_or(root_id)
+            };
+
+            let mut dictionary = Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"StructElem".to_vec())),
+                ("S", Object::Name(element.tag.as_bytes().to_vec())),
+                ("P", Object::Reference(parent)),
+                ("K", Object::Array(kids)),
+            ]);
+
+            if let Some(ref alt) = element.alt {
+                dictionary.set("Alt", text_string(alt));
+            }
+
+            doc.objects.insert(id, Object::Dictionary(dictionary));
+        }
+
+        let mut nums = Vec::new();
+
+        for (page, elements) in self.page_contents.iter().enumerate() {
+            let id = page_id(&pages, page)?;
+
+            nums.push(Object::Integer(page as i64));
+            nums.push(Object::Array(
+                elements
+                    .iter()
+                    .enumerate()
+                    .map(|(mcid, &element)| match ids[element] {
+                        Some(id) if !empty.contains(&(page, mcid as i64)) => Object::Reference(id),
+                        _ => Object::Null,
+                    })
+                    .collect(),
+            ));
+
+            doc.get_object_mut(id)
+                .and_then(Object::as_dict_mut)?
+                .set("StructParents", Object::Integer(page as i64));
+        }
+
+        let mut root = Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"StructTreeRoot".to_vec())),
+            ("K", Object::Array(vec![Object::Reference(ids[0].unwrap())])),
+            (
+                "ParentTree",
+                Object::Dictionary(Dictionary::from_iter(vec![("Nums", Object::Array(nums))])),
+            ),
+            (
+                "ParentTreeNextKey",
+                Object::Integer(self.page_contents.len() as i64),
+            ),
+        ]);
+
+        if !self.role_map.is_empty() {
+            root.set(
+                "RoleMap",
+                Dictionary::from_iter(
+                    self.role_map
+                        .iter()
+                        .map(|(custom, standard)| {
+                            (custom.as_str(), Object::Name(standard.as_bytes().to_vec()))
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            );
+        }
+
+        doc.objects.insert(root_id, Object::Dictionary(root));
+
+        let catalog = catalog_mut(doc)?;
+        catalog.set("StructTreeRoot", Object::Reference(root_id));
+        catalog.set(
+            "MarkInfo",
+            Dictionary::from_iter(vec![("Marked", Object::Boolean(true))]),
+        );
+        catalog.set(
+            "ViewerPreferences",
+            Dictionary::from_iter(vec![("DisplayDocTitle", Object::Boolean(true))]),
+        );
+
+        Ok(())
+    }
+}
+
+/// Removes the marked-content sequences and artifacts that didn't get any content, which
+/// happens when the content breaks to a new location before drawing anything. Returns the
+/// removed sequences by page and MCID.
+fn remove_empty_sequences(
+    doc: &mut Document,
+    pages: &[ObjectId],
+) -> Result<BTreeSet<(usize, i64)>> {
+    let mut empty = BTreeSet::new();
+
+    for (index, &page) in pages.iter().enumerate() {
+        for id in doc.get_page_contents(page) {
+            let stream = doc.get_object_mut(id).and_then(Object::as_stream_mut)?;
+            let content = Content::decode(&plain_content(stream)?)?;
+
+            let mut operations: Vec<Operation> = Vec::with_capacity(content.operations.len());
+            let mut found = false;
+
+            for operation in content.operations {
+                if operation.operator == "EMC" {
+                    if let Some(begin) = operations.last().and_then(sequence_begin) {
+                        if let Some(mcid) = begin {
+                            empty.insert((index, mcid));
+                        }
+
+                        operations.pop();
+                        found = true;
+                        continue;
+                    }
+                }
+
+                operations.push(operation);
+            }
+
+            if found {
+                stream.set_plain_content((Content { operations }).encode()?);
+            }
+        }
+    }
+
+    Ok(empty)
+}
+
+/// If the operation begins a marked-content sequence of the structure or an artifact, the MCID of
+/// the sequence, which artifacts don't have.
+fn sequence_begin(operation: &Operation) -> Option<Option<i64>> {
+    match (operation.operator.as_str(), operation.operands.as_slice()) {
+        ("BMC", [Object::Name(tag)]) if tag == b"Artifact" => Some(None),
+        ("BDC", [Object::Name(_), Object::Dictionary(properties)]) => properties
+            .get(b"MCID")
+            .and_then(Object::as_i64)
+            .ok()
+            .map(Some),
+        _ => None,
+    }
+}
+
+/// Draws content as a new structure element of the type. The content is marked on every location
+/// it's drawn on and can't contain other structure elements.
+pub fn draw_tagged(
+    ctx: DrawCtx,
+    structure_type: StructureType,
+    alt: Option<&str>,
+    draw: impl FnOnce(DrawCtx) -> ElementSize,
+) -> ElementSize {
+    let element = ctx.pdf.structure.reserve(structure_type, alt);
+    draw_into(ctx, element, draw)
+}
+
+/// Draws content into an element from [Structure::reserve].
+pub fn draw_into(
+    ctx: DrawCtx,
+    element: Option<usize>,
+    draw: impl FnOnce(DrawCtx) -> ElementSize,
+) -> ElementSize {
+    let element = match element {
+        Some(element) if ctx.pdf.structure.can_tag() => element,
+        _ => return draw(ctx),
+    };
+
+    ctx.pdf.structure.stack.push((element, false));
+
+    let size = draw_marked(ctx, Structure::begin_content, draw);
+
+    ctx.pdf.structure.stack.pop();
+
+    size
+}
+
+/// Draws content that isn't part of the actual content of the document, like page numbers and
+/// decorative shapes, so that it's skipped by assistive technology.
+pub fn draw_artifact(ctx: DrawCtx, draw: impl FnOnce(DrawCtx) -> ElementSize) -> ElementSize {
+    if !ctx.pdf.structure.can_tag() {
+        return draw(ctx);
+    }
+
+    draw_marked(
+        ctx,
+        |_, layer| {
+            layer.add_op(Operation::new(
+                "BMC",
+                vec![Object::Name(b"Artifact".to_vec())],
+            ))
+        },
+        draw,
+    )
+}
+
+/// Draws content as a new structure element that contains the structure elements of the content.
+pub fn draw_grouped(
+    ctx: DrawCtx,
+    structure_type: StructureType,
+    draw: impl FnOnce(DrawCtx) -> ElementSize,
+) -> ElementSize {
+    if !ctx.pdf.structure.can_tag() {
+        return draw(ctx);
+    }
+
+    ctx.pdf.structure.begin(structure_type);
+
+    let size = draw(DrawCtx {
+        pdf: &mut *ctx.pdf,
+        ..ctx
+    });
+
+    ctx.pdf.structure.end();
+
+    size
+}
+
+/// Surrounds the content with a marked-content sequence on each location. Only breaks to new
+/// locations end the sequence, so content is expected to be drawn in order.
+fn draw_marked(
+    ctx: DrawCtx,
+    begin: impl Fn(&mut Structure, &PdfLayerReference),
+    draw: impl FnOnce(DrawCtx) -> ElementSize,
+) -> ElementSize {
+    begin(&mut ctx.pdf.structure, &ctx.location.layer);
+    ctx.pdf.structure.marked = true;
+
+    let mut current = (0, ctx.location.layer.clone());
+
+    let size = if let Some(breakable) = ctx.breakable {
+        draw(DrawCtx {
+            pdf: &mut *ctx.pdf,
+            location: ctx.location,
+            breakable: Some(BreakableDraw {
+                do_break: &mut |pdf: &mut Pdf, location_idx, height| {
+                    let location = (breakable.do_break)(pdf, location_idx, height);
+
+                    if location_idx + 1 > current.0 {
+                        end_marked(&current.1);
+                        begin(&mut pdf.structure, &location.layer);
+                        current = (location_idx + 1, location.layer.clone());
+                    }
+
+                    location
+                },
+                ..breakable
+            }),
+            ..ctx
+        })
+    } else {
+        draw(DrawCtx {
+            pdf: &mut *ctx.pdf,
+            ..ctx
+        })
+    };
+
+    end_marked(&current.1);
+    ctx.pdf.structure.marked = false;
+
+    size
+}
+
+fn end_marked(layer: &PdfLayerReference) {
+    layer.add_op(Operation::new("EMC", Vec::new()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_pdf_sections,
+        elements::{
+            column::Column, rectangle::Rectangle, svg::Svg, text::Text, titled::Titled, v_gap::VGap,
+        },
+        fonts::builtin::BuiltinFont,
+        image::parse_svg,
+        sections::{Orientation, Section},
+    };
+
+    #[test]
+    fn test_structure() {
+        let mut structure = Structure::default();
+
+        assert_eq!(structure.reserve(StructureType::Paragraph, None), None);
+
+        structure.enable();
+        structure.begin(StructureType::Section);
+
+        let heading = structure.reserve(StructureType::Heading, None).unwrap();
+        assert_eq!(structure.elements[heading].tag, "H1");
+
+        structure.begin(StructureType::TableRow);
+        structure.end();
+        structure.begin(StructureType::TableRow);
+        structure.end();
+        structure.end();
+
+        let section = &structure.elements[1];
+        assert_eq!(section.tag, "Sect");
+        assert_eq!(section.kids.len(), 2);
+
+        // Both rows are in the same table.
+        let table = match section.kids[1] {
+            Kid::Element(table) => &structure.elements[table],
+            _ => panic!("expected a table"),
+        };
+        assert_eq!(table.tag, "Table");
+        assert_eq!(table.kids.len(), 2);
+
+        structure.remove(heading);
+        assert_eq!(structure.elements[1].kids.len(), 1);
+        assert_eq!(structure.current(), 0);
+    }
+
+    #[test]
+    fn test_tables() {
+        let mut structure = Structure::default();
+        structure.enable();
+
+        let rows = |structure: &mut Structure, count| {
+            for _ in 0..count {
+                structure.begin(StructureType::TableRow);
+                structure.end();
+            }
+        };
+
+        rows(&mut structure, 1);
+
+        structure.begin(StructureType::Table);
+        rows(&mut structure, 2);
+        structure.end();
+
+        structure.begin(StructureType::Table);
+        rows(&mut structure, 1);
+        structure.end();
+
+        rows(&mut structure, 2);
+
+        // Each table gets its own element and rows after a table don't join it.
+        let tables: Vec<usize> = structure.elements[0]
+            .kids
+            .iter()
+            .map(|kid| match *kid {
+                Kid::Element(table) => {
+                    assert_eq!(structure.elements[table].tag, "Table");
+                    structure.elements[table].kids.len()
+                }
+                _ => panic!("expected a table"),
+            })
+            .collect();
+        assert_eq!(tables, [1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_tagged_document() {
+        let pdf = build_pdf_sections("test", BuiltinFont::helvetica, |sections, font| {
+            sections.tagged().add(&Section {
+                page_size: (100., 100.),
+                orientation: Orientation::Portrait,
+                restart_page_numbers: None,
+                element: &Titled {
+                    title: &Text::basic("Title", font, 12.),
+                    content: &Text::basic("Content", font, 12.),
+                    gap: 1.,
+                    collapse_on_empty_content: false,
+                },
+            });
+        })
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let tags = |element: &Dictionary| -> Vec<(String, Dictionary)> {
+            element
+                .get(b"K")
+                .and_then(Object::as_array)
+                .unwrap()
+                .iter()
+                .filter_map(|kid| kid.as_reference().ok())
+                .map(|id| {
+                    let kid = document.get_dictionary(id).unwrap();
+                    let tag = kid.get(b"S").and_then(Object::as_name_str).unwrap();
+                    (tag.to_string(), kid.clone())
+                })
+                .collect()
+        };
+
+        let root = document
+            .catalog()
+            .and_then(|catalog| catalog.get(b"StructTreeRoot"))
+            .and_then(Object::as_reference)
+            .and_then(|id| document.get_dictionary(id))
+            .unwrap();
+
+        let documents = tags(root);
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].0, "Document");
+
+        let sections = tags(&documents[0].1);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].0, "Sect");
+
+        let kids: Vec<String> = tags(&sections[0].1).into_iter().map(|kid| kid.0).collect();
+        assert_eq!(kids, ["H1", "P"]);
+
+        let page = *document.get_pages().values().next().unwrap();
+        let content = String::from_utf8(document.get_page_content(page).unwrap()).unwrap();
+        assert!(content.contains("/MCID 0") && content.contains("/MCID 1"));
+        assert!(!content.contains("/MCID 2"));
+    }
+
+    #[test]
+    fn test_empty_sequences() {
+        let pdf = build_pdf_sections("test", BuiltinFont::helvetica, |sections, font| {
+            sections.tagged().add(&Section {
+                page_size: (100., 100.),
+                orientation: Orientation::Portrait,
+                restart_page_numbers: None,
+                element: &Column {
+                    content: |content| {
+                        content
+                            .add(&VGap(95.))?
+                            .add(&Rectangle {
+                                size: (10., 10.),
+                                fill: Some(0x00_00_00_FF),
+                                outline: None,
+                            })?
+                            .add(&VGap(89.))?
+                            .add(&Text::basic("Content", font, 12.))?;
+
+                        None
+                    },
+                    gap: 0.,
+                    collapse: true,
+                },
+            });
+        })
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+        let pages: Vec<ObjectId> = document.get_pages().into_values().collect();
+        assert_eq!(pages.len(), 3);
+
+        let content = |page| String::from_utf8(document.get_page_content(page).unwrap()).unwrap();
+
+        // The rectangle and the text break to the next page before drawing anything.
+        assert!(!content(pages[0]).contains("BMC"));
+        assert!(content(pages[1]).contains("/Artifact BMC"));
+        assert!(!content(pages[1]).contains("BDC"));
+        assert!(content(pages[2]).contains("/MCID 0"));
+
+        let paragraph = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .find(|element| element.get(b"S").and_then(Object::as_name_str).ok() == Some("P"))
+            .unwrap();
+
+        let kids = paragraph.get(b"K").and_then(Object::as_array).unwrap();
+        assert_eq!(kids.len(), 1);
+
+        let kid = kids[0].as_dict().unwrap();
+        assert_eq!(
+            kid.get(b"Pg").and_then(Object::as_reference).unwrap(),
+            pages[2]
+        );
+    }
+
+    #[test]
+    fn test_svg_alt() {
+        let svg = parse_svg(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                <rect width="10" height="10"/>
+            </svg>"#,
+        )
+        .unwrap();
+
+        let pdf = build_pdf_sections("test", BuiltinFont::helvetica, |sections, _| {
+            sections.tagged().add(&Section {
+                page_size: (100., 100.),
+                orientation: Orientation::Portrait,
+                restart_page_numbers: None,
+                element: &Column {
+                    content: |content| {
+                        content
+                            .add(&Svg {
+                                data: &svg,
+                                alt: Some("A square"),
+                            })?
+                            .add(&Svg {
+                                data: &svg,
+                                alt: None,
+                            })?;
+
+                        None
+                    },
+                    gap: 0.,
+                    collapse: true,
+                },
+            });
+        })
+        .unwrap();
+
+        let document = lopdf::Document::load_mem(&pdf.to_bytes().unwrap()).unwrap();
+
+        let figures: Vec<&Dictionary> = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .filter(|element| {
+                element.get(b"S").and_then(Object::as_name_str).ok() == Some("Figure")
+            })
+            .collect();
+        assert_eq!(figures.len(), 1);
+        assert!(figures[0].has(b"Alt"));
+
+        // The graphic without a description is an artifact instead.
+        let page = *document.get_pages().values().next().unwrap();
+        let content = String::from_utf8(document.get_page_content(page).unwrap()).unwrap();
+        assert!(content.contains("/MCID 0") && content.contains("/Artifact BMC"));
+    }
+}
@@ -268,10 +268,7 @@ fn draw_element<E: Element>(
     let (doc, page, layer) = PdfDocument::new("test", Mm(page_size.0), Mm(page_size.1), "Layer 0");
     let mut page_idx = 0;
 
-    let mut pdf = Pdf {
-        document: doc,
-        page_size,
-    };
+    let mut pdf = Pdf::new(doc, page_size);
 
     let mut breaks = vec![];
 
@@ -1,4 +1,4 @@
-use std::{fs::File, io::BufWriter};
+use std::fs::File;
 
 use printpdf::{
     indices::{PdfLayerIndex, PdfPageIndex},
@@ -103,10 +103,7 @@ impl Doc {
             .with_mod_date(OffsetDateTime::unix_epoch())
             .with_metadata_date(OffsetDateTime::unix_epoch());
 
-        let pdf = Pdf {
-            document,
-            page_size: params.page_size,
-        };
+        let pdf = Pdf::new(document, params.page_size);
 
         Doc { params, pdf }
     }
@@ -370,13 +367,43 @@ pub fn test_element_bytes(params: TestElementParams, build_element: impl Fn(Call
         }
     }
 
-    let mut bytes = Vec::new();
+    draw_doc.pdf.to_bytes().unwrap()
+}
+
+/// The strings shown with `Tj` and `TJ` in a document that only uses builtin fonts, one per text
+/// operator, in the order they are drawn.
+pub fn shown_text(bytes: &[u8]) -> Vec<String> {
+    use lopdf::{content::Content, Object};
+
+    let document = lopdf::Document::load_mem(bytes).unwrap();
+    let mut shown = Vec::new();
+
+    for page in document.get_pages().into_values() {
+        let content = Content::decode(&document.get_page_content(page).unwrap()).unwrap();
 
-    draw_doc
-        .pdf
-        .document
-        .save(&mut BufWriter::new(&mut bytes))
-        .unwrap();
+        for operation in content.operations {
+            if operation.operator != "Tj" && operation.operator != "TJ" {
+                continue;
+            }
+
+            let strings = operation.operands.iter().flat_map(|operand| match operand {
+                Object::Array(elements) => elements.iter().collect(),
+                operand => vec![operand],
+            });
+
+            shown.push(
+                strings
+                    .filter_map(|string| match string {
+                        // The builtin fonts are written with the WinAnsiEncoding.
+                        Object::String(bytes, _) => {
+                            Some(lopdf::Document::decode_text(Some("WinAnsiEncoding"), bytes))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            );
+        }
+    }
 
-    bytes
+    shown
 }
@@ -1,4 +1,8 @@
-use crate::fonts::Font;
+use std::ops::Range;
+
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level};
+
+use crate::fonts::{Font, ShapedGlyph};
 
 /**
  * Calculates the width needed for a given string, font and size (in pt).
@@ -10,27 +14,98 @@ pub fn text_width(
     character_spacing: f64,
     word_spacing: f64,
 ) -> f64 {
-    use itertools::{Itertools, Position};
+    let text = remove_non_trailing_soft_hyphens(text);
 
     let scale = font.units_per_em() as f64;
     let character_spacing = character_spacing * scale / size;
     let word_spacing = word_spacing * scale / size;
-    let total_width = text
-        .chars()
-        .with_position()
-        .filter_map(|(p, ch)| {
-            if ch == '\u{00ad}' && !matches!(p, Position::Last | Position::Only) {
-                return None;
-            }
-
-            Some((ch, font.codepoint_h_metrics(ch as u32)))
-        })
-        .fold(0., |acc, (ch, h_metrics)| {
-            acc + h_metrics.advance_width as f64
+    let total_width = shape_line(font, &text, false)
+        .iter()
+        .fold(0., |acc, glyph| {
+            acc + glyph.x_advance
                 + character_spacing
-                + if ch == ' ' { word_spacing } else { 0. }
+                + if text[glyph.cluster..].starts_with(' ') {
+                    word_spacing
+                } else {
+                    0.
+                }
         });
-    total_width as f64 * size as f64 / scale
+    total_width * size / scale
+}
+
+/// Splits a line into runs with a single direction using the Unicode bidirectional algorithm. The
+/// runs are returned in visual order together with whether they're right-to-left.
+pub fn visual_runs(text: &str, rtl: bool) -> Vec<(Range<usize>, bool)> {
+    let level = if rtl { Level::rtl() } else { Level::ltr() };
+    let bidi_info = BidiInfo::new(text, Some(level));
+
+    if !rtl && !bidi_info.has_rtl() {
+        return vec![(0..text.len(), false)];
+    }
+
+    bidi_info
+        .paragraphs
+        .iter()
+        .flat_map(|paragraph| {
+            let (levels, runs) = bidi_info.visual_runs(paragraph, paragraph.range.clone());
+
+            runs.into_iter().map(move |run| {
+                let rtl = levels[run.start].is_rtl();
+                (run, rtl)
+            })
+        })
+        .collect()
+}
+
+/// Shapes a line with bidirectional reordering. The glyphs are in visual order and their clusters
+/// are relative to `text`.
+pub fn shape_line(font: &impl Font, text: &str, rtl: bool) -> Vec<ShapedGlyph> {
+    let mut glyphs = Vec::new();
+
+    for (run, run_rtl) in visual_runs(text, rtl) {
+        glyphs.extend(
+            font.shape(&text[run.clone()], run_rtl)
+                .into_iter()
+                .map(|glyph| ShapedGlyph {
+                    cluster: glyph.cluster + run.start,
+                    ..glyph
+                }),
+        );
+    }
+
+    glyphs
+}
+
+/// Determines the direction of the paragraph starting at the beginning of `text` from its first
+/// strong character (rule P2 of the bidirectional algorithm). Returns `None` if the paragraph
+/// doesn't contain any strong characters.
+pub fn paragraph_direction(text: &str) -> Option<bool> {
+    for c in text.chars() {
+        if is_mandatory_break(c) {
+            break;
+        }
+
+        match bidi_class(c) {
+            BidiClass::L => return Some(false),
+            BidiClass::R | BidiClass::AL => return Some(true),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Whether the text contains any strong right-to-left characters.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars()
+        .any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL))
+}
+
+/// Returns the byte offset of `part` in `text`, of which it has to be a subslice.
+pub fn subslice_offset(text: &str, part: &str) -> usize {
+    let offset = part.as_ptr() as usize - text.as_ptr() as usize;
+    debug_assert!(offset + part.len() <= text.len());
+    offset
 }
 
 pub fn remove_non_trailing_soft_hyphens(text: &str) -> String {
@@ -62,6 +137,16 @@ impl<'a, F: Fn(&str) -> f64> Iterator for BreakTextIntoLines<'a, F> {
     }
 }
 
+impl<'a, F: Fn(&str) -> f64 + Clone> BreakTextIntoLines<'a, F> {
+    /// Yields each line together with whether it ends a paragraph.
+    pub fn with_paragraph_ends(mut self) -> impl Iterator<Item = (&'a str, bool)> + Clone {
+        std::iter::from_fn(move || {
+            let line = self.line_generator.next(self.max_width, false)?;
+            Some((line, self.line_generator.paragraph_end()))
+        })
+    }
+}
+
 pub fn break_text_into_lines<'a, F: Fn(&str) -> f64>(
     text: &'a str,
     max_width: f64,
@@ -78,6 +163,48 @@ pub struct LineGenerator<'a, F: Fn(&str) -> f64> {
     text: Option<&'a str>,
     text_width: F,
     soft_hyphen_width: f64,
+    paragraph_end: bool,
+}
+
+const SOFT_HYPHEN: char = '\u{00ad}';
+
+fn is_mandatory_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{000B}' | '\u{000C}' | '\u{0085}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// A soft hyphen directly following whitespace or another soft hyphen doesn't provide a break
+/// opportunity. This way a run of soft hyphens can only break after the first one.
+fn soft_hyphen_allows_break(text: &str, index: usize) -> bool {
+    !matches!(
+        text[..index].chars().next_back(),
+        Some(c) if c == SOFT_HYPHEN || c.is_whitespace()
+    )
+}
+
+/// Returns the byte indices at which a new line can start. These are the break opportunities of
+/// the Unicode line breaking algorithm (UAX #14), plus one after every soft hyphen that allows a
+/// break. The end of the text is always included.
+fn break_opportunities(text: &str) -> impl Iterator<Item = usize> + '_ {
+    use itertools::Itertools;
+    use unicode_linebreak::linebreaks;
+
+    let soft_hyphens = text
+        .char_indices()
+        .filter(move |&(i, c)| c == SOFT_HYPHEN && soft_hyphen_allows_break(text, i))
+        .map(|(i, c)| i + c.len_utf8());
+
+    linebreaks(text)
+        .map(|(i, _)| i)
+        .filter(move |&i| {
+            i == text.len()
+                || !text[..i].ends_with(SOFT_HYPHEN)
+                || soft_hyphen_allows_break(text, i - SOFT_HYPHEN.len_utf8())
+        })
+        .merge(soft_hyphens)
+        .dedup()
 }
 
 impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
@@ -88,6 +215,7 @@ impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
             text: Some(text),
             text_width,
             soft_hyphen_width,
+            paragraph_end: false,
         }
     }
 
@@ -95,105 +223,94 @@ impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
         self.text.is_none()
     }
 
+    /// Whether the last line returned by [LineGenerator::next] ended a paragraph, meaning it was
+    /// followed by a mandatory break or the end of the text.
+    pub fn paragraph_end(&self) -> bool {
+        self.paragraph_end
+    }
+
+    /// Returns the next line that fits within `max_width`. If `incomplete` is true the line is
+    /// continuing after some other content, so even the first word may be moved to the next line.
+    /// In that case an empty line is returned and the generator will start with the same text on
+    /// the next call.
     pub fn next(&mut self, max_width: f64, incomplete: bool) -> Option<&'a str> {
-        if let Some(slice) = self.text {
-            let mut current_width = 0.0;
-            let mut last_break = 0;
-            let mut end_break = 0;
-            let mut not_start = incomplete;
-
-            let mut in_whitespace: Option<usize> = None;
-
-            for (i, c) in slice.char_indices() {
-                if c == '\n' {
-                    if in_whitespace == None {
-                        current_width += (self.text_width)(&slice[last_break..i]);
-                    }
-
-                    if current_width > max_width && not_start {
-                        self.text = Some(&slice[end_break..]);
-                        return Some(&slice[..last_break]);
-                    } else {
-                        self.text = Some(&slice[i + 1..]);
-                        return Some(&slice[..i]);
-                    }
-                } else if c.is_whitespace() {
-                    if in_whitespace == None {
-                        current_width += (self.text_width)(&slice[last_break..i]);
-                        in_whitespace = Some(i);
-                    }
-                } else if c == '\u{00ad}' && in_whitespace == None {
-                    let end = i + c.len_utf8();
-
-                    current_width += (self.text_width)(&slice[last_break..i]);
-
-                    // While we don't add the soft hyphen to `current_width` we
-                    // check here if the line would be too long with it such
-                    // that if the code doesn't return here, but returns later
-                    // we know that the line will produce will fit within the
-                    // max width.
-                    if not_start && current_width + self.soft_hyphen_width > max_width {
-                        self.text = Some(&slice[end_break..]);
-                        return Some(&slice[..last_break]);
-                    }
-
-                    last_break = end;
-                    end_break = end;
-
-                    in_whitespace = Some(end);
-                } else if (c == '-' || c == '\u{2010}') && in_whitespace == None {
-                    // \u{2010} is the Unicode hyphen
-
-                    let end = i + c.len_utf8();
-
-                    current_width += (self.text_width)(&slice[last_break..end]);
-
-                    if not_start && current_width > max_width {
-                        self.text = Some(&slice[end_break..]);
-                        return Some(&slice[..last_break]);
-                    }
-
-                    last_break = end;
-                    end_break = end;
-
-                    in_whitespace = Some(end);
+        let slice = self.text?;
+
+        if slice.is_empty() {
+            self.text = None;
+            self.paragraph_end = true;
+            return Some(slice);
+        }
+
+        // The width of `slice[..measured_to]`. A trailing soft hyphen is not included because it's
+        // only visible at the end of a line.
+        let mut current_width = 0.;
+        let mut measured_to = 0;
+
+        // The end of the line and the start of the next one for the last break opportunity at which
+        // the line still fit.
+        let mut last_fitting: Option<(usize, usize)> = None;
+
+        for next_start in break_opportunities(slice) {
+            let before = &slice[..next_start];
+
+            let mandatory = before.ends_with(is_mandatory_break);
+            let at_end = next_start == slice.len() && !mandatory;
+
+            let without_newline = if mandatory {
+                before
+                    .strip_suffix("\r\n")
+                    .or_else(|| before.strip_suffix(is_mandatory_break))
+                    .unwrap()
+            } else {
+                before
+            };
+
+            // Trailing whitespace doesn't count towards the width of a line.
+            let content_end = without_newline.trim_end().len().max(measured_to);
+            let content = &slice[measured_to..content_end];
+
+            let width = current_width
+                + (self.text_width)(content.strip_suffix(SOFT_HYPHEN).unwrap_or(content));
+
+            let fits = width
+                + if content.ends_with(SOFT_HYPHEN) {
+                    self.soft_hyphen_width
                 } else {
-                    if let Some(start_whitespace) = in_whitespace {
-                        in_whitespace = None;
-
-                        if current_width > max_width {
-                            return Some(
-                                &slice[..if !not_start {
-                                    self.text = Some(&slice[i..]);
-                                    start_whitespace
-                                } else {
-                                    self.text = Some(&slice[end_break..]);
-                                    last_break
-                                }],
-                            );
-                        }
-
-                        not_start = true;
-                        last_break = start_whitespace;
-                        end_break = i;
-                    }
+                    0.
                 }
-            }
+                <= max_width;
 
-            if in_whitespace == None {
-                current_width += (self.text_width)(&slice[last_break..]);
+            if !fits && (incomplete || last_fitting.is_some()) {
+                let (line_end, next_start) = last_fitting.unwrap_or((0, 0));
+                self.text = Some(&slice[next_start..]);
+                self.paragraph_end = false;
+                return Some(&slice[..line_end]);
             }
 
-            if current_width > max_width && not_start {
-                self.text = Some(&slice[end_break..]);
-                Some(&slice[..last_break])
-            } else {
+            if mandatory {
+                self.text = Some(&slice[next_start..]);
+                self.paragraph_end = true;
+                return Some(without_newline);
+            } else if at_end {
                 self.text = None;
-                Some(slice)
+                self.paragraph_end = true;
+                return Some(slice);
+            } else if !fits {
+                // Nothing fits and the line is empty so far, so we have to overflow.
+                self.text = Some(&slice[next_start..]);
+                self.paragraph_end = false;
+                return Some(&slice[..content_end]);
             }
-        } else {
-            None
+
+            current_width = width;
+            measured_to = content_end;
+            last_fitting = Some((content_end, next_start));
         }
+
+        self.text = None;
+        self.paragraph_end = true;
+        Some(slice)
     }
 }
 
@@ -374,10 +491,12 @@ mod tests {
         assert_eq!(generator.next(7., false), Some("word"));
         assert_eq!(generator.next(7., false), None);
 
+        // There's no break opportunity between two hyphens.
         let mut generator = LineGenerator::new("A‐very--long-word", len_without_soft_hyphens);
 
-        assert_eq!(generator.next(7., false), Some("A‐very-"));
-        assert_eq!(generator.next(7., false), Some("-long-"));
+        assert_eq!(generator.next(7., false), Some("A‐"));
+        assert_eq!(generator.next(7., false), Some("very--"));
+        assert_eq!(generator.next(7., false), Some("long-"));
         assert_eq!(generator.next(7., false), Some("word"));
         assert_eq!(generator.next(7., false), None);
     }
@@ -400,4 +519,111 @@ mod tests {
         assert_eq!(generator.next(5., false), Some("word"));
         assert_eq!(generator.next(5., false), None);
     }
+
+    fn char_count(s: &str) -> f64 {
+        s.chars().count() as f64
+    }
+
+    #[test]
+    fn test_ideographs() {
+        let mut generator = LineGenerator::new("日本語のテキスト", char_count);
+
+        assert_eq!(generator.next(3., false), Some("日本語"));
+        assert_eq!(generator.next(3., false), Some("のテキ"));
+        assert_eq!(generator.next(3., false), Some("スト"));
+        assert_eq!(generator.next(3., false), None);
+    }
+
+    #[test]
+    fn test_zero_width_space() {
+        let mut generator = LineGenerator::new("foo\u{200b}bar", char_count);
+
+        assert_eq!(generator.next(4., false), Some("foo\u{200b}"));
+        assert_eq!(generator.next(4., false), Some("bar"));
+        assert_eq!(generator.next(4., false), None);
+    }
+
+    #[test]
+    fn test_no_break_space() {
+        let mut generator = LineGenerator::new("a\u{00a0}b c", char_count);
+
+        assert_eq!(generator.next(3., false), Some("a\u{00a0}b"));
+        assert_eq!(generator.next(3., false), Some("c"));
+        assert_eq!(generator.next(3., false), None);
+    }
+
+    #[test]
+    fn test_dashes_and_slashes() {
+        let mut generator = LineGenerator::new("foo—bar", char_count);
+
+        assert_eq!(generator.next(4., false), Some("foo—"));
+        assert_eq!(generator.next(4., false), Some("bar"));
+        assert_eq!(generator.next(4., false), None);
+
+        let mut generator = LineGenerator::new("example.com/path/to", char_count);
+
+        assert_eq!(generator.next(12., false), Some("example.com/"));
+        assert_eq!(generator.next(12., false), Some("path/to"));
+        assert_eq!(generator.next(12., false), None);
+    }
+
+    #[test]
+    fn test_mandatory_breaks() {
+        let mut generator = LineGenerator::new("foo\r\nbar\u{2028}baz\n", char_count);
+
+        assert_eq!(generator.next(16., false), Some("foo"));
+        assert_eq!(generator.next(16., false), Some("bar"));
+        assert_eq!(generator.next(16., false), Some("baz"));
+        assert_eq!(generator.next(16., false), Some(""));
+        assert_eq!(generator.next(16., false), None);
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let mut generator = LineGenerator::new("sum dolor", char_count);
+
+        assert_eq!(generator.next(2., true), Some(""));
+        assert_eq!(generator.next(4., true), Some("sum"));
+        assert_eq!(generator.next(4., false), Some("dolor"));
+        assert_eq!(generator.next(4., false), None);
+    }
+
+    #[test]
+    fn test_paragraph_ends() {
+        let lines: Vec<_> = break_text_into_lines("foo bar baz\nqux", 7., char_count)
+            .with_paragraph_ends()
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![("foo bar", false), ("baz", true), ("qux", true)]
+        );
+    }
+
+    #[test]
+    fn test_visual_runs() {
+        let text = "abc \u{5d0}\u{5d1} def";
+
+        assert_eq!(visual_runs("abc def", false), vec![(0..7, false)]);
+
+        assert_eq!(
+            visual_runs(text, false),
+            vec![(0..4, false), (4..8, true), (8..12, false)]
+        );
+
+        assert_eq!(
+            visual_runs(text, true),
+            vec![(9..12, false), (3..9, true), (0..3, false)]
+        );
+    }
+
+    #[test]
+    fn test_paragraph_direction() {
+        assert_eq!(paragraph_direction("abc \u{5d0}"), Some(false));
+        assert_eq!(paragraph_direction("123 \u{5d0} abc"), Some(true));
+        assert_eq!(paragraph_direction("123\n\u{5d0}"), None);
+
+        assert!(has_rtl("abc \u{627}"));
+        assert!(!has_rtl("abc 123"));
+    }
 }